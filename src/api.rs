@@ -0,0 +1,390 @@
+use crate::config::{SparrowConfig, TimeSplit};
+use crate::consts::LBF_SAMPLE_CONFIG;
+use crate::optimizer::lbf::LBFBuilder;
+use crate::optimizer::{optimize_from_builder, optimize_with_report};
+use crate::optimizer::preprocess::lock_min_height_rotations;
+use crate::optimizer::report::RunReport;
+use crate::quantify::separation_penalty_factors;
+use crate::util::io::{
+    OutputPaths, read_item_separations, read_spp_instance_json, validate_instance, write_json,
+};
+use crate::util::listener::{DummySolListener, ReportMeta, ReportType, SolutionListener};
+use crate::util::metrics::{ItemSizeStats, SolutionSummary};
+use crate::util::terminator::{CombinedTerminator, Terminator, TimedTerminator};
+use crate::{EPOCH, InfeasibleOutput, SPOutput};
+use anyhow::{Context, Result, ensure};
+use jagua_rs::io::import::Importer;
+use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
+use jagua_rs::probs::spp::io::ext_repr::ExtSPInstance;
+use itertools::Itertools;
+use log::{Level, info, warn};
+use rand::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// Reads an instance from a JSON file, optimizes it and returns both the instance and the solution.
+/// If `svg_paths` are provided, the final solution is exported as SVG to [`OutputPaths::final_svg`].
+pub fn run_from_json(
+    path: &Path,
+    config: &SparrowConfig,
+    terminator: &impl Terminator,
+    svg_paths: Option<&OutputPaths>,
+) -> Result<SPOutput> {
+    let ext_instance = read_spp_instance_json(path)?;
+    let separations = read_item_separations(path)?;
+    info!(
+        "[API] loaded instance {} with #{} items",
+        ext_instance.name,
+        ext_instance.items.len()
+    );
+    run_with_item_separations(ext_instance, &separations, config, terminator, svg_paths)
+}
+
+/// Same as [`run_from_json`], but for an instance which is already read.
+pub fn run(
+    ext_instance: ExtSPInstance,
+    config: &SparrowConfig,
+    terminator: &impl Terminator,
+    svg_paths: Option<&OutputPaths>,
+) -> Result<SPOutput> {
+    run_with_item_separations(ext_instance, &HashMap::new(), config, terminator, svg_paths)
+}
+
+/// Same as [`run`], but items in `separations` (by id) require more clearance than the global
+/// [`SparrowConfig::min_item_separation`]. See [`read_item_separations`] and [`separation_penalty_factors`].
+pub fn run_with_item_separations(
+    ext_instance: ExtSPInstance,
+    separations: &HashMap<usize, f32>,
+    config: &SparrowConfig,
+    terminator: &impl Terminator,
+    svg_paths: Option<&OutputPaths>,
+) -> Result<SPOutput> {
+    let instance = import_instance(&ext_instance, config)?;
+    let (config, time_split) = &with_time_split(config, &instance);
+    let rng = config_rng(config);
+    let penalty_factors =
+        separation_penalty_factors(&instance, separations, config.min_item_separation);
+
+    let (solution, mut report) = match svg_paths {
+        Some(paths) => {
+            let mut svg_exporter = paths.svg_exporter(false, false);
+            let listener = &mut svg_exporter;
+            run_optimize(instance.clone(), rng, listener, terminator, config, penalty_factors)
+        }
+        None => {
+            let listener = &mut DummySolListener;
+            run_optimize(instance.clone(), rng, listener, terminator, config, penalty_factors)
+        }
+    };
+    report.time_split = *time_split;
+    log_run_summary(&solution, &report, &instance);
+
+    Ok(SPOutput {
+        instance: ext_instance,
+        solution: jagua_rs::probs::spp::io::export(&instance, &solution, *EPOCH),
+        best_infeasible: report.best_infeasible.map(|(solution, total_loss)| InfeasibleOutput {
+            solution: jagua_rs::probs::spp::io::export(&instance, &solution, *EPOCH),
+            total_loss,
+        }),
+    })
+}
+
+/// Runs `n_runs` independent optimizations of the same instance (each with a fresh seed) and keeps the densest solution.
+/// Runs are executed concurrently in batches, sized such that the workers of all concurrent runs
+/// do not exceed the available parallelism. A single line summarizing every run is logged.
+/// The time limits of the config apply to every run individually, `terminator` is shared by all of them
+/// and should not carry a deadline of its own (e.g. a ctrl-c flag).
+/// Items in `separations` (by id) require more clearance in every run, see [`run_with_item_separations`].
+/// If `svg_paths` are provided, only the best solution is exported as SVG to [`OutputPaths::final_svg`].
+pub fn run_best_of(
+    ext_instance: ExtSPInstance,
+    separations: &HashMap<usize, f32>,
+    config: &SparrowConfig,
+    terminator: &(impl Terminator + Sync),
+    n_runs: usize,
+    svg_paths: Option<&OutputPaths>,
+) -> Result<SPOutput> {
+    ensure!(n_runs > 0, "at least one run is required");
+    let instance = import_instance(&ext_instance, config)?;
+    let (config, time_split) = &with_time_split(config, &instance);
+    let mut rng = config_rng(config);
+    let seeds = (0..n_runs).map(|_| rng.next_u64()).collect_vec();
+    let penalty_factors =
+        separation_penalty_factors(&instance, separations, config.min_item_separation);
+
+    let batch_size = max_concurrent_runs(config);
+    info!("[BATCH] starting {n_runs} runs, {batch_size} at a time");
+
+    let mut best: Option<(SPSolution, RunReport, f32)> = None;
+    for (batch_idx, batch) in seeds.chunks(batch_size).enumerate() {
+        let runs: Vec<(SPSolution, RunReport)> = batch
+            .par_iter()
+            .map(|seed| {
+                let rng = Xoshiro256PlusPlus::seed_from_u64(*seed);
+                let listener = &mut DummySolListener;
+                let terminator = &run_terminator(config, terminator);
+                let factors = penalty_factors.clone();
+                run_optimize(instance.clone(), rng, listener, terminator, config, factors)
+            })
+            .collect();
+        for (i, (solution, report)) in runs.into_iter().enumerate() {
+            let density = solution.density(&instance);
+            info!(
+                "[BATCH] run {}/{} (seed: {}): width: {:.3}, density: {:.3}%",
+                batch_idx * batch_size + i + 1,
+                n_runs,
+                batch[i],
+                solution.strip_width(),
+                density * 100.0
+            );
+            if best.as_ref().is_none_or(|(_, _, best_density)| density > *best_density) {
+                best = Some((solution, report, density));
+            }
+        }
+    }
+    let (solution, mut report, density) = best.expect("at least one run should have finished");
+    info!("[BATCH] best run reached a density of {:.3}%", density * 100.0);
+    report.time_split = *time_split;
+    log_run_summary(&solution, &report, &instance);
+
+    if let Some(paths) = svg_paths {
+        let mut svg_exporter = paths.svg_exporter(false, false);
+        svg_exporter.report_with_meta(
+            ReportType::Final,
+            &solution,
+            &instance,
+            ReportMeta::new(&solution, &instance, 0.0),
+        );
+    }
+
+    Ok(SPOutput {
+        instance: ext_instance,
+        solution: jagua_rs::probs::spp::io::export(&instance, &solution, *EPOCH),
+        best_infeasible: report.best_infeasible.map(|(solution, total_loss)| InfeasibleOutput {
+            solution: jagua_rs::probs::spp::io::export(&instance, &solution, *EPOCH),
+            total_loss,
+        }),
+    })
+}
+
+pub(crate) fn import_instance(
+    ext_instance: &ExtSPInstance,
+    config: &SparrowConfig,
+) -> Result<SPInstance> {
+    config.validate()?;
+    let importer = Importer::new(
+        config.cde_config,
+        config.poly_simpl_tolerance,
+        config.min_item_separation,
+        config.narrow_concavity_cutoff_ratio,
+    );
+    let instance = jagua_rs::probs::spp::io::import(&importer, ext_instance)
+        .context("could not import instance")?;
+    validate_instance(&instance)?;
+    Ok(match config.lock_rotations {
+        true => lock_min_height_rotations(&instance),
+        false => instance,
+    })
+}
+
+/// Copy of the config with the time limits redistributed according to [`SparrowConfig::time_split`] (if set).
+/// The item size statistics of the imported instance are only computed here, once per instance.
+fn with_time_split(
+    config: &SparrowConfig,
+    instance: &SPInstance,
+) -> (SparrowConfig, Option<TimeSplit>) {
+    let mut config = *config;
+    let time_split = match config.time_split {
+        Some(_) => config.apply_time_split(&ItemSizeStats::of(instance)),
+        None => None,
+    };
+    (config, time_split)
+}
+
+fn log_run_summary(solution: &SPSolution, report: &RunReport, instance: &SPInstance) {
+    info!(
+        "[API] run finished with {}",
+        SolutionSummary::of(solution, instance)
+    );
+    if let Some(split) = &report.time_split {
+        info!("[API] time split of the run ({split})");
+    }
+}
+
+fn config_rng(config: &SparrowConfig) -> Xoshiro256PlusPlus {
+    match config.rng_seed {
+        Some(seed) => Xoshiro256PlusPlus::seed_from_u64(seed as u64),
+        None => Xoshiro256PlusPlus::from_os_rng(),
+    }
+}
+
+fn run_optimize(
+    instance: SPInstance,
+    mut rng: Xoshiro256PlusPlus,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    config: &SparrowConfig,
+    penalty_factors: Vec<f32>,
+) -> (SPSolution, RunReport) {
+    if penalty_factors.is_empty() {
+        return optimize_with_report(
+            instance,
+            rng,
+            sol_listener,
+            terminator,
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+        );
+    }
+    //the penalty factors are passed to the optimizer through the initial solution's builder
+    let lbf_rng = Xoshiro256PlusPlus::seed_from_u64(rng.next_u64());
+    let builder = LBFBuilder::new(instance, lbf_rng, LBF_SAMPLE_CONFIG)
+        .with_fit_strip_slack(config.expl_cfg.fit_strip_slack)
+        .construct()
+        .with_penalty_factors(penalty_factors);
+    optimize_from_builder(
+        builder,
+        rng,
+        sol_listener,
+        terminator,
+        &config.expl_cfg,
+        &config.cmpr_cfg,
+    )
+}
+
+/// Number of optimizations which can run concurrently without the separator workers of all of them
+/// exceeding the available parallelism. Each separator runs its workers on a dedicated thread pool,
+/// the calling threads of the runs only block while their pools are busy.
+fn max_concurrent_runs(config: &SparrowConfig) -> usize {
+    let n_workers = usize::max(
+        config.expl_cfg.separator_config.n_workers,
+        config.cmpr_cfg.separator_config.n_workers,
+    );
+    let n_threads = thread::available_parallelism().map_or(1, |n| n.get());
+    usize::max(1, n_threads / n_workers.max(1))
+}
+
+/// Terminator for a single optimization within a batch: its own time limit (the combined time limits of both phases
+/// of the config), starting when it is created, or the shared `terminator` (e.g. ctrl-c), whichever comes first.
+fn run_terminator<T: Terminator>(
+    config: &SparrowConfig,
+    terminator: &T,
+) -> CombinedTerminator<T, TimedTerminator> {
+    let time_limit = config.expl_cfg.time_limit + config.cmpr_cfg.time_limit;
+    CombinedTerminator::new(terminator.clone(), TimedTerminator::new_duration(time_limit))
+}
+
+/// Outcome of solving a single instance of a batch, see [`run_from_dir`]
+pub struct BatchEntry {
+    pub path: PathBuf,
+    pub result: Result<SPOutput>,
+}
+
+/// Solves every `.json` instance in `input_dir`, writing the final solution of each (JSON and SVG) to `output_dir`,
+/// named after the instance file. The time limits of the config apply to every instance individually,
+/// `terminator` is shared by all of them and should not carry a deadline of its own (e.g. a ctrl-c flag).
+/// If `parallel` is set, the instances are solved concurrently in batches, sized such that the workers of all
+/// concurrent runs do not exceed the available parallelism.
+pub fn run_from_dir(
+    input_dir: &Path,
+    output_dir: &str,
+    config: &SparrowConfig,
+    terminator: &(impl Terminator + Sync),
+    parallel: bool,
+) -> Result<Vec<BatchEntry>> {
+    let paths = fs::read_dir(input_dir)
+        .context("could not read input directory")?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .sorted()
+        .collect_vec();
+    info!(
+        "[BATCH] solving {} instances from {}",
+        paths.len(),
+        input_dir.display()
+    );
+
+    let solve = |path: &PathBuf| {
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("unnamed");
+        let output_paths = OutputPaths::new(output_dir, name);
+        let terminator = &run_terminator(config, terminator);
+        let result = run_from_json(path, config, terminator, Some(&output_paths)).and_then(|output| {
+            write_json(&output, Path::new(&output_paths.final_json), Level::Info)?;
+            Ok(output)
+        });
+        if let Err(e) = &result {
+            warn!("[BATCH] failed to solve {}: {e:#}", path.display());
+        }
+        BatchEntry {
+            path: path.clone(),
+            result,
+        }
+    };
+
+    let entries = match parallel {
+        true => {
+            let batch_size = max_concurrent_runs(config);
+            info!("[BATCH] solving {batch_size} instances at a time");
+            paths
+                .chunks(batch_size)
+                .flat_map(|batch| batch.par_iter().map(solve).collect_vec())
+                .collect()
+        }
+        false => paths.iter().map(solve).collect(),
+    };
+
+    Ok(entries)
+}
+
+/// Formats the outcome of a batch as a table
+pub struct BatchSummaryTable<'a>(pub &'a [BatchEntry]);
+
+impl Display for BatchSummaryTable<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<30} | {:>12} | {:>8} | status", "instance", "width", "density")?;
+        for entry in self.0 {
+            let name = entry
+                .path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("?");
+            match &entry.result {
+                Ok(output) => writeln!(
+                    f,
+                    "{:<30} | {:>12.3} | {:>7.3}% | ok",
+                    name,
+                    output.solution.strip_width,
+                    output.solution.density * 100.0
+                )?,
+                Err(e) => writeln!(f, "{:<30} | {:>12} | {:>8} | failed: {e}", name, "-", "-")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_SPARROW_CONFIG;
+
+    #[test]
+    fn concurrent_runs_do_not_oversubscribe_the_cores() {
+        let n_threads = thread::available_parallelism().map_or(1, |n| n.get());
+        for n_workers in [1, 2, 3, n_threads] {
+            let mut config = DEFAULT_SPARROW_CONFIG;
+            config.expl_cfg.separator_config.n_workers = n_workers;
+            config.cmpr_cfg.separator_config.n_workers = 1;
+            let batch_size = max_concurrent_runs(&config);
+            assert!(batch_size >= 1);
+            if n_workers <= n_threads {
+                assert!(batch_size * n_workers <= n_threads);
+            }
+        }
+    }
+}
@@ -1,10 +1,26 @@
-use crate::optimizer::separator::SeparatorConfig;
+use crate::consts::{
+    DEFAULT_EXPLORE_TIME_RATIO, OVERLAP_PROXY_EPSILON_DIAM_RATIO, TIME_SPLIT_CV_SENSITIVITY,
+    TIME_SPLIT_EXPLORE_RATIO_RANGE,
+};
+use crate::optimizer::separator::{SeparatorConfig, SplitStrategy, WorkerSeeding};
+use crate::quantify::PenaltyKind;
 use crate::sample::search::SampleConfig;
+use crate::util::metrics::{ItemSizeStats, lower_bound_width};
 use jagua_rs::collision_detection::CDEConfig;
 use jagua_rs::geometry::fail_fast::SPSurrogateConfig;
+use jagua_rs::probs::spp::entities::SPInstance;
+use anyhow::{Context, Result, ensure};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
 use std::time::Duration;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SparrowConfig {
     pub rng_seed: Option<usize>,
     pub expl_cfg: ExplorationConfig,
@@ -24,40 +40,467 @@ pub struct SparrowConfig {
     /// Disabled if `None`.
     /// See [`jagua_rs::io::parser::Parser::new`] for more details.
     pub narrow_concavity_cutoff_ratio: Option<f32>,
+    /// Fixes the orientation of every item before optimizing, to the rotation minimizing its bounding box height.
+    /// See [`crate::optimizer::preprocess::lock_min_height_rotations`].
+    pub lock_rotations: bool,
+    /// Redistributes the combined time limit of both phases over exploration and compression once the instance is imported.
+    /// Disabled if `None`, in which case the time limits of both phase configs are used as is.
+    /// See [`SparrowConfig::apply_time_split`].
+    pub time_split: Option<TimeSplitStrategy>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ExplorationConfig {
+    /// If disabled, the exploration phase is skipped and its time limit is added to the compression phase.
+    /// Compression then starts from the warm start solution (see [`crate::optimizer::optimize_warm_start`]),
+    /// or from a constructed one if there is none.
+    pub enabled: bool,
     pub shrink_step: f32,
+    /// Whether the shrink step remains fixed or adapts to the recent success rate
+    pub shrink_step_strategy: ShrinkStepStrategy,
+    /// Where the strip is split when it is shrunk
+    pub split_strategy: SplitStrategy,
+    #[serde(with = "crate::util::serde_adapters::duration_secs")]
     pub time_limit: Duration,
     pub max_conseq_failed_attempts: Option<usize>,
+    /// Exploration stops once the best feasible width has not improved for this long,
+    /// regardless of the failed attempts at the current width. Disabled if `None`.
+    #[serde(with = "crate::util::serde_adapters::opt_duration_secs")]
+    pub stagnation_timeout: Option<Duration>,
     pub solution_pool_distribution_stddev: f32,
     pub separator_config: SeparatorConfig,
     pub large_item_ch_area_cutoff_percentile: f32,
+    /// Ratio by which the strip of the initial (LBF) solution is widened beyond the tightest fit.
+    /// Gives the exploration phase some room to start from. 0.0 keeps the tightest fit.
+    pub fit_strip_slack: f32,
+    /// Width below which the strip is not shrunk any further, exploration stops once it is reached.
+    /// If `None`, the provable lower bound of the instance is used, see [`ExplorationConfig::min_feasible_width`].
+    pub min_width: Option<f32>,
+    /// Intensity of the disruptions over the course of the exploration phase
+    pub disruption_schedule: DisruptionSchedule,
+    /// How the solution is disrupted when exploration gets stuck
+    pub disruption_strategy: DisruptionStrategy,
+    /// Number of independent explorations (each from a newly constructed solution), the best one is compressed.
+    /// The time limit is divided equally among them.
+    pub n_restarts: usize,
+    /// Also keeps the infeasible solution with the lowest total loss at the narrowest attempted width,
+    /// see [`crate::optimizer::report::RunReport::best_infeasible`]. It may contain overlaps.
+    pub keep_best_infeasible: bool,
+}
+
+impl ExplorationConfig {
+    /// The width below which exploration does not shrink the strip: the `min_width` override if set,
+    /// otherwise the area-based lower bound of the instance (see [`lower_bound_width`]).
+    pub fn min_feasible_width(&self, instance: &SPInstance) -> f32 {
+        self.min_width.unwrap_or_else(|| lower_bound_width(instance))
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CompressionConfig {
+    /// If disabled, the compression phase is skipped and its time limit is added to the exploration phase
+    pub enabled: bool,
     pub shrink_range: (f32, f32),
+    #[serde(with = "crate::util::serde_adapters::duration_secs")]
     pub time_limit: Duration,
     pub shrink_decay: ShrinkDecayStrategy,
+    /// Where the strip is split when it is shrunk
+    pub split_strategy: SplitStrategy,
+    /// What the compression phase minimizes, see [`Objective`]
+    pub objective: Objective,
     pub separator_config: SeparatorConfig,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum ShrinkDecayStrategy {
     /// The shrink ratio decays linearly with time
     TimeBased,
     /// The shrink ratio decays by a fixed ratio every time it fails to compress into a feasible solution
     FailureBased(f32),
+    /// The shrink ratio grows by `grow` after every successful compression (up to `max`)
+    /// and decays by `shrink` after every failed one. Compression stops once the ratio drops below `min`.
+    Adaptive {
+        grow: f32,
+        shrink: f32,
+        min: f32,
+        max: f32,
+    },
+}
+
+impl ShrinkDecayStrategy {
+    /// Fails if the bounds of the adaptive strategy are empty or not positive
+    pub fn validate(&self) -> Result<()> {
+        if let ShrinkDecayStrategy::Adaptive { min, max, .. } = *self {
+            ensure!(
+                min > 0.0 && min <= max,
+                "adaptive shrink decay requires 0.0 < min <= max, got min {min} and max {max}"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Determines how the strip is shrunk after every feasible solution in the exploration phase
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ShrinkStepStrategy {
+    /// Always shrink by [`ExplorationConfig::shrink_step`]
+    Fixed,
+    /// Start from [`ExplorationConfig::shrink_step`], grow it by `grow` when most of the last `window` attempts
+    /// reached feasibility and decay it by `shrink` when most failed, clamped to `[min, max]`.
+    Adaptive {
+        window: usize,
+        grow: f32,
+        shrink: f32,
+        min: f32,
+        max: f32,
+    },
+}
+
+impl ShrinkStepStrategy {
+    /// Fails if the bounds of the adaptive strategy are empty or not positive, or its window is empty
+    pub fn validate(&self) -> Result<()> {
+        if let ShrinkStepStrategy::Adaptive {
+            window, min, max, ..
+        } = *self
+        {
+            ensure!(
+                min > 0.0 && min <= max,
+                "adaptive shrink step requires 0.0 < min <= max, got min {min} and max {max}"
+            );
+            ensure!(window > 0, "adaptive shrink step requires a non-empty window");
+        }
+        Ok(())
+    }
+}
+
+/// Parameters determining the intensity of a single disruption in the exploration phase
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DisruptionParams {
+    /// Number of swaps of two large items
+    pub n_swaps: usize,
+    /// Items within this top percentile of the total convex hull area are considered 'large'
+    pub large_item_ch_area_cutoff_percentile: f32,
+}
+
+/// Schedule of the disruption intensity over the exploration phase.
+/// Large disruptions early on aid diversification, small disruptions near the end aid intensification.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum DisruptionSchedule {
+    /// A single swap with [`ExplorationConfig::large_item_ch_area_cutoff_percentile`] throughout the whole phase
+    Constant,
+    /// Linearly interpolates between `start` and `end` as the time limit of the phase is consumed
+    Linear {
+        start: DisruptionParams,
+        end: DisruptionParams,
+    },
+}
+
+impl DisruptionSchedule {
+    /// Disruption parameters after `elapsed_fraction` (in [0, 1]) of the exploration time limit has passed
+    pub fn params(&self, elapsed_fraction: f32, config: &ExplorationConfig) -> DisruptionParams {
+        match self {
+            DisruptionSchedule::Constant => DisruptionParams {
+                n_swaps: 1,
+                large_item_ch_area_cutoff_percentile: config.large_item_ch_area_cutoff_percentile,
+            },
+            DisruptionSchedule::Linear { start, end } => {
+                let t = elapsed_fraction.clamp(0.0, 1.0);
+                let lerp = |a: f32, b: f32| a + (b - a) * t;
+                DisruptionParams {
+                    n_swaps: lerp(start.n_swaps as f32, end.n_swaps as f32).round() as usize,
+                    large_item_ch_area_cutoff_percentile: lerp(
+                        start.large_item_ch_area_cutoff_percentile,
+                        end.large_item_ch_area_cutoff_percentile,
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// Objective minimized by the compression phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Objective {
+    /// Minimize the width of the strip, at the fixed strip height
+    MinWidth,
+    /// Minimize the area of the bounding box of all items (open dimension problem).
+    /// After every feasible compression of the width, the height is compressed as well by symmetry:
+    /// the problem is rotated by 90°, its width is compressed and the result is rotated back.
+    /// This requires the container to be rotatable: the strip height acts as an upper bound on the height of the
+    /// bounding box, and the allowed rotations of the items are preserved relative to the original strip.
+    /// Height compression is skipped if items are locked in place.
+    MinBBoxArea,
+}
+
+/// Determines how a solution is disrupted in the exploration phase
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum DisruptionStrategy {
+    /// Swaps two large items and relocates the items they practically contain
+    Swap,
+    /// Teleports a random small item into the largest empty rectangle of the layout
+    GapFill,
+}
+
+/// Strategy to divide a global time budget between the exploration and compression phase.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeSplitStrategy {
+    /// A fixed ratio of the time budget is spent exploring, the remainder is spent compressing
+    Fixed(f32),
+    /// The ratio is derived from the item size distribution of the instance, see [`ItemSizeStats`].
+    /// Instances with many small items (a wide spread in item areas) gain more from compressing the details of the layout.
+    /// Instances with a few large, similarly sized items are harder to arrange globally and get more exploration time.
+    /// The coefficient of variation (CV) of the item areas (weighted by demand) drives the split:
+    /// a CV of 1.0 results in the default 80/20 split, every unit of CV above (below) 1.0 shifts
+    /// [`TIME_SPLIT_CV_SENSITIVITY`] of the budget toward compression (exploration),
+    /// clamped to [`TIME_SPLIT_EXPLORE_RATIO_RANGE`].
+    ByDifficulty,
+}
+
+impl TimeSplitStrategy {
+    /// Returns the ratio of the time budget to be spent in the exploration phase
+    pub fn explore_ratio(&self, stats: &ItemSizeStats) -> f32 {
+        match self {
+            TimeSplitStrategy::Fixed(ratio) => *ratio,
+            TimeSplitStrategy::ByDifficulty => {
+                let (min, max) = TIME_SPLIT_EXPLORE_RATIO_RANGE;
+                let shift = TIME_SPLIT_CV_SENSITIVITY * (stats.area_cv - 1.0);
+                (DEFAULT_EXPLORE_TIME_RATIO - shift).clamp(min, max)
+            }
+        }
+    }
+}
+
+/// Division of a global time budget between both phases, see [`SparrowConfig::split_time_limit`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeSplit {
+    pub strategy: TimeSplitStrategy,
+    pub explore_ratio: f32,
+    pub explore_time: Duration,
+    pub compress_time: Duration,
+}
+
+impl Display for TimeSplit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?}: {:.0}% exploration ({:.1}s), {:.0}% compression ({:.1}s)",
+            self.strategy,
+            self.explore_ratio * 100.0,
+            self.explore_time.as_secs_f32(),
+            (1.0 - self.explore_ratio) * 100.0,
+            self.compress_time.as_secs_f32()
+        )
+    }
+}
+
+impl Default for TimeSplitStrategy {
+    fn default() -> Self {
+        TimeSplitStrategy::Fixed(DEFAULT_EXPLORE_TIME_RATIO)
+    }
+}
+
+impl Default for SparrowConfig {
+    fn default() -> Self {
+        DEFAULT_SPARROW_CONFIG
+    }
+}
+
+impl Default for ExplorationConfig {
+    fn default() -> Self {
+        DEFAULT_SPARROW_CONFIG.expl_cfg
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        DEFAULT_SPARROW_CONFIG.cmpr_cfg
+    }
+}
+
+impl SparrowConfig {
+    /// Checks the consistency of the config, on top of what is enforced by its types.
+    /// Called whenever a config is loaded or used to import an instance.
+    pub fn validate(&self) -> Result<()> {
+        self.expl_cfg
+            .shrink_step_strategy
+            .validate()
+            .context("invalid exploration config")?;
+        self.expl_cfg
+            .separator_config
+            .sample_config
+            .validate()
+            .context("invalid exploration config")?;
+        self.cmpr_cfg
+            .shrink_decay
+            .validate()
+            .context("invalid compression config")?;
+        self.cmpr_cfg
+            .separator_config
+            .sample_config
+            .validate()
+            .context("invalid compression config")?;
+        Ok(())
+    }
+
+    /// Overrides the number of separator workers of both the exploration and compression phase.
+    /// Warns if it exceeds the available parallelism of the machine.
+    pub fn set_n_workers(&mut self, n_workers: usize) {
+        assert!(n_workers >= 1, "at least one worker is required");
+        if let Ok(available) = std::thread::available_parallelism()
+            && n_workers > available.get()
+        {
+            warn!(
+                "[CFG] #workers ({}) exceeds the available parallelism ({})",
+                n_workers, available
+            );
+        }
+        self.expl_cfg.separator_config.n_workers = n_workers;
+        self.cmpr_cfg.separator_config.n_workers = n_workers;
+        info!("[CFG] #workers set to {}", n_workers);
+    }
+
+    /// Overrides the epsilon ratio of the overlap proxy of both the exploration and compression phase,
+    /// see [`SeparatorConfig::overlap_proxy_epsilon_ratio`].
+    pub fn set_overlap_proxy_epsilon_ratio(&mut self, ratio: f32) {
+        assert!(ratio > 0.0, "epsilon ratio of the overlap proxy should be positive");
+        self.expl_cfg.separator_config.overlap_proxy_epsilon_ratio = ratio;
+        self.cmpr_cfg.separator_config.overlap_proxy_epsilon_ratio = ratio;
+    }
+
+    /// Overrides the penalty kind of both the exploration and compression phase,
+    /// see [`SeparatorConfig::penalty_kind`].
+    pub fn set_penalty_kind(&mut self, penalty_kind: PenaltyKind) {
+        self.expl_cfg.separator_config.penalty_kind = penalty_kind;
+        self.cmpr_cfg.separator_config.penalty_kind = penalty_kind;
+    }
+
+    /// Loads a config from a JSON file, containing overrides of the default config.
+    /// Any field missing from the file (at any depth) falls back to its value in [`DEFAULT_SPARROW_CONFIG`].
+    pub fn from_json_file(path: &Path) -> Result<Self> {
+        let file = File::open(path).context("could not open config file")?;
+        let overrides: Value =
+            serde_json::from_reader(BufReader::new(file)).context("config file is not valid JSON")?;
+
+        //merge the overrides into the default config, so partially specified nested configs are completed as well
+        let mut merged = serde_json::to_value(DEFAULT_SPARROW_CONFIG)?;
+        merge_json(&mut merged, overrides);
+
+        let config: SparrowConfig =
+            serde_json::from_value(merged).context("not a valid sparrow config")?;
+        config.validate()?;
+        info!("[CFG] loaded config from {}", path.display());
+        Ok(config)
+    }
+
+    /// Sets the minimum width of the exploration phase to the provable lower bound of the instance.
+    /// See [`crate::util::metrics::lower_bound_width`].
+    pub fn set_min_width_to_lower_bound(&mut self, instance: &SPInstance) {
+        let min_width = lower_bound_width(instance);
+        info!("[CFG] minimum strip width set to lower bound: {:.3}", min_width);
+        self.expl_cfg.min_width = Some(min_width);
+    }
+
+    /// Divides the global time limit over the exploration and compression phase according to the strategy.
+    /// The chosen split is logged and returned, explicit time limits can still be set on the phase configs afterward.
+    pub fn split_time_limit(
+        &mut self,
+        global_time_limit: Duration,
+        strategy: TimeSplitStrategy,
+        stats: &ItemSizeStats,
+    ) -> TimeSplit {
+        let explore_ratio = strategy.explore_ratio(stats);
+        self.expl_cfg.time_limit = global_time_limit.mul_f32(explore_ratio);
+        self.cmpr_cfg.time_limit = global_time_limit.mul_f32(1.0 - explore_ratio);
+        let split = TimeSplit {
+            strategy,
+            explore_ratio,
+            explore_time: self.expl_cfg.time_limit,
+            compress_time: self.cmpr_cfg.time_limit,
+        };
+        info!("[CFG] time split ({split})");
+        split
+    }
+
+    /// Redistributes the combined time limit of both phases according to [`SparrowConfig::time_split`], if set.
+    /// `stats` should be computed once after importing the instance.
+    pub fn apply_time_split(&mut self, stats: &ItemSizeStats) -> Option<TimeSplit> {
+        let strategy = self.time_split?;
+        let global_time_limit = self.expl_cfg.time_limit + self.cmpr_cfg.time_limit;
+        Some(self.split_time_limit(global_time_limit, strategy, stats))
+    }
+}
+
+/// Overrides of the most important tuning knobs, each one replaces the corresponding default when present.
+/// Allows tuning per instance without editing the config, see [`ConfigOverrides::apply`].
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ConfigOverrides {
+    /// Ratio by which the strip is shrunk after every feasible solution in the exploration phase, in ]0.0, 1.0[
+    pub shrink_step: Option<f32>,
+    /// Strike limit of the separator in the exploration phase
+    pub expl_strikes: Option<usize>,
+    /// Strike limit of the separator in the compression phase
+    pub cmpr_strikes: Option<usize>,
+    /// Number of iterations without improvement after which a strike is given, in both phases
+    pub iter_limit: Option<usize>,
+}
+
+impl ConfigOverrides {
+    /// Applies all present overrides to the config, fails if any of them is invalid
+    pub fn apply(&self, config: &mut SparrowConfig) -> Result<()> {
+        if let Some(shrink_step) = self.shrink_step {
+            ensure!(
+                shrink_step > 0.0 && shrink_step < 1.0,
+                "shrink step should be in ]0.0, 1.0[, got {shrink_step}"
+            );
+            config.expl_cfg.shrink_step = shrink_step;
+            info!("[CFG] shrink step overridden: {shrink_step}");
+        }
+        if let Some(expl_strikes) = self.expl_strikes {
+            config.expl_cfg.separator_config.strike_limit = expl_strikes;
+            info!("[CFG] exploration strike limit overridden: {expl_strikes}");
+        }
+        if let Some(cmpr_strikes) = self.cmpr_strikes {
+            config.cmpr_cfg.separator_config.strike_limit = cmpr_strikes;
+            info!("[CFG] compression strike limit overridden: {cmpr_strikes}");
+        }
+        if let Some(iter_limit) = self.iter_limit {
+            config.expl_cfg.separator_config.iter_no_imprv_limit = iter_limit;
+            config.cmpr_cfg.separator_config.iter_no_imprv_limit = iter_limit;
+            info!("[CFG] iterations without improvement limit overridden: {iter_limit}");
+        }
+        Ok(())
+    }
+}
+
+/// Recursively overwrites the fields of `base` with those present in `overrides`
+fn merge_json(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_json(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
 }
 
 pub const DEFAULT_SPARROW_CONFIG: SparrowConfig = SparrowConfig {
     rng_seed: None,
     expl_cfg: ExplorationConfig {
+        enabled: true,
         shrink_step: 0.001,
+        shrink_step_strategy: ShrinkStepStrategy::Fixed,
+        split_strategy: SplitStrategy::Center,
         time_limit: Duration::from_secs(9 * 60),
         max_conseq_failed_attempts: None,
+        stagnation_timeout: None,
         solution_pool_distribution_stddev: 0.25,
         separator_config: SeparatorConfig {
             iter_no_imprv_limit: 200,
@@ -68,14 +511,39 @@ pub const DEFAULT_SPARROW_CONFIG: SparrowConfig = SparrowConfig {
                 n_container_samples: 50,
                 n_focussed_samples: 25,
                 n_coord_descents: 3,
+                cd_candidates_per_axis: 2,
+                n_continuous_rot_samples: 16,
+                pole_budget: None,
+                focussed_rotation_spread: None,
+                discrete_wiggle: false,
+                reuse_best_samples: false,
             },
+            rot_reuse_weight: 0.0,
+            pole_precheck: true,
+            worker_seeding: WorkerSeeding::Independent,
+            stable_after: None,
+            edge_margin: None,
+            gravity_bias: None,
+            deterministic: false,
+            overlap_proxy_epsilon_ratio: OVERLAP_PROXY_EPSILON_DIAM_RATIO,
+            penalty_kind: PenaltyKind::GeometricMean,
+            record_decisions: false,
         },
         large_item_ch_area_cutoff_percentile: 0.75,
+        fit_strip_slack: 0.0,
+        min_width: None,
+        disruption_schedule: DisruptionSchedule::Constant,
+        disruption_strategy: DisruptionStrategy::Swap,
+        n_restarts: 1,
+        keep_best_infeasible: false,
     },
     cmpr_cfg: CompressionConfig {
+        enabled: true,
         shrink_range: (0.0005, 0.00001),
         time_limit: Duration::from_secs(1 * 60),
         shrink_decay: ShrinkDecayStrategy::TimeBased,
+        split_strategy: SplitStrategy::AreaBalanced,
+        objective: Objective::MinWidth,
         separator_config: SeparatorConfig {
             iter_no_imprv_limit: 100,
             strike_limit: 5,
@@ -85,7 +553,23 @@ pub const DEFAULT_SPARROW_CONFIG: SparrowConfig = SparrowConfig {
                 n_container_samples: 50,
                 n_focussed_samples: 25,
                 n_coord_descents: 3,
+                cd_candidates_per_axis: 2,
+                n_continuous_rot_samples: 16,
+                pole_budget: None,
+                focussed_rotation_spread: None,
+                discrete_wiggle: false,
+                reuse_best_samples: false,
             },
+            rot_reuse_weight: 0.0,
+            pole_precheck: true,
+            worker_seeding: WorkerSeeding::Independent,
+            stable_after: None,
+            edge_margin: None,
+            gravity_bias: None,
+            deterministic: false,
+            overlap_proxy_epsilon_ratio: OVERLAP_PROXY_EPSILON_DIAM_RATIO,
+            penalty_kind: PenaltyKind::GeometricMean,
+            record_decisions: false,
         },
     },
     cde_config: CDEConfig {
@@ -99,5 +583,100 @@ pub const DEFAULT_SPARROW_CONFIG: SparrowConfig = SparrowConfig {
     },
     poly_simpl_tolerance: Some(0.001),
     narrow_concavity_cutoff_ratio: Some(0.01),
+    lock_rotations: false,
     min_item_separation: None,
+    time_split: None,
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::TempPath;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(DEFAULT_SPARROW_CONFIG.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_inverted_adaptive_bounds() {
+        let mut config = DEFAULT_SPARROW_CONFIG;
+        config.cmpr_cfg.shrink_decay = ShrinkDecayStrategy::Adaptive {
+            grow: 1.5,
+            shrink: 0.5,
+            min: 0.01,
+            max: 0.001,
+        };
+        assert!(config.validate().is_err());
+
+        let mut config = DEFAULT_SPARROW_CONFIG;
+        config.expl_cfg.shrink_step_strategy = ShrinkStepStrategy::Adaptive {
+            window: 4,
+            grow: 1.5,
+            shrink: 0.5,
+            min: 0.01,
+            max: 0.001,
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_zero_coord_descents() {
+        let mut config = DEFAULT_SPARROW_CONFIG;
+        config.expl_cfg.separator_config.sample_config.n_coord_descents = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = DEFAULT_SPARROW_CONFIG;
+        config.cmpr_cfg.separator_config.sample_config.n_coord_descents = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn default_config_round_trips_through_json() {
+        let json = serde_json::to_value(DEFAULT_SPARROW_CONFIG).unwrap();
+        let config: SparrowConfig = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(config).unwrap(), json);
+    }
+
+    #[test]
+    fn json_overrides_fall_back_to_defaults() {
+        let path = TempPath::new("config_overrides.json");
+        let overrides = serde_json::json!({
+            "expl_cfg": {
+                "shrink_step": 0.01,
+                "separator_config": { "n_workers": 1, "sample_config": { "n_container_samples": 7 } }
+            }
+        });
+        std::fs::write(&path, overrides.to_string()).unwrap();
+        let config = SparrowConfig::from_json_file(&path).unwrap();
+
+        let expl_sep = &config.expl_cfg.separator_config;
+        assert_eq!(config.expl_cfg.shrink_step, 0.01);
+        assert_eq!(expl_sep.n_workers, 1);
+        assert_eq!(expl_sep.sample_config.n_container_samples, 7);
+        //untouched fields, including siblings within a partially overridden nested config, keep their default
+        let default_sep = &DEFAULT_SPARROW_CONFIG.expl_cfg.separator_config;
+        assert_eq!(
+            expl_sep.sample_config.n_focussed_samples,
+            default_sep.sample_config.n_focussed_samples
+        );
+        assert_eq!(expl_sep.strike_limit, default_sep.strike_limit);
+        assert_eq!(
+            serde_json::to_value(config.cmpr_cfg).unwrap(),
+            serde_json::to_value(DEFAULT_SPARROW_CONFIG.cmpr_cfg).unwrap()
+        );
+    }
+
+    #[test]
+    fn json_overrides_are_validated() {
+        let path = TempPath::new("config_invalid.json");
+        let overrides = serde_json::json!({
+            "cmpr_cfg": {
+                "shrink_decay": { "Adaptive": { "grow": 1.5, "shrink": 0.5, "min": 0.1, "max": 0.01 } }
+            }
+        });
+        std::fs::write(&path, overrides.to_string()).unwrap();
+        let result = SparrowConfig::from_json_file(&path);
+        assert!(result.is_err());
+    }
+}
@@ -6,6 +6,28 @@ pub const GLS_WEIGHT_MIN_INC_RATIO: f32 = 1.2;
 pub const GLS_WEIGHT_DECAY: f32 = 0.95;
 pub const OVERLAP_PROXY_EPSILON_DIAM_RATIO: f32 = 0.01;
 
+/// Resolution (points per axis) of the grid used to estimate true overlap areas when validating the overlap proxy
+pub const PROXY_FIDELITY_GRID_RES: usize = 200;
+
+/// Maximum number of colliding pairs for which the overlap proxy is compared with the exact overlap in debug builds
+#[cfg(feature = "exact_overlap")]
+pub const EXACT_OVERLAP_CHECK_N_PAIRS: usize = 8;
+
+/// Ratio between two exact losses above which the overlap proxy is expected to order them the same way
+#[cfg(feature = "exact_overlap")]
+pub const EXACT_OVERLAP_ORDER_RATIO: f32 = 2.0;
+
+/// Items with a convex hull area below this ratio of their squared diameter are considered degenerate (e.g. collinear vertices)
+pub const DEGENERATE_ITEM_AREA_RATIO: f32 = 1e-6;
+
+/// Relative tolerance between the tracked and recomputed total loss, see [`crate::quantify::tracker::CollisionTracker::total_loss_matches`].
+/// Collisions are not exactly symmetrical, so the loss of a pair can differ slightly depending on which side quantified it.
+pub const TRACKER_LOSS_TOLERANCE_RATIO: f32 = 0.01;
+
+/// Upper bound of the offset added to clear samples by the gravity bias, see [`crate::eval::sep_evaluator::GravityBias`].
+/// Kept tiny, such that it only breaks ties and clear losses remain comparable.
+pub const GRAVITY_BIAS_MAX: f32 = 1e-3;
+
 /// Coordinate descent step multiplier on success
 pub const CD_STEP_SUCCESS: f32 = 1.1;
 
@@ -24,20 +46,52 @@ pub const SND_REFINE_CD_TL_RATIOS: (f32, f32) = (0.01, 0.001);
 /// Step sizes for rotation in the second (final) refinement
 pub const SND_REFINE_CD_R_STEPS: (f32, f32) = (f32::to_radians(0.5), f32::to_radians(0.05));
 
-/// If two samples are closer than this ratio of the item's min dimension, they are considered duplicates
+/// Number of rotations considered for continuously rotatable items when locking their rotation upfront
+pub const PREPROCESS_ROT_N_SAMPLES: usize = 360;
+
+/// Two rotations closer than this (in radians) are considered the same orientation
+pub const ROTATION_EQUALITY_TOLERANCE: f32 = f32::to_radians(0.01);
+
+/// Success rates (over the window) above which the adaptive shrink step of the exploration phase grows,
+/// and below which it decays
+pub const ADAPTIVE_SHRINK_SUCCESS_RATES: (f32, f32) = (0.66, 0.33);
+
+/// Maximum number of left edges considered by the empty rectangle scan of the gap filling disruption.
+/// Bounds its cost to O(k·n²) for large layouts, at the expense of possibly missing the largest gap.
+pub const GAP_FILL_MAX_LEFT_EDGES: usize = 64;
+
+/// Relative tolerance within which the strip width is considered to have reached the minimum feasible width
+pub const MIN_WIDTH_TOLERANCE_RATIO: f32 = 1e-4;
+
+/// If two samples are closer than this ratio of the item's width (along x) and height (along y), they are considered duplicates
 pub const UNIQUE_SAMPLE_THRESHOLD: f32 = 0.05;
 
 pub const DEFAULT_EXPLORE_TIME_RATIO: f32 = 0.8;
 pub const DEFAULT_COMPRESS_TIME_RATIO: f32 = 0.2;
 
+/// Shift of the exploration time ratio per unit of item area coefficient of variation (see [`crate::config::TimeSplitStrategy::ByDifficulty`])
+pub const TIME_SPLIT_CV_SENSITIVITY: f32 = 0.1;
+
+/// Bounds of the exploration time ratio when derived from the instance's difficulty
+pub const TIME_SPLIT_EXPLORE_RATIO_RANGE: (f32, f32) = (0.7, 0.9);
+
 pub const DEFAULT_MAX_CONSEQ_FAILS_EXPL: usize = 10;
 
 pub const DEFAULT_FAIL_DECAY_RATIO_CMPR: f32 = 0.9;
 
+/// Default directory to write all output artifacts to
+pub const DEFAULT_OUTPUT_DIR: &str = "output";
+
+/// Default directory of the live solution viewer
+pub const DEFAULT_LIVE_DIR: &str = "data/live";
+
 pub const LOG_LEVEL_FILTER_RELEASE: log::LevelFilter = log::LevelFilter::Info;
 
 pub const LOG_LEVEL_FILTER_DEBUG: log::LevelFilter = log::LevelFilter::Debug;
 
+/// Time (in seconds) each frame of an animated SVG is shown
+pub const ANIMATION_FRAME_DURATION: f32 = 0.5;
+
 pub const DRAW_OPTIONS: SvgDrawOptions = SvgDrawOptions {
     theme: SvgLayoutTheme::GRAY,
     quadtree: false,
@@ -47,8 +101,18 @@ pub const DRAW_OPTIONS: SvgDrawOptions = SvgDrawOptions {
     highlight_cd_shapes: true,
 };
 
+/// Scale factor (pixels per unit of the SVG) when rasterizing solutions to PNG
+#[cfg(feature = "png")]
+pub const PNG_SCALE: f32 = 1.0;
+
 pub const LBF_SAMPLE_CONFIG: SampleConfig = SampleConfig {
     n_container_samples: 1000,
     n_focussed_samples: 0,
     n_coord_descents: 3,
+    cd_candidates_per_axis: 2,
+    n_continuous_rot_samples: 16,
+    pole_budget: None,
+    focussed_rotation_spread: None,
+    discrete_wiggle: false,
+    reuse_best_samples: false,
 };
@@ -0,0 +1,74 @@
+use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
+use crate::eval::sep_evaluator::SeparationEvaluator;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::primitives::Rect;
+use serde::{Deserialize, Serialize};
+
+/// Margin which items should keep from the edges of the container (e.g. to avoid cutting issues)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct EdgeMargin {
+    /// Distance to the container's edges below which samples are penalized
+    pub margin: f32,
+    /// Penalty per unit of distance the item's bounding box intrudes into the margin
+    pub weight: f32,
+}
+
+/// Wraps a [`SeparationEvaluator`] and penalizes samples near the container's edges, like `SeparationEvaluator::apply_rotation_reuse_penalty`.
+pub struct MarginEvaluator<'a> {
+    inner: SeparationEvaluator<'a>,
+    container_bbox: Rect,
+    edge_margin: Option<EdgeMargin>,
+}
+
+impl<'a> MarginEvaluator<'a> {
+    pub fn new(
+        inner: SeparationEvaluator<'a>,
+        container_bbox: Rect,
+        edge_margin: Option<EdgeMargin>,
+    ) -> Self {
+        Self {
+            inner,
+            container_bbox,
+            edge_margin,
+        }
+    }
+
+    /// Penalty for the bounding box of a sample, proportional to how far it extends into the margin
+    fn margin_penalty(&self, bbox: Rect, em: EdgeMargin) -> f32 {
+        let c = self.container_bbox;
+        let intrusion = |dist: f32| f32::max(0.0, em.margin - dist);
+        let total_intrusion = intrusion(bbox.x_min - c.x_min)
+            + intrusion(c.x_max - bbox.x_max)
+            + intrusion(bbox.y_min - c.y_min)
+            + intrusion(c.y_max - bbox.y_max);
+        total_intrusion * em.weight
+    }
+}
+
+impl<'a> SampleEvaluator for MarginEvaluator<'a> {
+    /// The penalty is non-negative, so the upper bound of the inner evaluator remains valid:
+    /// samples which exceed it without the penalty will certainly exceed it with the penalty.
+    fn evaluate_sample(
+        &mut self,
+        dt: DTransformation,
+        upper_bound: Option<SampleEval>,
+    ) -> SampleEval {
+        let eval = self.inner.evaluate_sample(dt, upper_bound);
+        let Some(em) = self.edge_margin else {
+            return eval;
+        };
+        match eval {
+            SampleEval::Collision { loss } => SampleEval::Collision {
+                loss: loss + self.margin_penalty(self.inner.shape_bbox(), em),
+            },
+            SampleEval::Clear { loss } => SampleEval::Clear {
+                loss: loss + self.margin_penalty(self.inner.shape_bbox(), em),
+            },
+            SampleEval::Invalid => SampleEval::Invalid,
+        }
+    }
+
+    fn n_evals(&self) -> usize {
+        self.inner.n_evals()
+    }
+}
@@ -0,0 +1,43 @@
+use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
+use jagua_rs::geometry::DTransformation;
+
+/// Deterministic evaluator backed by a closure, without any layout or collision detection engine.
+/// Allows the sampling and refinement logic to be tested against landscapes with analytically known minima.
+pub struct MockEvaluator<F: Fn(DTransformation) -> SampleEval> {
+    eval_fn: F,
+    n_evals: usize,
+}
+
+impl<F: Fn(DTransformation) -> SampleEval> MockEvaluator<F> {
+    pub fn new(eval_fn: F) -> Self {
+        Self {
+            eval_fn,
+            n_evals: 0,
+        }
+    }
+}
+
+impl<F: Fn(DTransformation) -> SampleEval> SampleEvaluator for MockEvaluator<F> {
+    fn evaluate_sample(
+        &mut self,
+        dt: DTransformation,
+        _upper_bound: Option<SampleEval>,
+    ) -> SampleEval {
+        self.n_evals += 1;
+        (self.eval_fn)(dt)
+    }
+
+    fn n_evals(&self) -> usize {
+        self.n_evals
+    }
+}
+
+/// Quadratic bowl centered at `(x, y)`: every transformation evaluates to a [`SampleEval::Collision`]
+/// with a loss equal to the squared distance to the center (plus a constant 1.0, so the loss never reaches zero).
+pub fn quadratic_bowl(center: (f32, f32)) -> impl Fn(DTransformation) -> SampleEval {
+    move |dt: DTransformation| {
+        let (x, y) = dt.translation();
+        let loss = (x - center.0).powi(2) + (y - center.1).powi(2) + 1.0;
+        SampleEval::Collision { loss }
+    }
+}
@@ -1,4 +1,8 @@
 pub mod lbf_evaluator;
+pub mod margin_evaluator;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock_evaluator;
 pub mod sample_eval;
 pub mod sep_evaluator;
+pub mod shape_buffer_pool;
 pub mod specialized_jaguars_pipeline;
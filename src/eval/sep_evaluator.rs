@@ -1,21 +1,43 @@
 use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
+use crate::eval::shape_buffer_pool::ShapeBufferPool;
 use crate::eval::specialized_jaguars_pipeline::{
     SpecializedHazardCollector, collect_poly_collisions_in_detector_custom,
 };
+use crate::consts::GRAVITY_BIAS_MAX;
 use crate::quantify::tracker::CollisionTracker;
+use crate::util::metrics::rotations_are_equal;
 use jagua_rs::collision_detection::hazards::collector::HazardCollector;
 use jagua_rs::entities::Item;
 use jagua_rs::entities::Layout;
 use jagua_rs::entities::PItemKey;
 use jagua_rs::geometry::DTransformation;
-use jagua_rs::geometry::primitives::SPolygon;
+use jagua_rs::geometry::primitives::{Rect, SPolygon};
+use serde::{Deserialize, Serialize};
+
+/// Tie-break between clear placements, preferring those closer to the bottom-left of the container
+/// (like the [`crate::eval::lbf_evaluator::LBFEvaluator`]), so items do not drift during separation.
+/// See [`SeparationEvaluator::with_gravity_bias`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GravityBias {
+    /// Relative weight of the horizontal position of the placement
+    pub x_multiplier: f32,
+    /// Relative weight of the vertical position of the placement
+    pub y_multiplier: f32,
+}
 
 pub struct SeparationEvaluator<'a> {
     layout: &'a Layout,
     item: &'a Item,
     collector: SpecializedHazardCollector<'a>,
-    shape_buff: SPolygon,
+    /// Buffer borrowed from the pool, only taken out to return it on drop
+    shape_buff: Option<SPolygon>,
+    shape_pool: &'a mut ShapeBufferPool,
     n_evals: usize,
+    /// Penalty for placing the item in an orientation not used by any other placed item (0.0 disables it)
+    rot_reuse_weight: f32,
+    /// Distinct rotations used by the other placed items (only collected if the penalty is enabled)
+    used_rotations: Vec<f32>,
+    gravity_bias: Option<GravityBias>,
 }
 
 impl<'a> SeparationEvaluator<'a> {
@@ -24,15 +46,98 @@ impl<'a> SeparationEvaluator<'a> {
         item: &'a Item,
         current_pk: PItemKey,
         ct: &'a CollisionTracker,
+        rot_reuse_weight: f32,
+        shape_pool: &'a mut ShapeBufferPool,
     ) -> Self {
         let collector = SpecializedHazardCollector::new(layout, ct, current_pk);
 
+        let mut used_rotations: Vec<f32> = vec![];
+        if rot_reuse_weight > 0.0 {
+            for (_, pi) in layout.placed_items.iter().filter(|(pk, _)| *pk != current_pk) {
+                let r = pi.d_transf.rotation();
+                if !used_rotations.iter().any(|ur| rotations_are_equal(*ur, r)) {
+                    used_rotations.push(r);
+                }
+            }
+        }
+
         Self {
             layout,
             item,
             collector,
-            shape_buff: item.shape_cd.as_ref().clone(),
+            shape_buff: Some(shape_pool.take(item)),
+            shape_pool,
             n_evals: 0,
+            rot_reuse_weight,
+            used_rotations,
+            gravity_bias: None,
+        }
+    }
+
+    /// Offsets the loss of clear samples by a tiny amount, which increases toward the top-right of the container.
+    /// The offset is bounded by [`GRAVITY_BIAS_MAX`], so it only breaks ties between otherwise equally clear samples.
+    /// Collision losses are unaffected. Disabled if `None`.
+    pub fn with_gravity_bias(mut self, gravity_bias: Option<GravityBias>) -> Self {
+        self.gravity_bias = gravity_bias;
+        self
+    }
+
+    /// Enables or disables the pole-based fail-fast check before the edges of the shape are checked.
+    /// See [`collect_poly_collisions_in_detector_custom`].
+    pub fn with_pole_precheck(mut self, pole_precheck: bool) -> Self {
+        self.collector.pole_precheck = pole_precheck;
+        self
+    }
+
+    /// Bounding box of the shape of the most recently evaluated sample
+    pub fn shape_bbox(&self) -> Rect {
+        self.shape_buff
+            .as_ref()
+            .expect("shape buffer should only be taken on drop")
+            .bbox
+    }
+
+    /// Penalizes samples with an orientation which is not yet used by any other item in the layout.
+    /// Collision losses are scaled, clear losses are offset, so the penalty never turns a collision into a clear sample.
+    fn apply_rotation_reuse_penalty(&self, dt: DTransformation, eval: SampleEval) -> SampleEval {
+        if self.rot_reuse_weight == 0.0
+            || self
+                .used_rotations
+                .iter()
+                .any(|r| rotations_are_equal(*r, dt.rotation()))
+        {
+            return eval;
+        }
+        match eval {
+            SampleEval::Collision { loss } => SampleEval::Collision {
+                loss: loss * (1.0 + self.rot_reuse_weight),
+            },
+            SampleEval::Clear { loss } => SampleEval::Clear {
+                loss: loss + self.rot_reuse_weight,
+            },
+            SampleEval::Invalid => SampleEval::Invalid,
+        }
+    }
+
+    /// Offsets clear samples by their (normalized) position within the container, see [`SeparationEvaluator::with_gravity_bias`]
+    fn apply_gravity_bias(&self, eval: SampleEval) -> SampleEval {
+        match (eval, self.gravity_bias) {
+            (SampleEval::Clear { loss }, Some(gb)) => {
+                let c = self.layout.container.outer_cd.bbox;
+                let bbox = self.shape_bbox();
+                let x_ratio = ((bbox.x_min - c.x_min) / c.width()).clamp(0.0, 1.0);
+                let y_ratio = ((bbox.y_min - c.y_min) / c.height()).clamp(0.0, 1.0);
+                let total_multiplier = gb.x_multiplier + gb.y_multiplier;
+                let weighted_ratio = gb.x_multiplier * x_ratio + gb.y_multiplier * y_ratio;
+                let bias = match total_multiplier > 0.0 {
+                    true => weighted_ratio / total_multiplier,
+                    false => 0.0,
+                };
+                SampleEval::Clear {
+                    loss: loss + bias * GRAVITY_BIAS_MAX,
+                }
+            }
+            _ => eval,
         }
     }
 }
@@ -57,16 +162,21 @@ impl<'a> SampleEvaluator for SeparationEvaluator<'a> {
         //reload the hazard collector to prepare for a new query
         self.collector.reload(loss_bound);
 
+        let shape_buff = self
+            .shape_buff
+            .as_mut()
+            .expect("shape buffer should only be taken on drop");
+
         //query the CDE, all colliding hazards will be stored in the detection map
         collect_poly_collisions_in_detector_custom(
             cde,
             &dt,
-            &mut self.shape_buff,
+            shape_buff,
             self.item.shape_cd.as_ref(),
             &mut self.collector,
         );
 
-        if self.collector.early_terminate(&self.shape_buff) {
+        let eval = if self.collector.early_terminate(shape_buff) {
             //the detection map is in early termination state, this means potentially not all collisions were detected,
             //but its loss was above the loss bound anyway
             SampleEval::Invalid
@@ -74,12 +184,24 @@ impl<'a> SampleEvaluator for SeparationEvaluator<'a> {
             SampleEval::Clear { loss: 0.0 }
         } else {
             SampleEval::Collision {
-                loss: self.collector.loss(&self.shape_buff),
+                loss: self.collector.loss(shape_buff),
             }
-        }
+        };
+
+        let eval = self.apply_rotation_reuse_penalty(dt, eval);
+        self.apply_gravity_bias(eval)
     }
 
     fn n_evals(&self) -> usize {
         self.n_evals
     }
 }
+
+impl Drop for SeparationEvaluator<'_> {
+    fn drop(&mut self) {
+        //return the buffer to the pool, so the next evaluator for this item can reuse it
+        if let Some(shape_buff) = self.shape_buff.take() {
+            self.shape_pool.give_back(self.item.id, shape_buff);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use jagua_rs::entities::Item;
+use jagua_rs::geometry::primitives::SPolygon;
+use std::collections::HashMap;
+
+/// Pool of reusable shape buffers, keyed by item id.
+/// Avoids cloning an item's shape every time an evaluator is constructed for it.
+#[derive(Debug, Clone, Default)]
+pub struct ShapeBufferPool {
+    buffers: HashMap<usize, SPolygon>,
+}
+
+impl ShapeBufferPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a buffer for the item from the pool, or clones the item's shape if none is available.
+    /// The contents of the buffer are arbitrary, it should be overwritten before use.
+    pub fn take(&mut self, item: &Item) -> SPolygon {
+        self.buffers
+            .remove(&item.id)
+            .unwrap_or_else(|| item.shape_cd.as_ref().clone())
+    }
+
+    /// Returns a buffer, previously taken for the item, to the pool
+    pub fn give_back(&mut self, item_id: usize, buffer: SPolygon) {
+        self.buffers.insert(item_id, buffer);
+    }
+}
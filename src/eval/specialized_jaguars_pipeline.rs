@@ -1,10 +1,13 @@
-use crate::quantify::quantify_collision_poly_container;
 #[cfg(not(feature = "simd"))]
-use crate::quantify::quantify_collision_poly_poly;
+use crate::quantify::quantify_collision_poly_container_hazard;
+#[cfg(not(feature = "simd"))]
+use crate::quantify::quantify_collision_poly_poly_bounded;
 #[cfg(feature = "simd")]
 use crate::quantify::simd::circles_soa::CirclesSoA;
 #[cfg(feature = "simd")]
-use crate::quantify::simd::quantify_collision_poly_poly_simd;
+use crate::quantify::simd::{
+    quantify_collision_poly_container_simd, quantify_collision_poly_poly_simd,
+};
 use crate::quantify::tracker::CollisionTracker;
 use crate::util::assertions;
 use crate::util::bit_reversal_iterator::BitReversalIterator;
@@ -38,7 +41,7 @@ pub fn collect_poly_collisions_in_detector_custom(
     #[cfg(feature = "simd")]
     collector.poles_soa.load(&shape.surrogate().poles);
 
-    {
+    if collector.pole_precheck {
         // We start off by checking a few poles in order to detect obvious collisions quickly and quickly raise the loss.
         // Potentially allows us to fail fast (early terminate) without checking all edges.
         // We check poles until the area of the poles checked exceeds 50% of the shape.
@@ -107,6 +110,8 @@ pub struct SpecializedHazardCollector<'a> {
     pub idx_counter: usize,
     pub loss_cache: (usize, f32),
     pub loss_bound: f32,
+    /// Whether to check the poles of the shape for collisions before iterating over its edges
+    pub pole_precheck: bool,
     #[cfg(feature = "simd")]
     pub poles_soa: CirclesSoA,
 }
@@ -126,6 +131,7 @@ impl<'a> SpecializedHazardCollector<'a> {
             idx_counter: 0,
             loss_cache: (0, 0.0),
             loss_bound: f32::INFINITY,
+            pole_precheck: true,
             #[cfg(feature = "simd")]
             poles_soa: CirclesSoA::new(),
         }
@@ -151,9 +157,10 @@ impl<'a> SpecializedHazardCollector<'a> {
         if cache_idx < self.idx_counter {
             // additional hazards were detected, update the cache
             let extra_loss: f32 = self
-                .iter_with_index()
-                .filter(|(_, idx)| *idx >= cache_idx)
-                .map(|(h, _)| self.calc_weighted_loss(h, shape))
+                .detected
+                .iter()
+                .filter(|(_, (_, idx))| *idx >= cache_idx)
+                .map(|(hkey, (h, _))| self.calc_weighted_loss(hkey, h, shape))
                 .sum();
             self.loss_cache = (self.idx_counter, cached_loss + extra_loss);
         }
@@ -161,32 +168,66 @@ impl<'a> SpecializedHazardCollector<'a> {
             f32,
             self.loss_cache.1,
             self.iter()
-                .map(|(_, he)| self.calc_weighted_loss(he, shape))
+                .map(|(hkey, he)| self.calc_weighted_loss(hkey, he, shape))
                 .sum()
         ));
         self.loss_cache.1
     }
 
-    fn calc_weighted_loss(&self, haz: &HazardEntity, shape: &SPolygon) -> f32 {
+    fn calc_weighted_loss(&self, hkey: HazKey, haz: &HazardEntity, shape: &SPolygon) -> f32 {
         match haz {
             HazardEntity::PlacedItem { pk: other_pk, .. } => {
                 let other_shape = &self.layout.placed_items[*other_pk].shape;
 
+                let weight = self.ct.get_pair_weight(self.current_pk, *other_pk);
+                let penalty = self.ct.get_pair_penalty(self.current_pk, *other_pk);
+
+                //a single collision exceeding the loss bound suffices to early terminate,
+                //the exact loss of such a collision is irrelevant
                 #[cfg(not(feature = "simd"))]
-                let loss = quantify_collision_poly_poly(other_shape, shape);
+                let loss = quantify_collision_poly_poly_bounded(
+                    other_shape,
+                    shape,
+                    penalty,
+                    self.ct.epsilon_ratio(),
+                    Some(self.loss_bound / weight),
+                );
                 #[cfg(feature = "simd")]
-                let loss = quantify_collision_poly_poly_simd(other_shape, shape, &self.poles_soa);
+                let loss = quantify_collision_poly_poly_simd(
+                    other_shape,
+                    shape,
+                    &self.poles_soa,
+                    penalty,
+                    self.ct.epsilon_ratio(),
+                );
 
-                let weight = self.ct.get_pair_weight(self.current_pk, *other_pk);
                 loss * weight
             }
-            HazardEntity::Exterior => {
-                let loss =
-                    quantify_collision_poly_container(shape, self.layout.container.outer_cd.bbox);
+            _ => {
+                //the exterior and any holes of the container share the container weight
+                let haz_shape = &self.layout.cde().hazards_map[hkey].shape;
+                let penalty = self.ct.get_container_penalty(self.current_pk);
+                let epsilon_ratio = self.ct.epsilon_ratio();
+                #[cfg(not(feature = "simd"))]
+                let loss = quantify_collision_poly_container_hazard(
+                    shape,
+                    haz,
+                    haz_shape,
+                    penalty,
+                    epsilon_ratio,
+                );
+                #[cfg(feature = "simd")]
+                let loss = quantify_collision_poly_container_simd(
+                    shape,
+                    haz,
+                    haz_shape,
+                    &self.poles_soa,
+                    penalty,
+                    epsilon_ratio,
+                );
                 let weight = self.ct.get_container_weight(self.current_pk);
                 loss * weight
             }
-            _ => unimplemented!("unsupported hazard entity"),
         }
     }
 }
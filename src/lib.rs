@@ -2,22 +2,42 @@
 #![allow(const_item_mutation)]
 #![allow(unused_imports)]
 
+use jagua_rs::Instant;
 use jagua_rs::probs::spp::io::ext_repr::{ExtSPInstance, ExtSPSolution};
+use serde::Serialize;
+use std::sync::LazyLock;
 
 pub use jagua_rs;
+pub mod api;
 pub mod config;
 pub mod consts;
 pub mod eval;
 pub mod optimizer;
+pub mod prelude;
 pub mod quantify;
 pub mod sample;
 pub mod util;
 
 pub use config::*;
+pub use api::run_from_json;
 pub use optimizer::optimize;
 
-#[derive(Clone)]
+/// Reference point in time for the timestamps of exported solutions
+pub static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+#[derive(Clone, Serialize)]
 pub struct SPOutput {
     pub instance: ExtSPInstance,
     pub solution: ExtSPSolution,
+    /// See [`ExplorationConfig::keep_best_infeasible`], omitted if none was kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_infeasible: Option<InfeasibleOutput>,
+}
+
+/// An infeasible solution closest to feasibility, annotated with its total loss.
+/// Unlike [`SPOutput::solution`], it may contain overlaps (e.g. a starting point for manual cleanup).
+#[derive(Clone, Serialize)]
+pub struct InfeasibleOutput {
+    pub solution: ExtSPSolution,
+    pub total_loss: f32,
 }
\ No newline at end of file
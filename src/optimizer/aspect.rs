@@ -0,0 +1,264 @@
+use crate::consts::LBF_SAMPLE_CONFIG;
+use crate::optimizer::lbf::LBFBuilder;
+use crate::optimizer::separator::{Separator, SeparatorConfig};
+use crate::util::listener::{ReportMeta, ReportType, SolutionListener};
+use crate::util::metrics::{SolutionSummary, utilized_bbox};
+use crate::util::terminator::Terminator;
+use jagua_rs::entities::Instance;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use log::{info, warn};
+use rand::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+
+/// Configuration of the fixed area problem, see [`optimize_fixed_area`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FixedAreaConfig {
+    /// Area of the container, constant while its aspect ratio (width : height) varies
+    pub area: f32,
+    /// Initial relative change of the width (and inverse change of the height) between two attempted aspects
+    pub aspect_step: f32,
+    /// The search stops once the aspect step has decayed below this value
+    pub min_aspect_step: f32,
+    pub separator_config: SeparatorConfig,
+}
+
+/// Feasible solution of the fixed area problem.
+/// The height of the container differs from the original instance, so the solution comes with its own instance.
+#[derive(Debug, Clone)]
+pub struct FixedAreaSolution {
+    pub instance: SPInstance,
+    pub solution: SPSolution,
+}
+
+impl FixedAreaSolution {
+    pub fn width(&self) -> f32 {
+        self.solution.strip_width()
+    }
+
+    pub fn height(&self) -> f32 {
+        self.instance.base_strip.fixed_height
+    }
+
+    /// Area of the bounding box of all items which is not covered by any item, see [`wasted_area`]
+    pub fn wasted_area(&self) -> f32 {
+        wasted_area(&self.solution, &self.instance)
+    }
+}
+
+/// Area of the bounding box of all placed items which is not covered by any of them.
+/// In a container of fixed area, the space outside of this bounding box remains available as a single remnant,
+/// so only the space between the items is considered wasted.
+pub fn wasted_area(solution: &SPSolution, instance: &SPInstance) -> f32 {
+    let bbox_area = utilized_bbox(solution).map_or(0.0, |bbox| bbox.width() * bbox.height());
+    let item_area = solution
+        .layout_snapshot
+        .placed_items
+        .values()
+        .map(|pi| instance.item(pi.item_id).shape_cd.area)
+        .sum::<f32>();
+    bbox_area - item_area
+}
+
+/// Changes the width of a container of fixed `area`, adjusting its height inversely to keep the area constant.
+/// The instance is rebuilt with the coupled strip height. Every item keeps its position relative to the container
+/// (its bounding box center is scaled along both axes), which may introduce collisions to be resolved by separation.
+pub fn change_aspect(
+    instance: &SPInstance,
+    solution: &SPSolution,
+    area: f32,
+    new_width: f32,
+) -> (SPInstance, SPProblem) {
+    let c_bbox = solution.layout_snapshot.container.outer_cd.bbox;
+    let new_height = area / new_width;
+    let (sx, sy) = (new_width / c_bbox.width(), new_height / c_bbox.height());
+
+    let mut resized = instance.clone();
+    resized.base_strip.fixed_height = new_height;
+
+    let mut prob = SPProblem::new(resized.clone());
+    prob.change_strip_width(new_width);
+    for pi in solution.layout_snapshot.placed_items.values() {
+        let center = pi.shape.bbox.centroid();
+        let (x, y) = (center.0 - c_bbox.x_min, center.1 - c_bbox.y_min);
+        let (tx, ty) = pi.d_transf.translation();
+        let d_transf = DTransformation::new(
+            pi.d_transf.rotation(),
+            (tx + x * (sx - 1.0), ty + y * (sy - 1.0)),
+        );
+        prob.place_item(SPPlacement {
+            item_id: pi.item_id,
+            d_transf,
+        });
+    }
+    (resized, prob)
+}
+
+/// Searches the aspect ratio of a container with a fixed area in which all items can be packed feasibly,
+/// with the least [`wasted_area`]. Changing the width of the container changes its height inversely
+/// (see [`change_aspect`]), after which the layout is separated like in the exploration phase.
+///
+/// Starts from the strip height of the instance. Every iteration attempts to widen and narrow the container
+/// by the aspect step, starting from the best feasible aspect so far. If neither attempt improves upon it,
+/// the step is halved, until it decays below [`FixedAreaConfig::min_aspect_step`].
+/// Returns `None` if no feasible aspect was found (or the items do not even fit in the area).
+pub fn optimize_fixed_area(
+    instance: &SPInstance,
+    mut rng: Xoshiro256PlusPlus,
+    sol_listener: &mut impl SolutionListener,
+    term: &impl Terminator,
+    config: &FixedAreaConfig,
+) -> Option<FixedAreaSolution> {
+    let total_item_area = instance
+        .items
+        .iter()
+        .map(|(item, q)| item.shape_cd.area * *q as f32)
+        .sum::<f32>();
+    if total_item_area > config.area {
+        warn!(
+            "[ASPECT] total item area ({:.3}) exceeds the area of the container ({:.3})",
+            total_item_area, config.area
+        );
+        return None;
+    }
+
+    //construct an initial solution at the original strip height and fit it into the fixed area
+    let lbf_rng = Xoshiro256PlusPlus::seed_from_u64(rng.next_u64());
+    let builder = LBFBuilder::new(instance.clone(), lbf_rng, LBF_SAMPLE_CONFIG).construct();
+    let lbf_sol = builder.prob.save();
+    let init_width = config.area / instance.base_strip.fixed_height;
+    let (init_instance, init_prob) = change_aspect(instance, &lbf_sol, config.area, init_width);
+
+    let mut reference = (init_instance.clone(), init_prob.save());
+    let mut best = separate_aspect(init_instance, init_prob, &mut rng, sol_listener, term, config);
+    if let Some(best) = &best {
+        reference = (best.instance.clone(), best.solution.clone());
+    }
+
+    let mut step = config.aspect_step;
+    while step >= config.min_aspect_step && !term.should_terminate() {
+        let (ref_instance, ref_sol) = &reference;
+        let mut improved = false;
+        for factor in [1.0 + step, 1.0 - step] {
+            let width = ref_sol.strip_width() * factor;
+            let (aspect_instance, prob) = change_aspect(ref_instance, ref_sol, config.area, width);
+            let attempt =
+                separate_aspect(aspect_instance, prob, &mut rng, sol_listener, term, config);
+            if let Some(attempt) = attempt
+                && best
+                    .as_ref()
+                    .is_none_or(|b| attempt.wasted_area() < b.wasted_area())
+            {
+                info!(
+                    "[ASPECT] improved at {:.3} x {:.3} (wasted area: {:.3})",
+                    attempt.width(),
+                    attempt.height(),
+                    attempt.wasted_area()
+                );
+                best = Some(attempt);
+                improved = true;
+                break;
+            }
+        }
+        match improved {
+            true => {
+                let best = best.as_ref().unwrap();
+                reference = (best.instance.clone(), best.solution.clone());
+            }
+            false => step /= 2.0,
+        }
+    }
+
+    match &best {
+        Some(best) => info!(
+            "[ASPECT] finished at {:.3} x {:.3} ({})",
+            best.width(),
+            best.height(),
+            SolutionSummary::of(&best.solution, &best.instance)
+        ),
+        None => warn!("[ASPECT] no feasible aspect found for area {:.3}", config.area),
+    }
+    best
+}
+
+/// Separates the layout of a single aspect, returns the solution if it is feasible
+fn separate_aspect(
+    instance: SPInstance,
+    prob: SPProblem,
+    rng: &mut Xoshiro256PlusPlus,
+    sol_listener: &mut impl SolutionListener,
+    term: &impl Terminator,
+    config: &FixedAreaConfig,
+) -> Option<FixedAreaSolution> {
+    let sep_rng = Xoshiro256PlusPlus::seed_from_u64(rng.next_u64());
+    let mut sep = Separator::new(instance.clone(), prob, sep_rng, config.separator_config);
+    let (solution, ct) = sep.separate(term, sol_listener);
+    match ct.get_total_loss() == 0.0 {
+        true => {
+            sol_listener.report_with_meta(
+                ReportType::ExplFeas,
+                &solution,
+                &instance,
+                ReportMeta::new(&solution, &instance, 0.0),
+            );
+            Some(FixedAreaSolution { instance, solution })
+        }
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::{mixed_rects, quick_config};
+    use crate::util::listener::DummySolListener;
+    use crate::util::terminator::TimedTerminator;
+    use float_cmp::approx_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn change_aspect_keeps_the_area() {
+        let instance = mixed_rects();
+        let sol = SPProblem::new(instance.clone()).save();
+        let area = 150.0;
+        for width in [10.0, 15.0, 30.0] {
+            let (resized, prob) = change_aspect(&instance, &sol, area, width);
+            let c_bbox = prob.layout.container.outer_cd.bbox;
+            assert!(approx_eq!(f32, c_bbox.width() * c_bbox.height(), area, epsilon = 1e-3));
+            assert!(approx_eq!(f32, resized.base_strip.fixed_height, area / width, epsilon = 1e-4));
+        }
+    }
+
+    #[test]
+    fn finds_a_feasible_aspect_with_spare_area() {
+        let instance = mixed_rects();
+        let config = FixedAreaConfig {
+            area: 1.5 * 73.0,
+            aspect_step: 0.2,
+            min_aspect_step: 0.05,
+            separator_config: quick_config(Duration::from_secs(1)).expl_cfg.separator_config,
+        };
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let term = TimedTerminator::new_duration(Duration::from_secs(10));
+        let best = optimize_fixed_area(&instance, rng, &mut DummySolListener, &term, &config)
+            .expect("a feasible aspect should exist");
+        assert!(approx_eq!(f32, best.width() * best.height(), config.area, epsilon = 1e-2));
+        assert!(best.wasted_area() >= 0.0);
+    }
+
+    #[test]
+    fn rejects_an_area_smaller_than_the_items() {
+        let instance = mixed_rects();
+        let config = FixedAreaConfig {
+            area: 10.0,
+            aspect_step: 0.2,
+            min_aspect_step: 0.05,
+            separator_config: quick_config(Duration::from_secs(1)).expl_cfg.separator_config,
+        };
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let term = TimedTerminator::new_duration(Duration::from_secs(1));
+        let best = optimize_fixed_area(&instance, rng, &mut DummySolListener, &term, &config);
+        assert!(best.is_none());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::consts::LBF_SAMPLE_CONFIG;
+use crate::optimizer::compress::attempt_to_compress;
+use crate::optimizer::lbf::LBFBuilder;
+use crate::optimizer::separator::{Separator, SeparatorConfig, SplitStrategy};
+use crate::util::listener::{ReportMeta, ReportType, SolutionListener};
+use crate::util::metrics::lower_bound_width;
+use crate::util::terminator::{CombinedTerminator, Terminator, TimedTerminator};
+use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
+use log::info;
+use rand::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::time::Duration;
+
+/// Searches the minimum strip width at which the instance can be feasibly packed, using a binary search over widths.
+/// The upper bound is the width of the initial constructed solution, the lower bound is given by [`lower_bound_width`].
+/// Every width is tested by trying to separate the best feasible solution (compressed to the width) for at most `per_width_budget`.
+/// Stops when the bounds are within `tolerance` (relative to the best feasible width) of each other.
+/// Returns the best feasible width and accompanying solution.
+pub fn bisect_min_width(
+    instance: SPInstance,
+    mut rng: Xoshiro256PlusPlus,
+    tolerance: f32,
+    per_width_budget: Duration,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    sep_config: SeparatorConfig,
+) -> (f32, SPSolution) {
+    assert!(tolerance > 0.0, "tolerance should be positive");
+    let mut next_rng = || Xoshiro256PlusPlus::seed_from_u64(rng.next_u64());
+    let builder = LBFBuilder::new(instance.clone(), next_rng(), LBF_SAMPLE_CONFIG).construct();
+    let mut sep = Separator::new(builder.instance, builder.prob, next_rng(), sep_config);
+
+    let mut best = sep.prob.save();
+    sol_listener.report_with_meta(
+        ReportType::ExplFeas,
+        &best,
+        &instance,
+        ReportMeta::new(&best, &instance, 0.0),
+    );
+
+    let mut lower = lower_bound_width(&instance);
+    let mut upper = best.strip_width();
+
+    info!(
+        "[BSCT] starting binary search in width range [{:.3}, {:.3}]",
+        lower, upper
+    );
+
+    while !terminator.should_terminate() && (upper - lower) > tolerance * upper {
+        let mid = (lower + upper) / 2.0;
+        let width_term = CombinedTerminator::new(
+            terminator.clone(),
+            TimedTerminator::new_duration(per_width_budget),
+        );
+        let r_shrink = 1.0 - mid / best.strip_width();
+        match attempt_to_compress(
+            &mut sep,
+            &best,
+            r_shrink,
+            SplitStrategy::Random,
+            &width_term,
+            sol_listener,
+        ) {
+            Some(sol) => {
+                info!(
+                    "[BSCT] feasible at width {:.3} ({:.3}%)",
+                    mid,
+                    sol.density(&instance) * 100.0
+                );
+                sol_listener.report_with_meta(
+                    ReportType::CmprFeas,
+                    &sol,
+                    &instance,
+                    ReportMeta::new(&sol, &instance, 0.0),
+                );
+                best = sol;
+                upper = mid;
+            }
+            None => {
+                info!("[BSCT] infeasible at width {:.3}", mid);
+                lower = mid;
+            }
+        }
+    }
+
+    info!(
+        "[BSCT] finished, minimum feasible width: {:.3} ({:.3}%), search range: [{:.3}, {:.3}]",
+        best.strip_width(),
+        best.density(&instance) * 100.0,
+        lower,
+        upper
+    );
+    sol_listener.report_with_meta(
+        ReportType::Final,
+        &best,
+        &instance,
+        ReportMeta::new(&best, &instance, 0.0),
+    );
+
+    (best.strip_width(), best)
+}
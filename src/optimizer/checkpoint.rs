@@ -0,0 +1,261 @@
+use crate::config::{CompressionConfig, ExplorationConfig};
+use crate::optimizer::report::RunReport;
+use crate::optimizer::snapshot::SnapshotPlacement;
+use crate::optimizer::{optimize_warm_start, optimize_with_report};
+use crate::util::io::write_json;
+use crate::util::listener::{ListenerControl, ReportMeta, ReportType, SolutionListener};
+use crate::util::stability::StablePlacement;
+use crate::util::terminator::Terminator;
+use anyhow::{Context, Result};
+use jagua_rs::Instant;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use log::{Level, info, warn};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Serializable checkpoint of a long-running optimization: the best feasible solution so far
+/// and a random number generator to resume with. See [`resume_from_checkpoint`].
+///
+/// The random state is not the internal state of the optimizer at the time of the checkpoint
+/// (it is not observable from outside), but a stream derived from the seed of the original run,
+/// which does not overlap with the ones of earlier checkpoints. A resumed run is therefore
+/// reproducible, but it does not continue exactly as the original run would have.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub strip_width: f32,
+    pub placements: Vec<SnapshotPlacement>,
+    pub rng: Xoshiro256PlusPlus,
+}
+
+impl Checkpoint {
+    pub fn new(solution: &SPSolution, rng: Xoshiro256PlusPlus) -> Self {
+        let placements = solution
+            .layout_snapshot
+            .placed_items
+            .values()
+            .map(|pi| {
+                let (x, y) = pi.d_transf.translation();
+                SnapshotPlacement {
+                    item_id: pi.item_id,
+                    rotation: pi.d_transf.rotation(),
+                    x,
+                    y,
+                }
+            })
+            .collect();
+        Self {
+            strip_width: solution.strip_width(),
+            placements,
+            rng,
+        }
+    }
+
+    /// Recreates the checkpointed solution for the same `instance`
+    pub fn to_solution(&self, instance: &SPInstance) -> SPSolution {
+        let mut prob = SPProblem::new(instance.clone());
+        prob.change_strip_width(self.strip_width);
+        for p in self.placements.iter() {
+            prob.place_item(SPPlacement {
+                item_id: p.item_id,
+                d_transf: DTransformation::new(p.rotation, (p.x, p.y)),
+            });
+        }
+        prob.save()
+    }
+
+    /// Writes the checkpoint to a temporary file first and then moves it in place,
+    /// so an interruption during the write never corrupts an existing checkpoint.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        write_json(self, &tmp_path, Level::Debug)?;
+        fs::rename(&tmp_path, path).context("could not move checkpoint in place")
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path).context("could not open checkpoint file")?;
+        serde_json::from_reader(BufReader::new(file)).context("not a valid checkpoint")
+    }
+}
+
+/// Same as [`optimize_with_report`], but the best feasible solution is periodically written to `checkpoint_path`
+/// (at most once every `interval`, and always at the end), so the run can be resumed with [`resume_from_checkpoint`].
+#[allow(clippy::too_many_arguments)]
+pub fn optimize_with_checkpoint(
+    instance: SPInstance,
+    rng: Xoshiro256PlusPlus,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    expl_config: &ExplorationConfig,
+    cmpr_config: &CompressionConfig,
+    checkpoint_path: &Path,
+    interval: Duration,
+) -> (SPSolution, RunReport) {
+    let mut ckpt_listener = CheckpointListener::new(sol_listener, checkpoint_path, interval, &rng);
+    optimize_with_report(
+        instance,
+        rng,
+        &mut ckpt_listener,
+        terminator,
+        expl_config,
+        cmpr_config,
+    )
+}
+
+/// Resumes an optimization from the checkpoint at `checkpoint_path` (see [`optimize_with_checkpoint`]).
+/// The separator is rebuilt from the checkpointed solution instead of constructing a new one,
+/// and the run is seeded with the checkpointed random state (see [`Checkpoint`]).
+/// New checkpoints are written to the same path.
+pub fn resume_from_checkpoint(
+    instance: SPInstance,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    expl_config: &ExplorationConfig,
+    cmpr_config: &CompressionConfig,
+    checkpoint_path: &Path,
+    interval: Duration,
+) -> Result<(SPSolution, RunReport)> {
+    let checkpoint = Checkpoint::read(checkpoint_path)?;
+    let solution = checkpoint.to_solution(&instance);
+    info!(
+        "[IO] resuming from checkpoint with width: {:.3}",
+        solution.strip_width()
+    );
+
+    let mut ckpt_listener =
+        CheckpointListener::new(sol_listener, checkpoint_path, interval, &checkpoint.rng);
+    optimize_warm_start(
+        instance,
+        &solution,
+        checkpoint.rng,
+        &mut ckpt_listener,
+        terminator,
+        expl_config,
+        cmpr_config,
+    )
+}
+
+/// Forwards all reports to the inner listener, while periodically writing a [`Checkpoint`] of the narrowest feasible solution
+struct CheckpointListener<'a, L: SolutionListener> {
+    inner: &'a mut L,
+    path: PathBuf,
+    interval: Duration,
+    last_write: Instant,
+    best: Option<SPSolution>,
+    /// Random state stored in the checkpoints: the initial state of the run,
+    /// jumped ahead to a non-overlapping stream on every write
+    rng: Xoshiro256PlusPlus,
+}
+
+impl<'a, L: SolutionListener> CheckpointListener<'a, L> {
+    fn new(inner: &'a mut L, path: &Path, interval: Duration, rng: &Xoshiro256PlusPlus) -> Self {
+        Self {
+            inner,
+            path: path.to_path_buf(),
+            interval,
+            last_write: Instant::now(),
+            best: None,
+            rng: rng.clone(),
+        }
+    }
+
+    fn track(&mut self, report: &ReportType, solution: &SPSolution) {
+        let feasible = matches!(
+            report,
+            ReportType::ExplFeas | ReportType::CmprFeas | ReportType::Final
+        );
+        if feasible
+            && self
+                .best
+                .as_ref()
+                .is_none_or(|b| solution.strip_width() <= b.strip_width())
+        {
+            self.best = Some(solution.clone());
+        }
+        let due = self.last_write.elapsed() >= self.interval;
+        if (due || *report == ReportType::Final)
+            && let Some(best) = &self.best
+        {
+            self.rng.jump();
+            if let Err(e) = Checkpoint::new(best, self.rng.clone()).write(&self.path) {
+                warn!("[IO] failed to write checkpoint: {e:#}");
+            }
+            self.last_write = Instant::now();
+        }
+    }
+}
+
+impl<L: SolutionListener> SolutionListener for CheckpointListener<'_, L> {
+    fn report(&mut self, report: ReportType, solution: &SPSolution, instance: &SPInstance) {
+        self.track(&report, solution);
+        self.inner.report(report, solution, instance);
+    }
+
+    fn report_with_meta(
+        &mut self,
+        report: ReportType,
+        solution: &SPSolution,
+        instance: &SPInstance,
+        meta: ReportMeta,
+    ) {
+        self.track(&report, solution);
+        self.inner.report_with_meta(report, solution, instance, meta);
+    }
+
+    fn control(&self) -> ListenerControl {
+        self.inner.control()
+    }
+
+    fn report_stable(&mut self, placement: StablePlacement) {
+        self.inner.report_stable(placement);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::{TempPath, mixed_rects, quick_config};
+    use crate::util::listener::DummySolListener;
+    use crate::util::terminator::TimedTerminator;
+    use rand::SeedableRng;
+
+    #[test]
+    fn resumed_run_is_at_least_as_good_as_its_checkpoint() {
+        let path = TempPath::new("checkpoint.json");
+        let instance = mixed_rects();
+        let config = quick_config(Duration::from_millis(200));
+        let term = TimedTerminator::new_duration(Duration::from_secs(10));
+        let (solution, _) = optimize_with_checkpoint(
+            instance.clone(),
+            Xoshiro256PlusPlus::seed_from_u64(0),
+            &mut DummySolListener,
+            &term,
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+            &path,
+            Duration::from_millis(50),
+        );
+        let checkpoint = Checkpoint::read(&path).unwrap();
+        assert_eq!(checkpoint.strip_width, solution.strip_width());
+
+        let (resumed, _) = resume_from_checkpoint(
+            instance.clone(),
+            &mut DummySolListener,
+            &term,
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+            &path,
+            Duration::from_millis(50),
+        )
+        .unwrap();
+
+        let n_items = instance.items.iter().map(|(_, q)| *q).sum::<usize>();
+        assert_eq!(resumed.layout_snapshot.placed_items.len(), n_items);
+        assert!(resumed.strip_width() <= checkpoint.strip_width * (1.0 + 1e-4));
+    }
+}
@@ -1,11 +1,20 @@
-use crate::config::{CompressionConfig, ShrinkDecayStrategy};
-use crate::optimizer::separator::Separator;
-use crate::util::listener::{ReportType, SolutionListener};
+use crate::config::{CompressionConfig, Objective, ShrinkDecayStrategy};
+use crate::optimizer::separator::{Separator, SplitStrategy};
+use crate::quantify::tracker::CollisionTracker;
+use crate::util::listener::{
+    DummySolListener, ListenerControl, ReportMeta, ReportType, SolutionListener,
+};
+use crate::util::metrics::SolutionSummary;
 use crate::util::terminator::Terminator;
 use jagua_rs::Instant;
-use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
-use log::info;
-use rand::Rng;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use log::{error, info};
+use ordered_float::OrderedFloat;
+use rand::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::f32::consts::FRAC_PI_2;
 
 /// Algorithm 13 from https://doi.org/10.48550/arXiv.2509.13329
 pub fn compression_phase(
@@ -17,10 +26,22 @@ pub fn compression_phase(
     config: &CompressionConfig,
 ) -> SPSolution {
     let mut best = init.clone();
+    //with the bounding box area objective, the best solution by area is not necessarily the last (narrowest) one
+    let mut best_by_area = init.clone();
     let start = Instant::now();
     let mut n_failed_attempts = 0;
 
-    let shrink_step_size = |n_failed_attempts: i32| -> f32 {
+    //state of the adaptive strategy, grows on success and shrinks on failure
+    let mut adaptive_step = match config.shrink_decay {
+        ShrinkDecayStrategy::Adaptive { min, max, .. } => config.shrink_range.0.clamp(min, max),
+        _ => config.shrink_range.0,
+    };
+    let min_step = match config.shrink_decay {
+        ShrinkDecayStrategy::Adaptive { min, .. } => min,
+        _ => config.shrink_range.1,
+    };
+
+    let shrink_step_size = |n_failed_attempts: i32, adaptive_step: f32| -> f32 {
         match config.shrink_decay {
             ShrinkDecayStrategy::TimeBased => {
                 let range = config.shrink_range.1 - config.shrink_range.0;
@@ -31,26 +52,65 @@ pub fn compression_phase(
             ShrinkDecayStrategy::FailureBased(r) => {
                 config.shrink_range.0 * r.powi(n_failed_attempts)
             }
+            ShrinkDecayStrategy::Adaptive { .. } => adaptive_step,
         }
     };
     while !term.should_terminate()
-        && let step = shrink_step_size(n_failed_attempts)
-        && step >= config.shrink_range.1
+        && sol_listener.control() == ListenerControl::Continue
+        && let step = shrink_step_size(n_failed_attempts, adaptive_step)
+        && step >= min_step
     {
-        match attempt_to_compress(sep, &best, step, term, sol_listener) {
+        match attempt_to_compress(sep, &best, step, config.split_strategy, term, sol_listener) {
+            Some(_) if !tracker_is_consistent(sep) => {
+                error!(
+                    "[CMPR] compressed solution at {:.3}% is reported feasible, but the collision tracker is inconsistent, discarding it",
+                    step * 100.0
+                );
+                n_failed_attempts += 1;
+            }
             Some(compacted_sol) => {
                 info!(
-                    "[CMPR] success at {:.3}% ({:.3} | {:.3}%)",
+                    "[CMPR] success at {:.3}% ({})",
                     step * 100.0,
-                    compacted_sol.strip_width(),
-                    compacted_sol.density(instance) * 100.0
+                    SolutionSummary::of(&compacted_sol, instance)
+                );
+                sol_listener.report_with_meta(
+                    ReportType::CmprFeas,
+                    &compacted_sol,
+                    instance,
+                    ReportMeta::new(&compacted_sol, instance, 0.0),
                 );
-                sol_listener.report(ReportType::CmprFeas, &compacted_sol, instance);
                 best = compacted_sol;
+                if config.objective == Objective::MinBBoxArea
+                    && let Some(height_cmpr_sol) =
+                        attempt_to_compress_height(instance, sep, &best, step, config, term)
+                {
+                    info!(
+                        "[CMPR] height compressed to {:.3} (area: {:.3})",
+                        used_height(&height_cmpr_sol),
+                        bbox_area(&height_cmpr_sol)
+                    );
+                    sol_listener.report_with_meta(
+                        ReportType::CmprFeas,
+                        &height_cmpr_sol,
+                        instance,
+                        ReportMeta::new(&height_cmpr_sol, instance, 0.0),
+                    );
+                    best = height_cmpr_sol;
+                }
+                if bbox_area(&best) < bbox_area(&best_by_area) {
+                    best_by_area = best.clone();
+                }
+                if let ShrinkDecayStrategy::Adaptive { grow, max, .. } = config.shrink_decay {
+                    adaptive_step = (adaptive_step * grow).min(max);
+                }
             }
             None => {
                 info!("[CMPR] failed at {:.3}%", step * 100.0);
                 n_failed_attempts += 1;
+                if let ShrinkDecayStrategy::Adaptive { shrink, .. } = config.shrink_decay {
+                    adaptive_step *= shrink;
+                }
             }
         }
     }
@@ -60,24 +120,40 @@ pub fn compression_phase(
         best.density(instance) * 100.0,
         (best.density(instance) - init.density(instance)) * 100.0
     );
-    best
+    match config.objective {
+        Objective::MinWidth => best,
+        Objective::MinBBoxArea => {
+            info!(
+                "[CMPR] bounding box area reduced from {:.3} to {:.3}",
+                bbox_area(init),
+                bbox_area(&best_by_area)
+            );
+            best_by_area
+        }
+    }
 }
 
-fn attempt_to_compress(
+/// Whether the separator's collision tracker matches its layout and reports no collisions.
+/// Validated in release builds as well: an inconsistent tracker must never yield a 'feasible' solution.
+fn tracker_is_consistent(sep: &Separator) -> bool {
+    sep.ct.total_loss_matches(&sep.prob.layout) && sep.ct.get_total_loss() == 0.0
+}
+
+pub(crate) fn attempt_to_compress(
     sep: &mut Separator,
     init: &SPSolution,
     r_shrink: f32,
+    split: SplitStrategy,
     term: &impl Terminator,
     sol_listener: &mut impl SolutionListener,
 ) -> Option<SPSolution> {
     //restore to the initial solution and width
-    sep.change_strip_width(init.strip_width(), None);
+    sep.change_strip_width(init.strip_width(), SplitStrategy::Center);
     sep.rollback(&init, None);
 
-    //shrink the container at a random position
+    //shrink the container at the position determined by the split strategy
     let new_width = init.strip_width() * (1.0 - r_shrink);
-    let split_pos = sep.rng.random_range(0.0..sep.prob.strip_width());
-    sep.change_strip_width(new_width, Some(split_pos));
+    sep.change_strip_width(new_width, split);
 
     //try to separate layout, if all collisions are eliminated, return the solution
     let (compacted_sol, ot) = sep.separate(term, sol_listener);
@@ -86,3 +162,173 @@ fn attempt_to_compress(
         false => None,
     }
 }
+
+/// Attempts to compress the height of the (feasible) `init` solution by `r_shrink`.
+/// The problem is rotated by 90°, so the height becomes the width of the strip (and vice versa),
+/// compressed with a separator of its own and rotated back. See [`Objective::MinBBoxArea`].
+fn attempt_to_compress_height(
+    instance: &SPInstance,
+    sep: &mut Separator,
+    init: &SPSolution,
+    r_shrink: f32,
+    config: &CompressionConfig,
+    term: &impl Terminator,
+) -> Option<SPSolution> {
+    if !sep.locked_items.is_empty() {
+        //locked items cannot keep their keys (and placement) when the problem is rotated
+        return None;
+    }
+    let rot_instance = rotate_instance(instance, init.strip_width());
+
+    //in the rotated problem, the used height of the solution is the width to compress
+    let mut rot_prob = SPProblem::new(rot_instance.clone());
+    rot_prob.change_strip_width(used_height(init));
+    for pi in init.layout_snapshot.placed_items.values() {
+        rot_prob.place_item(SPPlacement {
+            item_id: pi.item_id,
+            d_transf: rotate_d_transf(pi.d_transf, init.strip_width()),
+        });
+    }
+    let rot_init = rot_prob.save();
+    let rng = Xoshiro256PlusPlus::seed_from_u64(sep.rng.next_u64());
+    let mut rot_sep = Separator::new(rot_instance, rot_prob, rng, config.separator_config);
+    let rot_sol = attempt_to_compress(
+        &mut rot_sep,
+        &rot_init,
+        r_shrink,
+        config.split_strategy,
+        term,
+        &mut DummySolListener,
+    )?;
+
+    //rotate back to the original problem, the strip width is unaffected
+    let mut prob = SPProblem::new(instance.clone());
+    prob.change_strip_width(init.strip_width());
+    for pi in rot_sol.layout_snapshot.placed_items.values() {
+        prob.place_item(SPPlacement {
+            item_id: pi.item_id,
+            d_transf: unrotate_d_transf(pi.d_transf, init.strip_width()),
+        });
+    }
+    //rounding errors of the rotation could introduce tiny collisions
+    let feasible = CollisionTracker::new(&prob.layout).get_total_loss() == 0.0;
+    feasible.then(|| prob.save())
+}
+
+/// Copy of the instance rotated by -90°: the strip is `width` high and the allowed rotations of every item are
+/// offset by -90°, so that every orientation remains the same relative to the original strip.
+fn rotate_instance(instance: &SPInstance, width: f32) -> SPInstance {
+    let mut rotated = instance.clone();
+    rotated.base_strip.fixed_height = width;
+    for (item, _) in rotated.items.iter_mut() {
+        item.allowed_rotation = match &item.allowed_rotation {
+            RotationRange::None => RotationRange::Discrete(vec![-FRAC_PI_2]),
+            RotationRange::Discrete(rs) => {
+                RotationRange::Discrete(rs.iter().map(|r| r - FRAC_PI_2).collect())
+            }
+            RotationRange::Continuous => RotationRange::Continuous,
+        };
+    }
+    rotated
+}
+
+/// Maps a placement in a strip of `width` onto the rotated problem (see [`rotate_instance`]): (x, y) -> (y, width - x)
+fn rotate_d_transf(dt: DTransformation, width: f32) -> DTransformation {
+    let (x, y) = dt.translation();
+    DTransformation::new(dt.rotation() - FRAC_PI_2, (y, width - x))
+}
+
+/// Inverse of [`rotate_d_transf`]: (x, y) -> (width - y, x)
+fn unrotate_d_transf(dt: DTransformation, width: f32) -> DTransformation {
+    let (x, y) = dt.translation();
+    DTransformation::new(dt.rotation() + FRAC_PI_2, (width - y, x))
+}
+
+/// Height of the bounding box of all placed items (measured from the bottom of the strip)
+pub fn used_height(solution: &SPSolution) -> f32 {
+    solution
+        .layout_snapshot
+        .placed_items
+        .values()
+        .map(|pi| OrderedFloat(pi.shape.bbox.y_max))
+        .max()
+        .map_or(0.0, |h| h.0)
+}
+
+/// Area of the bounding box spanned by the strip width and the [`used_height`] of a solution
+pub fn bbox_area(solution: &SPSolution) -> f32 {
+    solution.strip_width() * used_height(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CompressionConfig, DEFAULT_SPARROW_CONFIG};
+    use crate::consts::LBF_SAMPLE_CONFIG;
+    use crate::optimizer::lbf::LBFBuilder;
+    use crate::util::fixtures::rect_instance;
+    use crate::util::terminator::TimedTerminator;
+    use std::time::Duration;
+
+    #[test]
+    fn rotated_placements_map_back_onto_themselves() {
+        let dt = DTransformation::new(FRAC_PI_2, (1.5, 2.5));
+        let back = unrotate_d_transf(rotate_d_transf(dt, 8.0), 8.0);
+        assert!((back.rotation() - dt.rotation()).abs() < 1e-6);
+        assert!((back.translation().0 - 1.5).abs() < 1e-5);
+        assert!((back.translation().1 - 2.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn corrupted_tracker_entry_is_detected() {
+        let instance = rect_instance(10.0, &[(4.0, 3.0, 2), (2.0, 2.0, 4)]);
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let prob = LBFBuilder::new(instance, rng, LBF_SAMPLE_CONFIG).construct().prob;
+        let mut ct = CollisionTracker::new(&prob.layout);
+        assert!(ct.total_loss_matches(&prob.layout));
+
+        //modify an entry behind the tracker's back, without syncing it
+        ct.pair_collisions.data[0].loss = 1.0;
+        assert!(!ct.total_loss_matches(&prob.layout));
+    }
+
+    #[test]
+    fn bbox_area_objective_is_no_worse_than_width_only() {
+        //nine 4x4 squares in a tall strip: every feasible width below 12 requires two columns of five,
+        //so compressing the width alone cannot reduce the bounding box area below 8 x 20
+        let instance = rect_instance(20.0, &[(4.0, 4.0, 9)]);
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let init = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG)
+            .construct()
+            .prob
+            .save();
+        let term = TimedTerminator::new_duration(Duration::from_secs(30));
+
+        let compress = |objective: Objective| {
+            let mut config = CompressionConfig {
+                time_limit: Duration::from_secs(5),
+                shrink_decay: ShrinkDecayStrategy::FailureBased(0.5),
+                shrink_range: (0.1, 0.01),
+                objective,
+                ..DEFAULT_SPARROW_CONFIG.cmpr_cfg
+            };
+            config.separator_config.n_workers = 1;
+            config.separator_config.deterministic = true;
+            let mut prob = SPProblem::new(instance.clone());
+            prob.restore(&init);
+            let rng = Xoshiro256PlusPlus::seed_from_u64(1);
+            let mut sep = Separator::new(instance.clone(), prob, rng, config.separator_config);
+            compression_phase(&instance, &mut sep, &init, &mut DummySolListener, &term, &config)
+        };
+        let width_only = compress(Objective::MinWidth);
+        let min_area = compress(Objective::MinBBoxArea);
+
+        assert!(bbox_area(&min_area) <= bbox_area(&init) * (1.0 + 1e-4));
+        assert!(
+            bbox_area(&min_area) <= bbox_area(&width_only) * (1.0 + 1e-4),
+            "area: {} > width-only area: {}",
+            bbox_area(&min_area),
+            bbox_area(&width_only)
+        );
+    }
+}
@@ -1,13 +1,23 @@
-use crate::config::ExplorationConfig;
+use crate::config::{
+    DisruptionParams, DisruptionStrategy, ExplorationConfig, ShrinkStepStrategy,
+};
+use crate::consts::{
+    ADAPTIVE_SHRINK_SUCCESS_RATES, GAP_FILL_MAX_LEFT_EDGES, MIN_WIDTH_TOLERANCE_RATIO,
+};
 use crate::optimizer::separator::{Separator, SeparatorConfig};
 use crate::sample::uniform_sampler::convert_sample_to_closest_feasible;
-use crate::util::listener::{ReportType, SolutionListener};
+use crate::util::decisions::Decision;
+use crate::util::listener::{ListenerControl, ReportMeta, ReportType, SolutionListener};
+use crate::util::metrics::SolutionSummary;
 use crate::util::terminator::Terminator;
 use float_cmp::approx_eq;
 use itertools::Itertools;
+use jagua_rs::Instant;
 use jagua_rs::collision_detection::hazards::HazardEntity;
 use jagua_rs::entities::{Instance, Layout, PItemKey};
+use jagua_rs::geometry::DTransformation;
 use jagua_rs::geometry::geo_traits::CollidesWith;
+use jagua_rs::geometry::primitives::Rect;
 use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
 use log::{debug, info, warn};
 use ordered_float::OrderedFloat;
@@ -15,63 +25,130 @@ use rand::prelude::{Distribution, IteratorRandom};
 use rand_distr::Normal;
 use slotmap::SecondaryMap;
 use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::iter;
 
 /// Algorithm 12 from https://doi.org/10.48550/arXiv.2509.13329
+/// Returns the increasingly narrow feasible solutions and, if [`ExplorationConfig::keep_best_infeasible`] is set,
+/// the infeasible solution with the lowest total loss at the last attempted width (if any).
 pub fn exploration_phase(
     instance: &SPInstance,
     sep: &mut Separator,
     sol_listener: &mut impl SolutionListener,
     term: &impl Terminator,
     config: &ExplorationConfig,
-) -> Vec<SPSolution> {
+) -> (Vec<SPSolution>, Option<(SPSolution, f32)>) {
+    let start = Instant::now();
     let mut current_width = sep.prob.strip_width();
     let mut best_width = current_width;
+    let min_width = config.min_feasible_width(instance);
 
     let mut feasible_solutions = vec![sep.prob.save()];
 
-    sol_listener.report(ReportType::ExplFeas, &feasible_solutions[0], instance);
+    sol_listener.report_with_meta(
+        ReportType::ExplFeas,
+        &feasible_solutions[0],
+        instance,
+        ReportMeta::new(&feasible_solutions[0], instance, 0.0),
+    );
     info!(
-        "[EXPL] starting optimization with initial width: {:.3} ({:.3}%)",
-        current_width,
-        sep.prob.density() * 100.0
+        "[EXPL] starting optimization with initial solution ({})",
+        SolutionSummary::of(&feasible_solutions[0], instance)
     );
 
     let mut solution_pool: Vec<(SPSolution, f32)> = vec![];
 
-    while !term.should_terminate() {
+    //state of the adaptive shrink step: the current step and the outcomes of the most recent attempts
+    let mut shrink_step = config.shrink_step;
+    let mut recent_outcomes: VecDeque<bool> = VecDeque::new();
+
+    //moment at which the best feasible width last improved
+    let mut last_improvement = Instant::now();
+
+    while !term.should_terminate() && sol_listener.control() == ListenerControl::Continue {
+        if let Some(timeout) = config.stagnation_timeout
+            && last_improvement.elapsed() > timeout
+        {
+            info!(
+                "[EXPL] best width ({:.3}) has not improved for {:?}, terminating",
+                best_width, timeout
+            );
+            break;
+        }
+
         let local_best = sep.separate(term, sol_listener);
         let total_loss = local_best.1.get_total_loss();
 
+        if let ShrinkStepStrategy::Adaptive {
+            window,
+            grow,
+            shrink,
+            min,
+            max,
+        } = config.shrink_step_strategy
+        {
+            recent_outcomes.push_back(total_loss == 0.0);
+            if recent_outcomes.len() > window {
+                recent_outcomes.pop_front();
+            }
+            if recent_outcomes.len() == window {
+                let n_successes = recent_outcomes.iter().filter(|s| **s).count();
+                let success_rate = n_successes as f32 / window as f32;
+                let (high, low) = ADAPTIVE_SHRINK_SUCCESS_RATES;
+                if success_rate > high {
+                    shrink_step = (shrink_step * grow).clamp(min, max);
+                } else if success_rate < low {
+                    shrink_step = (shrink_step * shrink).clamp(min, max);
+                }
+                debug!("[EXPL] success rate: {:.2}, shrink step: {}", success_rate, shrink_step);
+            }
+        }
+
         if total_loss == 0.0 {
             //layout is successfully separated
             if current_width < best_width {
                 info!(
-                    "[EXPL] feasible solution found! (width: {:.3}, dens: {:.3}%)",
-                    current_width,
-                    sep.prob.density() * 100.0
+                    "[EXPL] feasible solution found! ({})",
+                    SolutionSummary::of(&local_best.0, instance)
                 );
                 best_width = current_width;
+                last_improvement = Instant::now();
                 feasible_solutions.push(local_best.0.clone());
-                sol_listener.report(ReportType::ExplFeas, &local_best.0, instance);
+                sol_listener.report_with_meta(
+                    ReportType::ExplFeas,
+                    &local_best.0,
+                    instance,
+                    ReportMeta::new(&local_best.0, instance, 0.0),
+                );
+            }
+            if current_width <= min_width * (1.0 + MIN_WIDTH_TOLERANCE_RATIO) {
+                info!(
+                    "[EXPL] minimum feasible width reached ({:.3}), terminating",
+                    min_width
+                );
+                break;
             }
-            let next_width = current_width * (1.0 - config.shrink_step);
+            let next_width = f32::max(current_width * (1.0 - shrink_step), min_width);
             info!(
                 "[EXPL] shrinking strip by {}%: {:.3} -> {:.3}",
-                config.shrink_step * 100.0,
+                shrink_step * 100.0,
                 current_width,
                 next_width
             );
-            sep.change_strip_width(next_width, None);
+            sep.change_strip_width(next_width, config.split_strategy);
             current_width = next_width;
             solution_pool.clear();
         } else {
             info!(
-                "[EXPL] unable to reach feasibility (width: {:.3}, dens: {:.3}%, min loss: {:.3})",
-                current_width,
-                sep.prob.density() * 100.0,
-                total_loss
+                "[EXPL] unable to reach feasibility ({})",
+                SolutionSummary::of(&local_best.0, instance).with_loss(total_loss)
+            );
+            sol_listener.report_with_meta(
+                ReportType::ExplInfeas,
+                &local_best.0,
+                instance,
+                ReportMeta::new(&local_best.0, instance, total_loss),
             );
-            sol_listener.report(ReportType::ExplInfeas, &local_best.0, instance);
 
             //layout was not successfully separated, add to local bests
             match solution_pool.binary_search_by(|(_, o)| o.partial_cmp(&total_loss).unwrap()) {
@@ -105,22 +182,37 @@ pub fn exploration_phase(
             };
 
             sep.rollback(selected_sol, None);
-            disrupt_solution(sep, config);
+
+            let elapsed_fraction =
+                start.elapsed().as_secs_f32() / config.time_limit.as_secs_f32();
+            let params = config.disruption_schedule.params(elapsed_fraction, config);
+            debug!("[EXPL] disruption params: {:?}", params);
+            for _ in 0..params.n_swaps {
+                match config.disruption_strategy {
+                    DisruptionStrategy::Swap => disrupt_solution(sep, params),
+                    DisruptionStrategy::GapFill => fill_largest_gap(sep),
+                }
+            }
         }
     }
 
     info!(
-        "[EXPL] finished, best feasible solution: width: {:.3} ({:.3}%)",
-        best_width,
-        feasible_solutions.last().unwrap().density(instance) * 100.0
+        "[EXPL] finished, best feasible solution: {}",
+        SolutionSummary::of(feasible_solutions.last().unwrap(), instance)
     );
 
-    feasible_solutions
+    //the pool only contains infeasible solutions of the current width, sorted by loss
+    let best_infeasible = match config.keep_best_infeasible {
+        true => solution_pool.into_iter().next(),
+        false => None,
+    };
+
+    (feasible_solutions, best_infeasible)
 }
 
-fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
-    if sep.prob.layout.placed_items.len() < 2 {
-        warn!("[DSRP] cannot disrupt solution with less than 2 items");
+fn disrupt_solution(sep: &mut Separator, params: DisruptionParams) {
+    if sep.prob.layout.placed_items.len() < 2 + sep.locked_items.len() {
+        warn!("[DSRP] cannot disrupt solution with less than 2 movable items");
         return;
     }
 
@@ -140,7 +232,7 @@ fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
         .sum();
 
     let cutoff_threshold_area =
-        total_convex_hull_area * config.large_item_ch_area_cutoff_percentile;
+        total_convex_hull_area * params.large_item_ch_area_cutoff_percentile;
 
     // Sort items by convex hull area in descending order.
     let sorted_items_by_ch_area = sep
@@ -179,12 +271,21 @@ fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
         .layout
         .placed_items
         .iter()
+        .filter(|(pk, _)| !sep.locked_items.contains(pk))
         .filter(|(_, pi)| pi.shape.surrogate().convex_hull_area >= ch_area_cutoff);
 
-    //Choose a first item with a large enough convex hull
+    //Choose a first item with a large enough convex hull (or any movable one, if all large items are locked)
     let (pk1, pi1) = large_items
         .clone()
         .choose(&mut sep.rng)
+        .or_else(|| {
+            sep.prob
+                .layout
+                .placed_items
+                .iter()
+                .filter(|(pk, _)| !sep.locked_items.contains(pk))
+                .choose(&mut sep.rng)
+        })
         .expect("[DSRP] failed to choose first item");
 
     //Choose a second item with a large enough convex hull and different enough from the first.
@@ -198,7 +299,7 @@ fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
         .choose(&mut sep.rng)
         .or_else(|| {
             sep.prob.layout.placed_items.iter()
-                .filter(|(pk, _)| *pk != pk1) // Ensure the second item is not the same as the first
+                .filter(|(pk, _)| *pk != pk1 && !sep.locked_items.contains(pk)) // Not the same as the first, nor locked
                 .choose(&mut sep.rng)
         }) // As a fallback, choose any item
         .expect("[EXPL] failed to choose second item for disruption");
@@ -209,14 +310,23 @@ fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
     let dt2_old = pi2.d_transf;
 
     // Make sure the swaps do not violate feasibility (rotation).
-    let dt1_new = convert_sample_to_closest_feasible(dt2_old, sep.prob.instance.item(pi1.item_id));
-    let dt2_new = convert_sample_to_closest_feasible(dt1_old, sep.prob.instance.item(pi2.item_id));
+    let item1 = sep.prob.instance.item(pi1.item_id);
+    let item2 = sep.prob.instance.item(pi2.item_id);
+    let dt1_new = convert_sample_to_closest_feasible(dt2_old, item1, sep.allowed_region(item1.id));
+    let dt2_new = convert_sample_to_closest_feasible(dt1_old, item2, sep.allowed_region(item2.id));
 
     info!(
         "[EXPL] disrupting by swapping two large items (id: {} <-> {})",
         pi1.item_id, pi2.item_id
     );
 
+    if let Some(recorder) = &mut sep.recorder {
+        recorder.record(Decision::Disruption {
+            item_id_1: pi1.item_id,
+            item_id_2: pi2.item_id,
+        });
+    }
+
     let pk1 = sep.move_item(pk1, dt1_new);
     let pk2 = sep.move_item(pk2, dt2_new);
 
@@ -230,7 +340,7 @@ fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
 
         for c1_pk in practically_contained_items(&sep.prob.layout, pk1)
             .into_iter()
-            .filter(|c1_pk| *c1_pk != pk2)
+            .filter(|c1_pk| *c1_pk != pk2 && !sep.locked_items.contains(c1_pk))
         {
             let c1_pi = &sep.prob.layout.placed_items[c1_pk];
 
@@ -241,8 +351,9 @@ fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
                 .decompose();
 
             //Ensure the sure the new position is feasible
+            let c1_item = sep.prob.instance.item(c1_pi.item_id);
             let new_feasible_dt =
-                convert_sample_to_closest_feasible(new_dt, sep.prob.instance.item(c1_pi.item_id));
+                convert_sample_to_closest_feasible(new_dt, c1_item, sep.allowed_region(c1_item.id));
             sep.move_item(c1_pk, new_feasible_dt);
         }
     }
@@ -253,7 +364,7 @@ fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
 
         for c2_pk in practically_contained_items(&sep.prob.layout, pk2)
             .into_iter()
-            .filter(|c2_pk| *c2_pk != pk1)
+            .filter(|c2_pk| *c2_pk != pk1 && !sep.locked_items.contains(c2_pk))
         {
             let c2_pi = &sep.prob.layout.placed_items[c2_pk];
             let new_dt = c2_pi
@@ -263,13 +374,143 @@ fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
                 .decompose();
 
             //make sure the new position is feasible
+            let c2_item = sep.prob.instance.item(c2_pi.item_id);
             let new_feasible_dt =
-                convert_sample_to_closest_feasible(new_dt, sep.prob.instance.item(c2_pi.item_id));
+                convert_sample_to_closest_feasible(new_dt, c2_item, sep.allowed_region(c2_item.id));
             sep.move_item(c2_pk, new_feasible_dt);
         }
     }
 }
 
+/// Disrupts a solution by teleporting a small item into the largest empty region of the layout.
+/// Items whose bounding box fits inside the region are preferred, otherwise the smallest item is moved.
+fn fill_largest_gap(sep: &mut Separator) {
+    let Some(gap) = largest_empty_rect(&sep.prob.layout) else {
+        warn!("[DSRP] no empty region found to fill");
+        return;
+    };
+
+    let fits_in_gap = |bbox: Rect| bbox.width() <= gap.width() && bbox.height() <= gap.height();
+
+    let movable_items = sep
+        .prob
+        .layout
+        .placed_items
+        .iter()
+        .filter(|(pk, _)| !sep.locked_items.contains(pk));
+
+    let Some((pk, pi)) = movable_items
+        .clone()
+        .filter(|(_, pi)| fits_in_gap(pi.shape.bbox))
+        .choose(&mut sep.rng)
+        .or_else(|| movable_items.min_by_key(|(_, pi)| OrderedFloat(pi.shape.area)))
+    else {
+        warn!("[DSRP] no movable item to fill the empty region with");
+        return;
+    };
+
+    // Translate the item such that its bounding box is centered in the empty region
+    let bbox = pi.shape.bbox;
+    let (tx, ty) = pi.d_transf.translation();
+    let dx = (gap.x_min + gap.x_max) / 2.0 - (bbox.x_min + bbox.x_max) / 2.0;
+    let dy = (gap.y_min + gap.y_max) / 2.0 - (bbox.y_min + bbox.y_max) / 2.0;
+    let new_dt = DTransformation::new(pi.d_transf.rotation(), (tx + dx, ty + dy));
+
+    //make sure the new position is feasible
+    let item = sep.prob.instance.item(pi.item_id);
+    let new_feasible_dt =
+        convert_sample_to_closest_feasible(new_dt, item, sep.allowed_region(item.id));
+
+    info!(
+        "[EXPL] disrupting by moving item (id: {}) into the largest empty region ({:.3} x {:.3})",
+        pi.item_id,
+        gap.width(),
+        gap.height()
+    );
+
+    sep.move_item(pk, new_feasible_dt);
+}
+
+/// Finds the largest axis-aligned rectangle within the container that does not intersect any placed item's bounding box.
+/// Every pair of candidate left and right edges (taken from the bounding boxes) defines a vertical slab,
+/// in which the largest vertical gap between the bounding boxes overlapping the slab is searched.
+/// For every left edge, the slab is swept to the right while the overlapping bounding boxes are collected incrementally.
+/// With more than [`GAP_FILL_MAX_LEFT_EDGES`] left edges, only an evenly spaced subset of them is considered,
+/// bounding the scan to O(k·n²) for n items, instead of O(n³).
+fn largest_empty_rect(layout: &Layout) -> Option<Rect> {
+    let c_bbox = layout.container.outer_cd.bbox;
+    let bboxes = layout
+        .placed_items
+        .values()
+        .map(|pi| pi.shape.bbox)
+        .sorted_by_key(|b| OrderedFloat(b.x_min))
+        .collect_vec();
+
+    let left_edges = iter::once(c_bbox.x_min)
+        .chain(bboxes.iter().map(|b| b.x_max))
+        .filter(|x| *x >= c_bbox.x_min && *x < c_bbox.x_max)
+        .sorted_by_key(|x| OrderedFloat(*x))
+        .dedup()
+        .collect_vec();
+    let right_edges = bboxes
+        .iter()
+        .map(|b| b.x_min)
+        .chain(iter::once(c_bbox.x_max))
+        .filter(|x| *x > c_bbox.x_min && *x <= c_bbox.x_max)
+        .sorted_by_key(|x| OrderedFloat(*x))
+        .dedup()
+        .collect_vec();
+
+    let stride = left_edges.len().div_ceil(GAP_FILL_MAX_LEFT_EDGES).max(1);
+
+    let mut best: Option<(f32, Rect)> = None;
+    for &x_min in left_edges.iter().step_by(stride) {
+        //bounding boxes overlapping the slab (sorted by y_min), only grows as the slab widens
+        let mut blocked: Vec<(f32, f32)> = vec![];
+        let mut to_block = bboxes.iter().filter(|b| b.x_max > x_min).peekable();
+        for &x_max in right_edges.iter().filter(|x| **x > x_min) {
+            while let Some(b) = to_block.next_if(|b| b.x_min < x_max) {
+                let idx = blocked.partition_point(|(y_min, _)| *y_min < b.y_min);
+                blocked.insert(idx, (b.y_min, b.y_max));
+            }
+            let (y_min, y_max) = largest_gap(blocked.iter().copied(), c_bbox.y_min, c_bbox.y_max);
+
+            // Widening the slab can only shrink the gap, stop once it can no longer beat the best one
+            let best_area = best.map_or(0.0, |(area, _)| area);
+            if (y_max - y_min) * (c_bbox.x_max - x_min) <= best_area {
+                break;
+            }
+            let area = (y_max - y_min) * (x_max - x_min);
+            if area > best_area {
+                let rect = Rect {
+                    x_min,
+                    y_min,
+                    x_max,
+                    y_max,
+                };
+                best = Some((area, rect));
+            }
+        }
+    }
+    best.map(|(_, rect)| rect)
+}
+
+/// Largest interval in [lower, upper] not covered by any of the `blocked` intervals (sorted by their start)
+fn largest_gap(blocked: impl Iterator<Item = (f32, f32)>, lower: f32, upper: f32) -> (f32, f32) {
+    let mut best = (lower, lower);
+    let mut cursor = lower;
+    for (start, end) in blocked {
+        if start - cursor > best.1 - best.0 {
+            best = (cursor, start);
+        }
+        cursor = cursor.max(end);
+    }
+    if upper - cursor > best.1 - best.0 {
+        best = (cursor, upper);
+    }
+    best
+}
+
 /// Collects all items which point of inaccessibility (POI) is contained by pk_c's shape.
 fn practically_contained_items(layout: &Layout, pk_c: PItemKey) -> Vec<PItemKey> {
     let pi_c = &layout.placed_items[pk_c];
@@ -294,3 +535,84 @@ fn practically_contained_items(layout: &Layout, pk_c: PItemKey) -> Vec<PItemKey>
         })
         .collect_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::LBF_SAMPLE_CONFIG;
+    use crate::optimizer::lbf::LBFBuilder;
+    use crate::util::fixtures::{mixed_rects, quick_config};
+    use crate::util::listener::DummySolListener;
+    use crate::util::terminator::TimedTerminator;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use std::time::Duration;
+
+    /// Exhaustive reference of [`largest_empty_rect`]: the area of the largest empty rectangle
+    fn largest_empty_area_exhaustive(layout: &Layout) -> f32 {
+        let c_bbox = layout.container.outer_cd.bbox;
+        let bboxes = layout.placed_items.values().map(|pi| pi.shape.bbox).collect_vec();
+        let left_edges = iter::once(c_bbox.x_min).chain(bboxes.iter().map(|b| b.x_max));
+        let right_edges = || iter::once(c_bbox.x_max).chain(bboxes.iter().map(|b| b.x_min));
+        left_edges
+            .cartesian_product(right_edges().collect_vec())
+            .filter(|(x_min, x_max)| {
+                x_min < x_max && *x_min >= c_bbox.x_min && *x_max <= c_bbox.x_max
+            })
+            .map(|(x_min, x_max)| {
+                let blocked = bboxes
+                    .iter()
+                    .filter(|b| b.x_min < x_max && b.x_max > x_min)
+                    .map(|b| (b.y_min, b.y_max))
+                    .sorted_by_key(|(y_min, _)| OrderedFloat(*y_min));
+                let (y_min, y_max) = largest_gap(blocked, c_bbox.y_min, c_bbox.y_max);
+                (y_max - y_min) * (x_max - x_min)
+            })
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn incremental_scan_finds_the_largest_empty_rect() {
+        let instance = mixed_rects();
+        for seed in 0..5 {
+            let rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+            let builder = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct();
+            let mut prob = builder.prob;
+            //widen the strip to leave a large empty region on the right
+            prob.change_strip_width(prob.strip_width() * 1.5);
+            let gap = largest_empty_rect(&prob.layout).expect("widened strip has an empty region");
+            let expected = largest_empty_area_exhaustive(&prob.layout);
+            let area = gap.width() * gap.height();
+            let matches = (area - expected).abs() <= expected * 1e-4;
+            assert!(matches, "seed {seed}: {area} vs {expected}");
+            let overlaps = |bbox: Rect| {
+                Rect::intersection(bbox, gap).is_some_and(|r| r.width() * r.height() > 1e-4)
+            };
+            assert!(
+                prob.layout.placed_items.values().all(|pi| !overlaps(pi.shape.bbox)),
+                "the empty rectangle should not overlap any item"
+            );
+        }
+    }
+
+    #[test]
+    fn stagnation_ends_the_exploration_before_its_time_limit() {
+        let instance = mixed_rects();
+        let mut config = quick_config(Duration::from_secs(60)).expl_cfg;
+        config.stagnation_timeout = Some(Duration::from_millis(200));
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let prob = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct().prob;
+        let rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let mut sep = Separator::new(instance.clone(), prob, rng, config.separator_config);
+        let term = TimedTerminator::new_duration(config.time_limit);
+
+        let start = Instant::now();
+        let (solutions, _) =
+            exploration_phase(&instance, &mut sep, &mut DummySolListener, &term, &config);
+        assert!(start.elapsed() < Duration::from_secs(20), "took {:?}", start.elapsed());
+        assert!(!term.should_terminate());
+        //the rectangles cannot be packed without waste, so the minimum width was not what ended it
+        let min_width = config.min_feasible_width(&instance) * (1.0 + MIN_WIDTH_TOLERANCE_RATIO);
+        assert!(solutions.last().unwrap().strip_width() > min_width);
+    }
+}
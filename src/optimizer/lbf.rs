@@ -2,14 +2,17 @@ use crate::eval::lbf_evaluator::LBFEvaluator;
 use crate::eval::sample_eval::SampleEval;
 use crate::sample::search::{SampleConfig, search_placement};
 use crate::util::assertions;
+use anyhow::{Result, ensure};
 use itertools::Itertools;
 use jagua_rs::Instant;
-use jagua_rs::entities::Instance;
-use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem};
-use log::debug;
+use jagua_rs::entities::{Instance, PItemKey};
+use jagua_rs::geometry::primitives::Rect;
+use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use log::{debug, info};
 use ordered_float::OrderedFloat;
 use rand_xoshiro::Xoshiro256PlusPlus;
 use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 use std::iter;
 
 pub struct LBFBuilder {
@@ -17,6 +20,14 @@ pub struct LBFBuilder {
     pub prob: SPProblem,
     pub rng: Xoshiro256PlusPlus,
     pub sample_config: SampleConfig,
+    /// Ratio by which the strip is widened after fitting it to the placed items
+    pub fit_strip_slack: f32,
+    /// Placed items which keep their placement during optimization, see [`LBFBuilder::with_locked_items`]
+    pub locked_items: HashSet<PItemKey>,
+    /// Penalty factor per item id used during optimization, see [`LBFBuilder::with_penalty_factors`]
+    pub penalty_factors: Vec<f32>,
+    /// Keep-in zone per item id used during optimization, see [`LBFBuilder::with_allowed_regions`]
+    pub allowed_regions: HashMap<usize, Rect>,
 }
 
 impl LBFBuilder {
@@ -28,6 +39,111 @@ impl LBFBuilder {
             prob,
             rng,
             sample_config,
+            fit_strip_slack: 0.0,
+            locked_items: HashSet::new(),
+            penalty_factors: vec![],
+            allowed_regions: HashMap::new(),
+        }
+    }
+
+    /// Starts from the placements of an existing solution (warm start), instead of constructing one with [`LBFBuilder::construct`].
+    /// Fails if the solution does not contain exactly the demanded items of the instance.
+    pub fn from_solution(
+        instance: SPInstance,
+        solution: &SPSolution,
+        rng: Xoshiro256PlusPlus,
+        sample_config: SampleConfig,
+    ) -> Result<Self> {
+        let mut builder = Self::new(instance, rng, sample_config);
+
+        let placed_qtys = solution
+            .layout_snapshot
+            .placed_items
+            .values()
+            .map(|pi| pi.item_id)
+            .counts();
+        for (item_id, &demand) in builder.prob.item_demand_qtys.iter().enumerate() {
+            let placed = placed_qtys.get(&item_id).copied().unwrap_or(0);
+            ensure!(
+                placed == demand,
+                "warm start solution contains {placed} copies of item {item_id}, while the instance demands {demand}"
+            );
+        }
+        ensure!(
+            placed_qtys.len() <= builder.instance.items.len(),
+            "warm start solution contains items which are not part of the instance"
+        );
+
+        builder.prob.change_strip_width(solution.strip_width());
+        for pi in solution.layout_snapshot.placed_items.values() {
+            builder.prob.place_item(SPPlacement {
+                item_id: pi.item_id,
+                d_transf: pi.d_transf,
+            });
+        }
+        info!(
+            "[CONSTR] warm started from solution with width: {:.3}",
+            builder.prob.strip_width()
+        );
+        Ok(builder)
+    }
+
+    pub fn with_fit_strip_slack(mut self, fit_strip_slack: f32) -> Self {
+        assert!(fit_strip_slack >= 0.0, "fit strip slack should be non-negative");
+        self.fit_strip_slack = fit_strip_slack;
+        self
+    }
+
+    /// Locks placed items (e.g. pre-placed parts) in place for the remainder of the optimization.
+    /// Only respected when the builder is optimized directly, see [`crate::optimizer::optimize_from_builder`].
+    /// Restarts other than the first construct their solution around the locked items, see [`LBFBuilder::locked_only`].
+    pub fn with_locked_items(mut self, pks: impl IntoIterator<Item = PItemKey>) -> Self {
+        for pk in pks {
+            assert!(
+                self.prob.layout.placed_items.contains_key(pk),
+                "cannot lock item {pk:?}, it is not placed"
+            );
+            self.locked_items.insert(pk);
+        }
+        self
+    }
+
+    /// Scales the collision penalty of items (indexed by item id) during optimization.
+    /// These apply to every restart. See [`crate::quantify::separation_penalty_factors`].
+    pub fn with_penalty_factors(mut self, penalty_factors: Vec<f32>) -> Self {
+        self.penalty_factors = penalty_factors;
+        self
+    }
+
+    /// Restricts items (by id) to a region of the strip during optimization (not during construction).
+    /// See [`crate::optimizer::separator::Separator::set_allowed_regions`].
+    pub fn with_allowed_regions(mut self, allowed_regions: HashMap<usize, Rect>) -> Self {
+        self.allowed_regions = allowed_regions;
+        self
+    }
+
+    /// A new builder which only keeps the locked items of this one, all other items are left for [`LBFBuilder::construct`].
+    /// Penalty factors and allowed regions are carried over.
+    pub fn locked_only(&self, rng: Xoshiro256PlusPlus) -> Self {
+        let mut prob = self.prob.clone();
+        let unlocked = prob
+            .layout
+            .placed_items
+            .keys()
+            .filter(|pk| !self.locked_items.contains(pk))
+            .collect_vec();
+        for pk in unlocked {
+            prob.remove_item(pk);
+        }
+        Self {
+            instance: self.instance.clone(),
+            prob,
+            rng,
+            sample_config: self.sample_config,
+            fit_strip_slack: self.fit_strip_slack,
+            locked_items: self.locked_items.clone(),
+            penalty_factors: self.penalty_factors.clone(),
+            allowed_regions: self.allowed_regions.clone(),
         }
     }
 
@@ -55,6 +171,11 @@ impl LBFBuilder {
         }
 
         self.prob.fit_strip();
+        if self.fit_strip_slack > 0.0 {
+            //leave some room to the right of the rightmost item
+            let padded_width = self.prob.strip_width() * (1.0 + self.fit_strip_slack);
+            self.prob.change_strip_width(padded_width);
+        }
         debug!(
             "[CONSTR] placed all items in width: {:.3} (in {:?})",
             self.prob.strip_width(),
@@ -96,12 +217,15 @@ impl LBFBuilder {
         let item = self.instance.item(item_id);
         let evaluator = LBFEvaluator::new(layout, item);
 
-        let (best_sample, _) = search_placement(
+        let (best_sample, ..) = search_placement(
             layout,
             item,
             None,
             evaluator,
             self.sample_config,
+            None,
+            &[],
+            None,
             &mut self.rng,
         );
 
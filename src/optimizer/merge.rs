@@ -0,0 +1,110 @@
+use anyhow::{Result, ensure};
+use float_cmp::approx_eq;
+use itertools::Itertools;
+use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use log::info;
+
+/// Combines two partial solutions of the same instance side by side into a single problem.
+/// The items of `b` are placed to the right of those of `a`, offset by the strip width of `a`.
+/// Together, both solutions should contain exactly the demanded items of the instance,
+/// and both should have the strip height of the instance. Fails otherwise.
+/// The returned problem can be used as a starting point for the compression phase.
+pub fn merge_solutions(a: SPSolution, b: SPSolution, instance: &SPInstance) -> Result<SPProblem> {
+    let strip_height = instance.base_strip.fixed_height;
+    for (name, sol) in [("a", &a), ("b", &b)] {
+        let height = sol.layout_snapshot.container.outer_cd.bbox.height();
+        ensure!(
+            approx_eq!(f32, height, strip_height, epsilon = 1e-4 * strip_height),
+            "strip height of solution {name} ({height}) differs from the instance ({strip_height})"
+        );
+    }
+
+    let mut prob = SPProblem::new(instance.clone());
+
+    //verify the combined items match the demand of the instance
+    let placed_qtys = a
+        .layout_snapshot
+        .placed_items
+        .values()
+        .chain(b.layout_snapshot.placed_items.values())
+        .map(|pi| pi.item_id)
+        .counts();
+    for (item_id, &demand) in prob.item_demand_qtys.iter().enumerate() {
+        let placed = placed_qtys.get(&item_id).copied().unwrap_or(0);
+        ensure!(
+            placed == demand,
+            "merged solutions contain {placed} copies of item {item_id}, while the instance demands {demand}"
+        );
+    }
+
+    let offset = a.strip_width();
+    prob.change_strip_width(a.strip_width() + b.strip_width());
+
+    for pi in a.layout_snapshot.placed_items.values() {
+        prob.place_item(SPPlacement {
+            item_id: pi.item_id,
+            d_transf: pi.d_transf,
+        });
+    }
+    for pi in b.layout_snapshot.placed_items.values() {
+        let d_transf = pi.d_transf.compose().translate((offset, 0.0)).decompose();
+        prob.place_item(SPPlacement {
+            item_id: pi.item_id,
+            d_transf,
+        });
+    }
+
+    info!(
+        "[MERGE] merged solutions of width {:.3} and {:.3} into {:.3}",
+        a.strip_width(),
+        b.strip_width(),
+        prob.strip_width()
+    );
+
+    Ok(prob)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::LBF_SAMPLE_CONFIG;
+    use crate::optimizer::lbf::LBFBuilder;
+    use crate::util::fixtures::{mixed_rects, rect_instance};
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    fn lbf_solution(instance: &SPInstance) -> SPSolution {
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let builder = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct();
+        builder.prob.save()
+    }
+
+    #[test]
+    fn merges_a_complete_and_an_empty_solution() {
+        let instance = mixed_rects();
+        let full = lbf_solution(&instance);
+        let empty = SPProblem::new(instance.clone()).save();
+        let merged = merge_solutions(full.clone(), empty.clone(), &instance).unwrap();
+        assert_eq!(merged.strip_width(), full.strip_width() + empty.strip_width());
+        assert_eq!(
+            merged.layout.placed_items.len(),
+            full.layout_snapshot.placed_items.len()
+        );
+    }
+
+    #[test]
+    fn rejects_a_demand_mismatch() {
+        let instance = mixed_rects();
+        let full = lbf_solution(&instance);
+        assert!(merge_solutions(full.clone(), full, &instance).is_err());
+    }
+
+    #[test]
+    fn rejects_a_strip_height_mismatch() {
+        let instance = mixed_rects();
+        let taller = rect_instance(20.0, &[(1.0, 1.0, 1)]);
+        let empty = SPProblem::new(instance.clone()).save();
+        let other = SPProblem::new(taller).save();
+        assert!(merge_solutions(empty, other, &instance).is_err());
+    }
+}
@@ -1,78 +1,472 @@
 use crate::config::*;
-use crate::consts::LBF_SAMPLE_CONFIG;
+use crate::consts::{LBF_SAMPLE_CONFIG, MIN_WIDTH_TOLERANCE_RATIO};
 use crate::optimizer::compress::compression_phase;
 use crate::optimizer::explore::exploration_phase;
 use crate::optimizer::lbf::LBFBuilder;
+use crate::optimizer::report::{
+    Phase, PhaseEndReason, PhaseReport, PhaseReportTable, RunReport, SeedTrace,
+};
 use crate::optimizer::separator::Separator;
-use crate::util::listener::{ReportType, SolutionListener};
+use crate::util::listener::{
+    BestSolutionListener, BestSolutionSnapshot, ListenerControl, ReportMeta, ReportType,
+    SolutionListener,
+};
 use crate::util::terminator::{CombinedTerminator, FlagTerminator, Terminator, TimedTerminator};
+use anyhow::Result;
 use event_listener::{Event, Listener};
-use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
+use jagua_rs::Instant;
+use jagua_rs::probs::spp::entities::{SPInstance, SPProblem, SPSolution};
+use log::{info, warn};
 use rand::{RngCore, SeedableRng};
 use rand_xoshiro::Xoshiro256PlusPlus;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
+pub mod aspect;
+pub mod bisect;
+pub mod checkpoint;
 pub mod compress;
 pub mod explore;
 pub mod lbf;
+pub mod merge;
+pub mod preprocess;
+pub mod report;
 pub mod separator;
+pub mod snapshot;
 mod worker;
 
+pub use bisect::bisect_min_width;
+pub use merge::merge_solutions;
+
 ///Algorithm 11 from https://doi.org/10.48550/arXiv.2509.13329
 pub fn optimize(
     instance: SPInstance,
-    mut rng: Xoshiro256PlusPlus,
+    rng: Xoshiro256PlusPlus,
     sol_listener: &mut impl SolutionListener,
     terminator: &impl Terminator,
     expl_config: &ExplorationConfig,
     cmpr_config: &CompressionConfig,
 ) -> SPSolution {
-    let mut next_rng = || Xoshiro256PlusPlus::seed_from_u64(rng.next_u64());
-    let builder = LBFBuilder::new(instance.clone(), next_rng(), LBF_SAMPLE_CONFIG).construct();
+    optimize_with_report(
+        instance,
+        rng,
+        sol_listener,
+        terminator,
+        expl_config,
+        cmpr_config,
+    )
+    .0
+}
 
-    let expl_term = CombinedTerminator::new(
-        terminator.clone(),
-        TimedTerminator::new_duration(expl_config.time_limit),
-    );
-    let mut expl_separator = Separator::new(
-        builder.instance,
-        builder.prob,
-        next_rng(),
-        expl_config.separator_config,
-    );
-    let solutions = exploration_phase(
-        &instance,
-        &mut expl_separator,
+/// Same as [`optimize`], but also reports how much time each phase used and why it ended.
+pub fn optimize_with_phase_reports(
+    instance: SPInstance,
+    rng: Xoshiro256PlusPlus,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    expl_config: &ExplorationConfig,
+    cmpr_config: &CompressionConfig,
+) -> (SPSolution, [PhaseReport; 2]) {
+    let (solution, report) = optimize_with_report(
+        instance,
+        rng,
         sol_listener,
-        &expl_term,
+        terminator,
         expl_config,
+        cmpr_config,
     );
-    let final_explore_sol = solutions.last().unwrap().clone();
+    (solution, report.phases)
+}
+
+/// Same as [`optimize`], but also returns statistics of the run, see [`RunReport`].
+pub fn optimize_with_report(
+    instance: SPInstance,
+    rng: Xoshiro256PlusPlus,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    expl_config: &ExplorationConfig,
+    cmpr_config: &CompressionConfig,
+) -> (SPSolution, RunReport) {
+    optimize_from(
+        instance,
+        None,
+        rng,
+        sol_listener,
+        terminator,
+        expl_config,
+        cmpr_config,
+    )
+}
+
+/// Same as [`optimize_with_report`], but improves an existing (feasible) solution instead of constructing one (warm start).
+/// Only the first restart starts from `initial`, any other restarts construct their own.
+/// Fails if `initial` does not contain exactly the demanded items of the instance.
+pub fn optimize_warm_start(
+    instance: SPInstance,
+    initial: &SPSolution,
+    mut rng: Xoshiro256PlusPlus,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    expl_config: &ExplorationConfig,
+    cmpr_config: &CompressionConfig,
+) -> Result<(SPSolution, RunReport)> {
+    let builder = LBFBuilder::from_solution(
+        instance.clone(),
+        initial,
+        Xoshiro256PlusPlus::seed_from_u64(rng.next_u64()),
+        LBF_SAMPLE_CONFIG,
+    )?;
+    Ok(optimize_from(
+        instance,
+        Some(builder),
+        rng,
+        sol_listener,
+        terminator,
+        expl_config,
+        cmpr_config,
+    ))
+}
+
+/// Same as [`optimize_with_report`], but the first restart starts from the (constructed) `builder` instead of a new one.
+/// Items locked in the builder keep their placement throughout the whole optimization, in every restart
+/// (see [`LBFBuilder::with_locked_items`]). Penalty factors and allowed regions also apply to every restart.
+pub fn optimize_from_builder(
+    builder: LBFBuilder,
+    rng: Xoshiro256PlusPlus,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    expl_config: &ExplorationConfig,
+    cmpr_config: &CompressionConfig,
+) -> (SPSolution, RunReport) {
+    optimize_from(
+        builder.instance.clone(),
+        Some(builder),
+        rng,
+        sol_listener,
+        terminator,
+        expl_config,
+        cmpr_config,
+    )
+}
+
+fn optimize_from(
+    instance: SPInstance,
+    mut warm_start: Option<LBFBuilder>,
+    mut rng: Xoshiro256PlusPlus,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    expl_config: &ExplorationConfig,
+    cmpr_config: &CompressionConfig,
+) -> (SPSolution, RunReport) {
+    //every derived rng is seeded from the master, the seeds are logged and reported to allow replaying a phase
+    let mut next_seed = || rng.next_u64();
+    let penalty_factors = warm_start
+        .as_ref()
+        .map_or(vec![], |builder| builder.penalty_factors.clone());
+    let allowed_regions = warm_start
+        .as_ref()
+        .map_or(HashMap::new(), |builder| builder.allowed_regions.clone());
+    //restarts after the first construct their solution around the locked items of the warm start
+    let locked_start = warm_start
+        .as_ref()
+        .filter(|builder| !builder.locked_items.is_empty())
+        .map(|builder| builder.locked_only(builder.rng.clone()));
+
+    //if compression is disabled, its time budget is reallocated to exploration
+    let expl_time_limit = match cmpr_config.enabled {
+        true => expl_config.time_limit,
+        false => expl_config.time_limit + cmpr_config.time_limit,
+    };
 
-    let cmpr_term = CombinedTerminator::new(
+    let expl_start = Instant::now();
+    let expl_term = CombinedTerminator::new(
         terminator.clone(),
-        TimedTerminator::new_duration(cmpr_config.time_limit),
+        TimedTerminator::new_instant(expl_start + expl_time_limit),
+    );
+
+    //if exploration is disabled, its time budget is reallocated to compression
+    let cmpr_time_limit = match expl_config.enabled {
+        true => cmpr_config.time_limit,
+        false => cmpr_config.time_limit + expl_config.time_limit,
+    };
+
+    let (mut expl_iterations, mut n_feasible_widths) = (0, 0);
+    let (mut total_moves, mut total_evals) = (0, 0);
+    let (mut expl_separator, final_explore_sol, best_infeasible, expl_report, expl_seeds) = match expl_config.enabled {
+        true => {
+            //every restart explores independently from a new initial solution, with an equal share of the time budget
+            let n_restarts = expl_config.n_restarts.max(1);
+            let restart_config = ExplorationConfig {
+                time_limit: expl_time_limit / n_restarts as u32,
+                ..*expl_config
+            };
+            let mut best_expl: Option<(Separator, SPSolution, Option<(SPSolution, f32)>, _)> = None;
+            for restart in 0..n_restarts {
+                if best_expl.is_some()
+                    && (expl_term.should_terminate()
+                        || sol_listener.control() == ListenerControl::Stop)
+                {
+                    break;
+                }
+                let (builder, lbf_seed) = match (warm_start.take(), &locked_start) {
+                    (Some(builder), _) => (builder, None),
+                    (None, Some(locked_start)) => {
+                        let lbf_seed = next_seed();
+                        info!("[CONSTR] LBF builder seed: {lbf_seed} (around locked items)");
+                        let builder = locked_start
+                            .locked_only(Xoshiro256PlusPlus::seed_from_u64(lbf_seed))
+                            .with_fit_strip_slack(expl_config.fit_strip_slack)
+                            .construct();
+                        (builder, Some(lbf_seed))
+                    }
+                    (None, None) => {
+                        let lbf_seed = next_seed();
+                        (construct_lbf(&instance, lbf_seed, expl_config), Some(lbf_seed))
+                    }
+                };
+                let restart_term = CombinedTerminator::new(
+                    expl_term.clone(),
+                    TimedTerminator::new_duration(restart_config.time_limit),
+                );
+                let expl_seed = next_seed();
+                info!("[EXPL] separator seed: {expl_seed}");
+                let mut separator = Separator::new(
+                    builder.instance,
+                    builder.prob,
+                    Xoshiro256PlusPlus::seed_from_u64(expl_seed),
+                    expl_config.separator_config,
+                );
+                separator.set_penalty_factors(penalty_factors.clone());
+                separator.lock_items(builder.locked_items);
+                separator.set_allowed_regions(allowed_regions.clone());
+                let (solutions, best_infeasible) = exploration_phase(
+                    &instance,
+                    &mut separator,
+                    sol_listener,
+                    &restart_term,
+                    &restart_config,
+                );
+                let restart_sol = solutions.last().unwrap().clone();
+                expl_iterations += separator.n_separations;
+                n_feasible_widths += solutions.len() - 1;
+                total_moves += separator.stats.total_moves;
+                total_evals += separator.stats.total_evals;
+                if n_restarts > 1 {
+                    info!(
+                        "[EXPL] restart {}/{} finished with width: {:.3}",
+                        restart + 1,
+                        n_restarts,
+                        restart_sol.strip_width()
+                    );
+                }
+                let is_best = best_expl
+                    .as_ref()
+                    .is_none_or(|(_, best, ..)| restart_sol.strip_width() < best.strip_width());
+                if is_best {
+                    let seeds = (lbf_seed, expl_seed);
+                    best_expl = Some((separator, restart_sol, best_infeasible, seeds));
+                }
+            }
+            let (mut expl_separator, final_explore_sol, best_infeasible, expl_seeds) =
+                best_expl.expect("at least one exploration should have run");
+            let target_reached = final_explore_sol.strip_width()
+                <= expl_config.min_feasible_width(&instance) * (1.0 + MIN_WIDTH_TOLERANCE_RATIO);
+            let expl_report = PhaseReport {
+                phase: Phase::Exploration,
+                requested_time: expl_time_limit,
+                actual_time: expl_start.elapsed(),
+                end_reason: phase_end_reason(terminator, &expl_term, sol_listener, target_reached),
+            };
+            (expl_separator, final_explore_sol, best_infeasible, expl_report, expl_seeds)
+        }
+        false => {
+            let (builder, lbf_seed) = match warm_start.take() {
+                Some(builder) => (builder, None),
+                None => {
+                    warn!("[EXPL] exploration disabled without a warm start, compressing a constructed solution");
+                    let lbf_seed = next_seed();
+                    (construct_lbf(&instance, lbf_seed, expl_config), Some(lbf_seed))
+                }
+            };
+            info!("[EXPL] exploration disabled, skipping phase");
+            let expl_seed = next_seed();
+            let mut expl_separator = Separator::new(
+                builder.instance,
+                builder.prob,
+                Xoshiro256PlusPlus::seed_from_u64(expl_seed),
+                expl_config.separator_config,
+            );
+            expl_separator.set_penalty_factors(penalty_factors.clone());
+            expl_separator.lock_items(builder.locked_items);
+            expl_separator.set_allowed_regions(allowed_regions.clone());
+            let initial_sol = expl_separator.prob.save();
+            let expl_report = PhaseReport {
+                phase: Phase::Exploration,
+                requested_time: Duration::ZERO,
+                actual_time: Duration::ZERO,
+                end_reason: PhaseEndReason::Skipped,
+            };
+            (expl_separator, initial_sol, None, expl_report, (lbf_seed, expl_seed))
+        }
+    };
+
+    let expl_decisions = expl_separator.recorder.take();
+    let (cmpr_sol, cmpr_report, cmpr_seed, cmpr_decisions) = match cmpr_config.enabled {
+        true => {
+            let cmpr_start = Instant::now();
+            let cmpr_term = CombinedTerminator::new(
+                terminator.clone(),
+                TimedTerminator::new_duration(cmpr_time_limit),
+            );
+            let cmpr_seed = next_seed();
+            info!("[CMPR] separator seed: {cmpr_seed}");
+            let mut cmpr_separator = Separator::new(
+                expl_separator.instance,
+                expl_separator.prob,
+                Xoshiro256PlusPlus::seed_from_u64(cmpr_seed),
+                cmpr_config.separator_config,
+            );
+            cmpr_separator.set_penalty_factors(penalty_factors);
+            let pinned = &expl_separator.pinned_items;
+            cmpr_separator.lock_items(
+                expl_separator.locked_items.iter().filter(|pk| !pinned.contains(pk)).copied(),
+            );
+            cmpr_separator.pin_stable(expl_separator.stable_placements);
+            cmpr_separator.set_allowed_regions(allowed_regions);
+            if let Some(stability) = &mut cmpr_separator.stability
+                && let Some(expl_stability) = expl_separator.stability.take()
+            {
+                //avoid reporting placements which were already reported during exploration
+                stability.carry_over(expl_stability);
+            }
+            let cmpr_sol = compression_phase(
+                &instance,
+                &mut cmpr_separator,
+                &final_explore_sol,
+                sol_listener,
+                &cmpr_term,
+                &CompressionConfig {
+                    time_limit: cmpr_time_limit,
+                    ..*cmpr_config
+                },
+            );
+            let cmpr_report = PhaseReport {
+                phase: Phase::Compression,
+                requested_time: cmpr_time_limit,
+                actual_time: cmpr_start.elapsed(),
+                end_reason: phase_end_reason(terminator, &cmpr_term, sol_listener, false),
+            };
+            total_moves += cmpr_separator.stats.total_moves;
+            total_evals += cmpr_separator.stats.total_evals;
+            let cmpr_decisions = cmpr_separator.recorder.take();
+            (cmpr_sol, cmpr_report, Some(cmpr_seed), cmpr_decisions)
+        }
+        false => {
+            info!("[CMPR] compression disabled, skipping phase");
+            let cmpr_report = PhaseReport {
+                phase: Phase::Compression,
+                requested_time: Duration::ZERO,
+                actual_time: Duration::ZERO,
+                end_reason: PhaseEndReason::Skipped,
+            };
+            (final_explore_sol, cmpr_report, None, None)
+        }
+    };
+
+    sol_listener.report_with_meta(
+        ReportType::Final,
+        &cmpr_sol,
+        &instance,
+        ReportMeta::new(&cmpr_sol, &instance, 0.0),
     );
+
+    //an infeasible solution is of no use if compression found a feasible one which is at least as narrow
+    let best_infeasible =
+        best_infeasible.filter(|(sol, _)| sol.strip_width() < cmpr_sol.strip_width());
+
+    let run_report = RunReport {
+        expl_iterations,
+        n_feasible_widths,
+        total_moves,
+        total_evals,
+        phases: [expl_report, cmpr_report],
+        seeds: SeedTrace {
+            lbf: expl_seeds.0,
+            exploration: expl_seeds.1,
+            compression: cmpr_seed,
+        },
+        best_infeasible,
+        time_split: None,
+        decisions: [expl_decisions, cmpr_decisions],
+    };
+    info!("[OPT] time usage per phase:\n{}", PhaseReportTable(&run_report.phases));
+    (cmpr_sol, run_report)
+}
+
+/// Constructs an initial solution with the LBF builder, seeded with `lbf_seed`
+fn construct_lbf(
+    instance: &SPInstance,
+    lbf_seed: u64,
+    expl_config: &ExplorationConfig,
+) -> LBFBuilder {
+    info!("[CONSTR] LBF builder seed: {lbf_seed}");
+    LBFBuilder::new(
+        instance.clone(),
+        Xoshiro256PlusPlus::seed_from_u64(lbf_seed),
+        LBF_SAMPLE_CONFIG,
+    )
+    .with_fit_strip_slack(expl_config.fit_strip_slack)
+    .construct()
+}
+
+/// Infers why a phase ended from the state of its terminators and the solution listener
+fn phase_end_reason(
+    global_term: &impl Terminator,
+    phase_term: &impl Terminator,
+    sol_listener: &impl SolutionListener,
+    target_reached: bool,
+) -> PhaseEndReason {
+    if global_term.should_terminate() {
+        PhaseEndReason::Killed
+    } else if phase_term.should_terminate() {
+        PhaseEndReason::TimeLimit
+    } else if target_reached || sol_listener.control() == ListenerControl::Stop {
+        PhaseEndReason::TargetReached
+    } else {
+        PhaseEndReason::Stalled
+    }
+}
+
+/// Replays the compression phase of a run from its recorded seed (see [`SeedTrace::compression`]),
+/// starting from the final solution of the exploration phase.
+/// Given the same configuration and a terminator which ends the phase at the same point, the result is identical to the original run.
+/// Locked items and penalty factors of the original run are not replayed.
+pub fn replay_compression(
+    instance: &SPInstance,
+    final_explore_sol: &SPSolution,
+    seed: u64,
+    sol_listener: &mut impl SolutionListener,
+    terminator: &impl Terminator,
+    cmpr_config: &CompressionConfig,
+) -> SPSolution {
+    let mut prob = SPProblem::new(instance.clone());
+    prob.restore(final_explore_sol);
     let mut cmpr_separator = Separator::new(
-        expl_separator.instance,
-        expl_separator.prob,
-        next_rng(),
+        instance.clone(),
+        prob,
+        Xoshiro256PlusPlus::seed_from_u64(seed),
         cmpr_config.separator_config,
     );
-    let cmpr_sol = compression_phase(
-        &instance,
+    compression_phase(
+        instance,
         &mut cmpr_separator,
-        &final_explore_sol,
+        final_explore_sol,
         sol_listener,
-        &cmpr_term,
+        terminator,
         cmpr_config,
-    );
-
-    sol_listener.report(ReportType::Final, &cmpr_sol, &instance);
-
-    cmpr_sol
+    )
 }
 
 #[derive(Debug)]
@@ -80,6 +474,8 @@ pub struct OptimizeWorker {
     terminate_flag: Arc<AtomicBool>,
     waiter: Arc<Event>,
     result: Arc<Mutex<Option<SPSolution>>>,
+    best: BestSolutionSnapshot,
+    instance: SPInstance,
     _thread: std::thread::JoinHandle<()>,
 }
 
@@ -97,6 +493,9 @@ impl OptimizeWorker {
         let thread_waiter = waiter.clone();
         let result = Arc::new(Mutex::new(None));
         let thread_result = result.clone();
+        let worker_instance = instance.clone();
+        let sol_listener = BestSolutionListener::new(sol_listener);
+        let best = sol_listener.snapshot();
 
         let terminator = CombinedTerminator::new(
             terminator,
@@ -124,6 +523,8 @@ impl OptimizeWorker {
             terminate_flag,
             waiter,
             result,
+            best,
+            instance: worker_instance,
             _thread: thread,
         }
     }
@@ -158,7 +559,214 @@ impl OptimizeWorker {
         }
     }
 
+    /// Strip width and density of the best feasible solution reported so far, `None` if none was reported yet.
+    /// Updated from within the worker thread as solutions are reported, so it may lag slightly behind.
+    pub fn current_best(&self) -> Option<(f32, f32)> {
+        self.best
+            .lock()
+            .expect("OptimizeWorker mutex was poisoned")
+            .as_ref()
+            .map(|sol| (sol.strip_width(), sol.density(&self.instance)))
+    }
+
     pub fn terminate(&self) {
         self.terminate_flag.store(true, Ordering::Relaxed);
     }
-}
\ No newline at end of file
+
+    /// The best feasible solution reported so far, `None` if none was reported yet.
+    pub fn best_solution(&self) -> Option<SPSolution> {
+        self.best
+            .lock()
+            .expect("OptimizeWorker mutex was poisoned")
+            .clone()
+    }
+
+    /// Terminates the optimization and waits at most `grace` for it to return its final solution.
+    /// If it does not return in time (e.g. stuck mid-iteration), the best feasible solution reported so far is returned instead,
+    /// so an interrupted run never loses its progress.
+    pub fn terminate_and_wait(&self, grace: Duration) -> Option<SPSolution> {
+        self.terminate();
+        self.wait_timeout(grace).or_else(|| self.best_solution())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::{mixed_rects, quick_config};
+    use crate::util::listener::{DummySolListener, ReportType};
+
+    /// Remembers the type of the last report
+    struct LastReport(Option<ReportType>);
+
+    impl SolutionListener for LastReport {
+        fn report(&mut self, report: ReportType, _: &SPSolution, _: &SPInstance) {
+            self.0 = Some(report);
+        }
+    }
+
+    #[test]
+    fn skipping_exploration_without_warm_start_compresses_a_constructed_solution() {
+        let instance = mixed_rects();
+        let mut config = quick_config(Duration::from_millis(300));
+        config.expl_cfg.enabled = false;
+        let mut listener = LastReport(None);
+        let term = TimedTerminator::new_duration(Duration::from_secs(10));
+        let (solution, report) = optimize_with_report(
+            instance.clone(),
+            Xoshiro256PlusPlus::seed_from_u64(0),
+            &mut listener,
+            &term,
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+        );
+        assert_eq!(listener.0, Some(ReportType::Final));
+        assert_eq!(report.phases[0].end_reason, PhaseEndReason::Skipped);
+        assert!(report.seeds.lbf.is_some());
+        let n_items = instance.items.iter().map(|(_, q)| *q).sum::<usize>();
+        assert_eq!(solution.layout_snapshot.placed_items.len(), n_items);
+    }
+
+    #[test]
+    fn locked_items_keep_their_transformation_in_every_restart() {
+        let instance = mixed_rects();
+        let mut config = quick_config(Duration::from_millis(300));
+        config.expl_cfg.n_restarts = 3;
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let builder = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct();
+        let locked_pk = builder.prob.layout.placed_items.keys().next().unwrap();
+        let locked = &builder.prob.layout.placed_items[locked_pk];
+        let (item_id, d_transf) = (locked.item_id, locked.d_transf);
+
+        let (solution, _) = optimize_from_builder(
+            builder.with_locked_items([locked_pk]),
+            Xoshiro256PlusPlus::seed_from_u64(1),
+            &mut LastReport(None),
+            &TimedTerminator::new_duration(Duration::from_secs(10)),
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+        );
+        let kept = solution.layout_snapshot.placed_items.values().any(|pi| {
+            pi.item_id == item_id
+                && pi.d_transf.translation() == d_transf.translation()
+                && pi.d_transf.rotation() == d_transf.rotation()
+        });
+        assert!(kept, "locked item {item_id} should remain at {d_transf}");
+    }
+
+    #[test]
+    fn interrupted_run_still_exports_a_solution() {
+        let instance = mixed_rects();
+        let config = quick_config(Duration::from_secs(60));
+        //stands in for the flag set by a ctrl-c handler
+        let ctrl_c = Arc::new(AtomicBool::new(false));
+        let worker = OptimizeWorker::new(
+            instance.clone(),
+            Xoshiro256PlusPlus::seed_from_u64(0),
+            DummySolListener,
+            FlagTerminator::of(ctrl_c.clone()),
+            config.expl_cfg,
+            config.cmpr_cfg,
+        );
+        let start = Instant::now();
+        while worker.current_best().is_none() {
+            assert!(start.elapsed() < Duration::from_secs(10), "no feasible solution was reported");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        ctrl_c.store(true, Ordering::Relaxed);
+
+        let solution = worker
+            .wait_timeout(Duration::from_secs(10))
+            .or_else(|| worker.best_solution())
+            .expect("an interrupted run should still yield a solution");
+        let n_items = instance.items.iter().map(|(_, q)| *q).sum::<usize>();
+        assert_eq!(solution.layout_snapshot.placed_items.len(), n_items);
+        let exported = jagua_rs::probs::spp::io::export(&instance, &solution, *crate::EPOCH);
+        let json = serde_json::to_value(&exported).unwrap();
+        assert_eq!(json["strip_width"].as_f64(), Some(solution.strip_width() as f64));
+    }
+
+    /// Remembers the last feasible solution of the exploration phase
+    struct LastExplFeas(Option<SPSolution>);
+
+    impl SolutionListener for LastExplFeas {
+        fn report(&mut self, report: ReportType, solution: &SPSolution, _: &SPInstance) {
+            if report == ReportType::ExplFeas {
+                self.0 = Some(solution.clone());
+            }
+        }
+    }
+
+    /// Placements of a solution as bits, for bit-exact comparisons
+    fn placement_bits(solution: &SPSolution) -> Vec<(usize, [u32; 3])> {
+        let mut bits = solution
+            .layout_snapshot
+            .placed_items
+            .values()
+            .map(|pi| {
+                let (x, y) = pi.d_transf.translation();
+                (pi.item_id, [x.to_bits(), y.to_bits(), pi.d_transf.rotation().to_bits()])
+            })
+            .collect::<Vec<_>>();
+        bits.sort();
+        bits
+    }
+
+    #[test]
+    fn compression_replayed_from_its_seed_is_identical() {
+        let instance = mixed_rects();
+        let mut config = quick_config(Duration::from_millis(300));
+        //end the compression by its failures rather than its time limit, so the replay ends at the same point
+        config.cmpr_cfg.time_limit = Duration::from_secs(60);
+        config.cmpr_cfg.shrink_decay = ShrinkDecayStrategy::FailureBased(0.5);
+        config.cmpr_cfg.shrink_range = (0.05, 0.005);
+        config.cmpr_cfg.separator_config.deterministic = true;
+        let term = TimedTerminator::new_duration(Duration::from_secs(120));
+
+        let mut listener = LastExplFeas(None);
+        let (solution, report) = optimize_with_report(
+            instance.clone(),
+            Xoshiro256PlusPlus::seed_from_u64(0),
+            &mut listener,
+            &term,
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+        );
+        let final_explore_sol = listener.0.expect("exploration should report a feasible solution");
+        let seed = report.seeds.compression.expect("compression should have run");
+
+        let replayed = replay_compression(
+            &instance,
+            &final_explore_sol,
+            seed,
+            &mut DummySolListener,
+            &term,
+            &config.cmpr_cfg,
+        );
+        assert_eq!(replayed.strip_width().to_bits(), solution.strip_width().to_bits());
+        assert_eq!(placement_bits(&replayed), placement_bits(&solution));
+    }
+
+    #[test]
+    fn best_infeasible_solution_is_kept_when_the_strip_is_too_narrow() {
+        let instance = mixed_rects();
+        let mut config = quick_config(Duration::from_millis(300));
+        config.expl_cfg.keep_best_infeasible = true;
+        //without compression, nothing can replace the infeasible solution by a narrower feasible one
+        config.cmpr_cfg.enabled = false;
+        let (solution, report) = optimize_with_report(
+            instance.clone(),
+            Xoshiro256PlusPlus::seed_from_u64(0),
+            &mut DummySolListener,
+            &TimedTerminator::new_duration(Duration::from_secs(10)),
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+        );
+        let (infeasible, total_loss) =
+            report.best_infeasible.expect("a narrower width should have been attempted");
+        let n_items = instance.items.iter().map(|(_, q)| *q).sum::<usize>();
+        assert_eq!(infeasible.layout_snapshot.placed_items.len(), n_items);
+        assert!(total_loss > 0.0);
+        assert!(infeasible.strip_width() < solution.strip_width());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::consts::PREPROCESS_ROT_N_SAMPLES;
+use itertools::Itertools;
+use jagua_rs::entities::Item;
+use jagua_rs::geometry::Transformation;
+use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::geometry::geo_traits::TransformableFrom;
+use jagua_rs::probs::spp::entities::SPInstance;
+use log::info;
+use ordered_float::OrderedFloat;
+use std::f32::consts::PI;
+
+/// Fixes the orientation of every item upfront, to the allowed rotation which minimizes the height of its bounding box.
+/// Returns a copy of the instance where every item has a single allowed (discrete) rotation.
+/// The optimizer then only has to search for positions, which can converge faster for instances
+/// where each item has a strongly preferred orientation.
+pub fn lock_min_height_rotations(instance: &SPInstance) -> SPInstance {
+    let mut locked = instance.clone();
+    for (item, _) in locked.items.iter_mut() {
+        let rotation = min_height_rotation(item);
+        item.allowed_rotation = RotationRange::Discrete(vec![rotation]);
+    }
+    info!(
+        "[PRE] locked rotations of {} items to their minimum bbox height",
+        locked.items.len()
+    );
+    locked
+}
+
+fn min_height_rotation(item: &Item) -> f32 {
+    let candidates = match &item.allowed_rotation {
+        RotationRange::None => vec![0.0],
+        RotationRange::Discrete(r) => r.clone(),
+        RotationRange::Continuous => (0..PREPROCESS_ROT_N_SAMPLES)
+            .map(|i| i as f32 * 2.0 * PI / PREPROCESS_ROT_N_SAMPLES as f32)
+            .collect_vec(),
+    };
+
+    let mut shape_buffer = item.shape_cd.as_ref().clone();
+    candidates
+        .into_iter()
+        .min_by_key(|&r| {
+            let bbox = shape_buffer
+                .transform_from(item.shape_cd.as_ref(), &Transformation::from_rotation(r))
+                .bbox;
+            OrderedFloat(bbox.height())
+        })
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimizer::optimize;
+    use crate::quantify::tracker::CollisionTracker;
+    use crate::util::fixtures::{mixed_rects, quick_config};
+    use crate::util::listener::DummySolListener;
+    use crate::util::terminator::TimedTerminator;
+    use float_cmp::approx_eq;
+    use jagua_rs::entities::Instance;
+    use jagua_rs::probs::spp::entities::SPProblem;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use std::time::Duration;
+
+    fn locked_rotation(instance: &SPInstance, item_id: usize) -> f32 {
+        match &instance.item(item_id).allowed_rotation {
+            RotationRange::Discrete(r) if r.len() == 1 => r[0],
+            r => panic!("item {item_id} should have a single rotation, got {r:?}"),
+        }
+    }
+
+    #[test]
+    fn locks_every_item_to_its_flattest_rotation() {
+        let locked = lock_min_height_rotations(&mixed_rects());
+        //4x3 and 5x1 are already flattest upright, 3x6 has to be turned on its side
+        assert!(approx_eq!(f32, locked_rotation(&locked, 0), 0.0, epsilon = 1e-4));
+        assert!(approx_eq!(f32, locked_rotation(&locked, 2), 0.0, epsilon = 1e-4));
+        assert!(approx_eq!(f32, locked_rotation(&locked, 3), PI / 2.0, epsilon = 1e-4));
+        assert_eq!(locked.items.len(), mixed_rects().items.len());
+    }
+
+    #[test]
+    fn locked_rotations_still_reach_a_feasible_solution() {
+        let config = quick_config(Duration::from_millis(300));
+        let solve = |instance: SPInstance| {
+            let term = TimedTerminator::new_duration(Duration::from_secs(10));
+            let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+            let sol = optimize(
+                instance.clone(),
+                rng,
+                &mut DummySolListener,
+                &term,
+                &config.expl_cfg,
+                &config.cmpr_cfg,
+            );
+            assert_eq!(
+                sol.layout_snapshot.placed_items.len(),
+                instance.items.iter().map(|(_, q)| *q).sum::<usize>()
+            );
+            //feasible: rebuilding the tracker from the solution's layout finds no collisions
+            let mut prob = SPProblem::new(instance.clone());
+            prob.restore(&sol);
+            assert_eq!(CollisionTracker::new(&prob.layout).get_total_loss(), 0.0);
+            sol.density(&instance)
+        };
+        let joint = solve(mixed_rects());
+        let locked = solve(lock_min_height_rotations(&mixed_rects()));
+        //locking the rotations shrinks the search space, but should not cost more than 10 percentage points
+        assert!(
+            locked >= joint - 0.10,
+            "density with locked rotations ({locked:.3}) is far below the joint one ({joint:.3})"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::config::TimeSplit;
+use crate::util::decisions::DecisionRecorder;
+use jagua_rs::probs::spp::entities::SPSolution;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Exploration,
+    Compression,
+}
+
+/// Reason why an optimization phase ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseEndReason {
+    /// The time limit of the phase was reached
+    TimeLimit,
+    /// The phase could not make any further progress (e.g. too many failed attempts)
+    Stalled,
+    /// The phase reached its target (e.g. the minimum width) or was stopped by the solution listener
+    TargetReached,
+    /// The global terminator ended the optimization prematurely
+    Killed,
+    /// The phase was disabled and did not run
+    Skipped,
+}
+
+/// Time usage of a single optimization phase
+#[derive(Debug, Clone, Copy)]
+pub struct PhaseReport {
+    pub phase: Phase,
+    pub requested_time: Duration,
+    pub actual_time: Duration,
+    pub end_reason: PhaseEndReason,
+}
+
+/// Statistics of a complete optimization run, see [`crate::optimizer::optimize_with_report`]
+#[derive(Debug, Clone)]
+pub struct RunReport {
+    /// Number of separations attempted during exploration (over all restarts)
+    pub expl_iterations: usize,
+    /// Number of increasingly narrow feasible solutions found during exploration, excluding the initial one
+    pub n_feasible_widths: usize,
+    /// Total number of item moves performed by the separators of both phases
+    pub total_moves: usize,
+    /// Total number of sample evaluations performed by the separators of both phases
+    pub total_evals: usize,
+    pub phases: [PhaseReport; 2],
+    /// Seeds of the random number generators derived for each phase
+    pub seeds: SeedTrace,
+    /// Infeasible solution (and its total loss) narrower than the final solution, closest to feasibility.
+    /// Only kept if [`crate::config::ExplorationConfig::keep_best_infeasible`] is set. It may contain overlaps.
+    pub best_infeasible: Option<(SPSolution, f32)>,
+    /// How the time budget was divided over both phases, if derived from the instance
+    /// (see [`crate::config::SparrowConfig::time_split`]). Only set by the [`crate::api`] entry points.
+    pub time_split: Option<TimeSplit>,
+    /// Decisions taken by the separators of both phases (in the order of [`RunReport::phases`]),
+    /// if [`crate::optimizer::separator::SeparatorConfig::record_decisions`] was enabled for the phase.
+    /// In case of multiple restarts, the exploration decisions are those of the restart that was continued.
+    /// See [`crate::util::decisions::DecisionReplayer`] to replay them.
+    pub decisions: [Option<DecisionRecorder>; 2],
+}
+
+/// Seeds of the random number generators which were derived from the master rng during a run,
+/// allowing a single phase to be reproduced in isolation (e.g. with [`crate::optimizer::replay_compression`]).
+/// In case of multiple restarts, the seeds are those of the restart that was continued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedTrace {
+    /// Seed of the constructive LBF builder, `None` when warm started
+    pub lbf: Option<u64>,
+    /// Seed of the exploration separator
+    pub exploration: u64,
+    /// Seed of the compression separator, `None` when compression was disabled
+    pub compression: Option<u64>,
+}
+
+/// Formats the reports of multiple phases as a table
+pub struct PhaseReportTable<'a>(pub &'a [PhaseReport]);
+
+impl Display for PhaseReportTable<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<12} | {:>10} | {:>10} | {:>6} | {}",
+            "phase", "requested", "actual", "used", "end reason"
+        )?;
+        for r in self.0 {
+            let usage = match r.requested_time.is_zero() {
+                true => 0.0,
+                false => r.actual_time.as_secs_f32() / r.requested_time.as_secs_f32() * 100.0,
+            };
+            writeln!(
+                f,
+                "{:<12} | {:>9.1}s | {:>9.1}s | {:>5.1}% | {:?}",
+                format!("{:?}", r.phase),
+                r.requested_time.as_secs_f32(),
+                r.actual_time.as_secs_f32(),
+                usage,
+                r.end_reason
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,13 +1,21 @@
+use crate::eval::margin_evaluator::EdgeMargin;
+use crate::eval::sep_evaluator::GravityBias;
+use crate::eval::shape_buffer_pool::ShapeBufferPool;
 use crate::optimizer::Terminator;
 use crate::optimizer::worker::{SepStats, SeparatorWorker};
+use crate::quantify::PenaltyKind;
 use crate::quantify::tracker::{CTSnapshot, CollisionTracker};
 use crate::sample::search::SampleConfig;
+use crate::sample::uniform_sampler::{RotatedBBoxCache, convert_sample_to_closest_feasible};
 use crate::util::assertions::tracker_matches_layout;
-use crate::util::listener::{ReportType, SolutionListener};
+use crate::util::decisions::{Decision, DecisionRecorder};
+use crate::util::listener::{ReportMeta, ReportType, SolutionListener};
+use crate::util::stability::{StabilityTracker, StablePlacement};
 use itertools::Itertools;
 use jagua_rs::Instant;
 use jagua_rs::entities::PItemKey;
 use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::primitives::Rect;
 use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
 use log::{Level, debug, log};
 use ordered_float::OrderedFloat;
@@ -16,14 +24,88 @@ use rand_xoshiro::Xoshiro256PlusPlus;
 use rayon::ThreadPool;
 use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
+use serde::{Deserialize, Serialize};
+use slotmap::SecondaryMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct SeparatorConfig {
     pub iter_no_imprv_limit: usize,
     pub strike_limit: usize,
     pub n_workers: usize,
+    #[serde(with = "crate::util::serde_adapters::log_level")]
     pub log_level: Level,
     pub sample_config: SampleConfig,
+    /// Weight of the secondary objective favoring orientations already used by other items.
+    /// Reduces the number of distinct rotations in the solution, 0.0 disables it.
+    pub rot_reuse_weight: f32,
+    /// Whether the collision detection checks the poles of a shape before its edges (fail-fast).
+    /// Disabling it does not change the outcome, only the performance.
+    pub pole_precheck: bool,
+    /// How the random number generators of the workers are seeded
+    pub worker_seeding: WorkerSeeding,
+    /// Number of consecutive separations an item has to remain unmoved (and collision-free) to be reported as stable.
+    /// Stable placements are kept in place from then on, see [`Separator::pin_stable`].
+    /// Disabled if `None`. See [`SolutionListener::report_stable`].
+    pub stable_after: Option<usize>,
+    /// Penalizes placements within a margin of the container's edges.
+    /// Disabled if `None`. See [`crate::eval::margin_evaluator::MarginEvaluator`].
+    pub edge_margin: Option<EdgeMargin>,
+    /// Biases clear placements toward the bottom-left of the container.
+    /// Disabled if `None`. See [`crate::eval::sep_evaluator::SeparationEvaluator::with_gravity_bias`].
+    pub gravity_bias: Option<GravityBias>,
+    /// Runs the workers sequentially (in a fixed order) instead of on a thread pool, for reproducible benchmarks.
+    /// The outcome is identical, but it takes (roughly) `n_workers` times longer.
+    pub deterministic: bool,
+    /// Ratio of the shapes' diameter used as epsilon in the overlap proxy, controls the smoothness of the loss.
+    /// Smaller values give sharper gradients (e.g. for instances with small features), larger ones are more stable.
+    pub overlap_proxy_epsilon_ratio: f32,
+    /// How the penalty of a collision is derived from the convex hull areas of the shapes involved
+    pub penalty_kind: PenaltyKind,
+    /// Records all decisions of the separator from its creation onward, see [`Separator::enable_recording`]
+    pub record_decisions: bool,
+}
+
+/// Determines how divergent the random streams of the separator's workers are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkerSeeding {
+    /// Every worker receives an independent seed
+    Independent,
+    /// All workers receive the same seed and thus explore identically
+    Shared,
+    /// All workers share a base seed, offset by the worker's index times the jitter
+    Jittered(u64),
+}
+
+impl WorkerSeeding {
+    /// Draws a seed for each of the `n_workers` workers from `rng`
+    pub fn worker_seeds(&self, rng: &mut impl Rng, n_workers: usize) -> Vec<u64> {
+        match self {
+            WorkerSeeding::Independent => (0..n_workers).map(|_| rng.random()).collect(),
+            WorkerSeeding::Shared => vec![rng.random(); n_workers],
+            WorkerSeeding::Jittered(jitter) => {
+                let base: u64 = rng.random();
+                (0..n_workers as u64)
+                    .map(|i| base.wrapping_add(i.wrapping_mul(*jitter)))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Determines the position at which the strip is split when its width changes.
+/// All items with their centroid right of the split are shifted along with the right edge of the strip.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SplitStrategy {
+    /// Split at the center of the strip
+    Center,
+    /// Split at the weighted median of the items' centroids, balancing the item area on both sides
+    AreaBalanced,
+    /// Split at a uniformly random position
+    Random,
+    /// Split at a fixed position
+    Fixed(f32),
 }
 
 pub struct Separator {
@@ -34,6 +116,24 @@ pub struct Separator {
     pub workers: Vec<SeparatorWorker>,
     pub config: SeparatorConfig,
     pub thread_pool: Option<ThreadPool>,
+    /// Records all decisions modifying the state of the separator, if enabled
+    pub recorder: Option<DecisionRecorder>,
+    /// Tracks which placements have stabilized, if enabled
+    pub stability: Option<StabilityTracker>,
+    /// Moves and evaluations accumulated over all separations
+    pub stats: SepStats,
+    /// Number of separations performed
+    pub n_separations: usize,
+    /// Items which keep their placement, see [`Separator::lock_items`]
+    pub locked_items: HashSet<PItemKey>,
+    /// Penalty factor per item id, see [`Separator::set_penalty_factors`]
+    pub penalty_factors: Vec<f32>,
+    /// Keep-in zone per item id, see [`Separator::set_allowed_regions`]
+    pub allowed_regions: HashMap<usize, Rect>,
+    /// Placements which were reported as stable, see [`Separator::pin_stable`]
+    pub stable_placements: Vec<StablePlacement>,
+    /// Keys of the items in [`Separator::locked_items`] which hold a stable placement
+    pub pinned_items: HashSet<PItemKey>,
 }
 
 impl Separator {
@@ -43,19 +143,39 @@ impl Separator {
         mut rng: Xoshiro256PlusPlus,
         config: SeparatorConfig,
     ) -> Self {
-        let ct = CollisionTracker::new(&prob.layout);
-        let workers = (0..config.n_workers)
-            .map(|_| SeparatorWorker {
+        let ct = CollisionTracker::with_penalty_kind(
+            &prob.layout,
+            &[],
+            config.overlap_proxy_epsilon_ratio,
+            config.penalty_kind,
+        );
+        let bbox_cache = Arc::new(RotatedBBoxCache::new(&instance));
+        let workers = config
+            .worker_seeding
+            .worker_seeds(&mut rng, config.n_workers)
+            .into_iter()
+            .map(|seed| SeparatorWorker {
                 instance: instance.clone(),
                 prob: prob.clone(),
                 ct: ct.clone(),
-                rng: Xoshiro256PlusPlus::seed_from_u64(rng.random()),
+                rng: Xoshiro256PlusPlus::seed_from_u64(seed),
                 sample_config: config.sample_config.clone(),
+                rot_reuse_weight: config.rot_reuse_weight,
+                pole_precheck: config.pole_precheck,
+                edge_margin: config.edge_margin,
+                gravity_bias: config.gravity_bias,
+                shape_pool: ShapeBufferPool::new(),
+                bbox_cache: bbox_cache.clone(),
+                decisions: None,
+                locked_items: HashSet::new(),
+                warm_samples: SecondaryMap::new(),
+                allowed_regions: HashMap::new(),
             })
             .collect();
 
-        let pool = if cfg!(target_arch = "wasm32") {
-            // On wasm32, only the global thread pool is available
+        let pool = if cfg!(target_arch = "wasm32") || config.deterministic {
+            // On wasm32, only the global thread pool is available.
+            // In deterministic mode, the workers run sequentially and no pool is needed
             None
         } else {
             // Create a local thread pool to keep using the same threads for the same optimization (helps the OS scheduler)
@@ -67,7 +187,7 @@ impl Separator {
             )
         };
 
-        Self {
+        let mut sep = Self {
             prob,
             instance,
             rng,
@@ -75,6 +195,150 @@ impl Separator {
             workers,
             config,
             thread_pool: pool,
+            recorder: None,
+            stability: config.stable_after.map(StabilityTracker::new),
+            stats: SepStats::default(),
+            n_separations: 0,
+            locked_items: HashSet::new(),
+            penalty_factors: vec![],
+            allowed_regions: HashMap::new(),
+            stable_placements: vec![],
+            pinned_items: HashSet::new(),
+        };
+        if config.record_decisions {
+            sep.enable_recording();
+        }
+        sep
+    }
+
+    /// Builds a collision tracker for the current layout, with the separator's penalty factors, epsilon ratio
+    /// and penalty kind
+    pub(crate) fn fresh_tracker(&self) -> CollisionTracker {
+        CollisionTracker::with_penalty_kind(
+            &self.prob.layout,
+            &self.penalty_factors,
+            self.config.overlap_proxy_epsilon_ratio,
+            self.config.penalty_kind,
+        )
+    }
+
+    /// Scales the penalty of collisions per item (indexed by item id), e.g. for items which require extra clearance.
+    /// See [`crate::quantify::separation_penalty_factors`].
+    pub fn set_penalty_factors(&mut self, penalty_factors: Vec<f32>) {
+        self.penalty_factors = penalty_factors;
+        self.ct = self.fresh_tracker();
+        for worker in self.workers.iter_mut() {
+            worker.ct = self.ct.clone();
+        }
+    }
+
+    /// Locks placed items in place: they are never moved by the separator (nor shifted when the strip width changes),
+    /// but their collisions are still tracked, so the other items are pushed away from them.
+    /// Locked items are expected to reside in the part of the strip which remains after shrinking.
+    pub fn lock_items(&mut self, pks: impl IntoIterator<Item = PItemKey>) {
+        for pk in pks {
+            assert!(
+                self.prob.layout.placed_items.contains_key(pk),
+                "cannot lock item {pk:?}, it is not placed"
+            );
+            self.locked_items.insert(pk);
+        }
+        for worker in self.workers.iter_mut() {
+            worker.locked_items = self.locked_items.clone();
+        }
+    }
+
+    /// Keeps placements which were reported as stable in place for the remainder of the optimization.
+    /// Items are locked (see [`Separator::lock_items`]) at their stable placement, and moved back to it whenever it
+    /// differs from the current layout (e.g. after a rollback to a solution from before it was reported).
+    pub fn pin_stable(&mut self, placements: impl IntoIterator<Item = StablePlacement>) {
+        self.stable_placements.extend(placements);
+        self.repin_stable();
+    }
+
+    fn repin_stable(&mut self) {
+        //the keys of earlier pins are no longer valid if the layout was restored
+        for pk in self.pinned_items.drain() {
+            self.locked_items.remove(&pk);
+        }
+        let mut claimed = HashSet::new();
+        for sp in self.stable_placements.clone() {
+            let is_at = |dt: &DTransformation| {
+                dt.rotation() == sp.d_transf.rotation()
+                    && dt.translation() == sp.d_transf.translation()
+            };
+            //prefer a copy of the item which already holds the stable placement, otherwise move a movable copy
+            let candidate = self
+                .prob
+                .layout
+                .placed_items
+                .iter()
+                .filter(|(pk, pi)| pi.item_id == sp.item_id && !claimed.contains(pk))
+                .map(|(pk, pi)| (pk, is_at(&pi.d_transf)))
+                .filter(|(pk, at)| *at || !self.locked_items.contains(pk))
+                .max_by_key(|(_, at)| *at);
+            let pk = match candidate {
+                Some((pk, true)) => pk,
+                Some((pk, false)) => self.move_item(pk, sp.d_transf),
+                None => continue,
+            };
+            claimed.insert(pk);
+            if self.locked_items.insert(pk) {
+                self.pinned_items.insert(pk);
+            }
+        }
+        for worker in self.workers.iter_mut() {
+            worker.locked_items = self.locked_items.clone();
+        }
+    }
+
+    /// Restricts items (by id) to a region of the strip (keep-in zone, e.g. for grain direction):
+    /// all their placements are sampled and clamped such that their bounding box resides within the region.
+    /// Items which currently lie (partially) outside of their region are moved into it right away.
+    pub fn set_allowed_regions(&mut self, allowed_regions: HashMap<usize, Rect>) {
+        self.allowed_regions = allowed_regions;
+        for worker in self.workers.iter_mut() {
+            worker.allowed_regions = self.allowed_regions.clone();
+        }
+        let to_clamp = self
+            .prob
+            .layout
+            .placed_items
+            .iter()
+            .filter(|(pk, _)| !self.locked_items.contains(pk))
+            .filter_map(|(pk, pi)| {
+                let item = self.instance.item(pi.item_id);
+                let region = self.allowed_region(pi.item_id)?;
+                let dt = convert_sample_to_closest_feasible(pi.d_transf, item, Some(region));
+                let moved = dt.translation() != pi.d_transf.translation()
+                    || dt.rotation() != pi.d_transf.rotation();
+                moved.then_some((pk, dt))
+            })
+            .collect_vec();
+        for (pk, dt) in to_clamp {
+            self.move_item(pk, dt);
+        }
+    }
+
+    /// The keep-in zone of an item, if any. See [`Separator::set_allowed_regions`]
+    pub fn allowed_region(&self, item_id: usize) -> Option<Rect> {
+        self.allowed_regions.get(&item_id).copied()
+    }
+
+    /// Starts recording all decisions which modify the state of the separator.
+    /// The recording starts with the current strip width and placements, so it can be replayed on any separator
+    /// of the same instance. See [`crate::util::decisions::DecisionReplayer`] to replay them.
+    pub fn enable_recording(&mut self) {
+        let mut recorder = DecisionRecorder::new();
+        let width = self.prob.strip_width();
+        recorder.record(Decision::StripWidth {
+            width,
+            split_position: width,
+        });
+        recorder.record_restore(&self.prob.save());
+        self.recorder = Some(recorder);
+        for worker in self.workers.iter_mut() {
+            worker.decisions = Some(vec![]);
         }
     }
 
@@ -95,10 +359,7 @@ impl Separator {
 
         let mut n_strikes = 0;
         let mut n_iter = 0;
-        let mut sep_stats = SepStats {
-            total_moves: 0,
-            total_evals: 0,
-        };
+        let mut sep_stats = SepStats::default();
         let start = Instant::now();
 
         'outer: while n_strikes < self.config.strike_limit && !term.should_terminate() {
@@ -147,10 +408,12 @@ impl Separator {
                         "[SEP] [s:{n_strikes},i:{n_iter}] (*) min_l: {}",
                         loss
                     );
-                    sol_listener.report(
+                    let improving_sol = self.prob.save();
+                    sol_listener.report_with_meta(
                         ReportType::ExplImproving,
-                        &self.prob.save(),
+                        &improving_sol,
                         &self.instance,
+                        ReportMeta::new(&improving_sol, &self.instance, loss),
                     );
                     if loss < min_loss * 0.98 {
                         //only reset the iter_no_improvement counter if the loss improved significantly
@@ -176,15 +439,29 @@ impl Separator {
         let secs = start.elapsed().as_secs_f32();
         log!(
             self.config.log_level,
-            "[SEP] finished, evals/s: {} K, evals/move: {}, moves/s: {}, iter/s: {}, #workers: {}, total {:.3}s",
+            "[SEP] finished, evals/s: {} K, evals/move: {}, moves/s: {}, iter/s: {}, sample acceptance: {:.1}%, #workers: {}, total {:.3}s",
             (sep_stats.total_evals as f32 / (1000.0 * secs)) as usize,
             sep_stats.total_evals as f32 / sep_stats.total_moves as f32,
             sep_stats.total_moves as f32 / secs,
             n_iter as f32 / secs,
+            sep_stats.sample_metrics.acceptance_rate() * 100.0,
             self.workers.len(),
             secs,
         );
 
+        self.stats += sep_stats;
+        self.n_separations += 1;
+
+        if let Some(stability) = &mut self.stability {
+            let stable = stability.update(&min_loss_sol.0, &min_loss_sol.1);
+            for placement in stable.iter() {
+                sol_listener.report_stable(*placement);
+            }
+            if !stable.is_empty() {
+                self.pin_stable(stable);
+            }
+        }
+
         (min_loss_sol.0, min_loss_sol.1)
     }
 
@@ -192,16 +469,18 @@ impl Separator {
     fn move_items_multi(&mut self) -> SepStats {
         let master_sol = self.prob.save();
 
+        let deterministic = self.config.deterministic;
         let mut separate_multi = || -> SepStats {
-            self.workers
-                .par_iter_mut()
-                .map(|worker| {
-                    // Sync the workers with the master
-                    worker.load(&master_sol, &self.ct);
-                    // Let them modify
-                    worker.move_items()
-                })
-                .sum()
+            let separate = |worker: &mut SeparatorWorker| {
+                // Sync the workers with the master
+                worker.load(&master_sol, &self.ct);
+                // Let them modify
+                worker.move_items()
+            };
+            match deterministic {
+                true => self.workers.iter_mut().map(separate).sum(),
+                false => self.workers.par_iter_mut().map(separate).sum(),
+            }
         };
 
         let sep_report = match self.thread_pool.as_mut() {
@@ -209,25 +488,35 @@ impl Separator {
             None => separate_multi(),
         };
 
-        debug!(
-            "[MOD] optimizers w_o's: {:?}",
-            self.workers
-                .iter()
-                .map(|opt| opt.ct.get_total_weighted_loss())
-                .collect_vec()
-        );
-
-        // Check which worker has the lowest total weighted loss
-        let best_opt = self
+        // Snapshot the total weighted loss of every worker, indexed by the worker's (stable) position
+        let worker_losses = self
             .workers
-            .iter_mut()
-            .min_by_key(|opt| OrderedFloat(opt.ct.get_total_weighted_loss()))
-            .map(|opt| (opt.prob.save(), &opt.ct))
+            .iter()
+            .map(|opt| opt.ct.get_total_weighted_loss())
+            .collect_vec();
+
+        debug!("[MOD] optimizers w_o's: {:?}", worker_losses);
+
+        // Check which worker has the lowest total weighted loss, ties are broken by the lowest index
+        let best_idx = worker_losses
+            .iter()
+            .enumerate()
+            .min_by_key(|(idx, loss)| (OrderedFloat(**loss), *idx))
+            .map(|(idx, _)| idx)
             .unwrap();
 
         // Sync the master with the best optimizer
-        self.prob.restore(&best_opt.0);
-        self.ct = best_opt.1.clone();
+        let best_opt = &self.workers[best_idx];
+        self.prob.restore(&best_opt.prob.save());
+        self.ct = best_opt.ct.clone();
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(Decision::WorkerSelected(best_idx));
+            let worker_decisions = self.workers[best_idx].decisions.iter().flatten();
+            for decision in worker_decisions {
+                recorder.record(decision.clone());
+            }
+        }
 
         sep_report
     }
@@ -235,6 +524,9 @@ impl Separator {
     pub fn rollback(&mut self, sol: &SPSolution, ots: Option<&CTSnapshot>) {
         debug_assert!(sol.strip_width() == self.prob.strip_width());
         self.prob.restore(sol);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_restore(sol);
+        }
 
         match ots {
             Some(ots) => {
@@ -243,15 +535,20 @@ impl Separator {
             }
             None => {
                 //otherwise, rebuild it
-                self.ct = CollisionTracker::new(&self.prob.layout);
+                self.ct = self.fresh_tracker();
             }
         }
+
+        if !self.stable_placements.is_empty() {
+            self.repin_stable();
+        }
     }
 
     pub fn move_item(&mut self, pk: PItemKey, d_transf: DTransformation) -> PItemKey {
         debug_assert!(tracker_matches_layout(&self.ct, &self.prob.layout));
 
         let item_id = self.prob.layout.placed_items[pk].item_id;
+        let old_d_transf = self.prob.layout.placed_items[pk].d_transf;
 
         let old_loss = self.ct.get_loss(pk);
         let old_weighted_loss = self.ct.get_weighted_loss(pk);
@@ -264,6 +561,14 @@ impl Separator {
 
         self.ct.register_item_move(&self.prob.layout, pk, new_pk);
 
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(Decision::MoveItem {
+                item_id,
+                from: old_d_transf,
+                to: d_transf,
+            });
+        }
+
         let new_loss = self.ct.get_loss(new_pk);
         let new_weighted_loss = self.ct.get_weighted_loss(new_pk);
 
@@ -277,13 +582,28 @@ impl Separator {
         );
 
         debug_assert!(tracker_matches_layout(&self.ct, &self.prob.layout));
+        #[cfg(feature = "exact_overlap")]
+        debug_assert!(crate::util::assertions::proxy_ordering_matches_exact(
+            &self.prob.layout
+        ));
 
         new_pk
     }
 
-    pub fn change_strip_width(&mut self, new_width: f32, split_position: Option<f32>) {
-        //if no split position is provided, use the center of the strip
-        let split_position = split_position.unwrap_or(self.prob.strip_width() / 2.0);
+    pub fn change_strip_width(&mut self, new_width: f32, split: SplitStrategy) {
+        let split_position = match split {
+            SplitStrategy::Center => self.prob.strip_width() / 2.0,
+            SplitStrategy::AreaBalanced => self.area_balanced_split_position(),
+            SplitStrategy::Random => self.rng.random_range(0.0..self.prob.strip_width()),
+            SplitStrategy::Fixed(split_position) => split_position,
+        };
+        //never split left of a locked item, so it is not overtaken by the shifted items
+        let split_position = self
+            .locked_items
+            .iter()
+            .filter_map(|pk| self.prob.layout.placed_items.get(*pk))
+            .map(|pi| pi.shape.bbox.x_max)
+            .fold(split_position, f32::max);
         let delta = new_width - self.prob.strip_width();
 
         //shift all items right of the split position
@@ -292,31 +612,230 @@ impl Separator {
             .layout
             .placed_items
             .iter()
-            .filter(|(_, pi)| pi.shape.centroid().0 > split_position)
+            .filter(|(k, pi)| {
+                pi.shape.centroid().0 > split_position && !self.locked_items.contains(k)
+            })
             .map(|(k, pi)| (k, pi.d_transf))
             .collect_vec();
 
+        //the individual shifts are not recorded, they follow from the strip width change itself
+        let recorder = self.recorder.take();
         for (pik, dtransf) in items_to_shift {
             let existing_transf = dtransf.compose();
             let new_transf = existing_transf.translate((delta, 0.0));
-            self.move_item(pik, new_transf.decompose());
+            //shifted items remain within their keep-in zone
+            let item_id = self.prob.layout.placed_items[pik].item_id;
+            let new_dt = match self.allowed_region(item_id) {
+                Some(region) => {
+                    let item = self.instance.item(item_id);
+                    convert_sample_to_closest_feasible(new_transf.decompose(), item, Some(region))
+                }
+                None => new_transf.decompose(),
+            };
+            self.move_item(pik, new_dt);
+        }
+        self.recorder = recorder;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(Decision::StripWidth {
+                width: new_width,
+                split_position,
+            });
         }
 
         self.prob.change_strip_width(new_width);
 
         //rebuild the collision tracker
-        self.ct = CollisionTracker::new(&self.prob.layout);
+        self.ct = self.fresh_tracker();
 
         //rebuild the workers
-        self.workers.iter_mut().for_each(|opt| {
+        let seeds = self
+            .config
+            .worker_seeding
+            .worker_seeds(&mut self.rng, self.workers.len());
+        self.workers.iter_mut().zip(seeds).for_each(|(opt, seed)| {
             *opt = SeparatorWorker {
                 instance: self.instance.clone(),
                 prob: self.prob.clone(),
                 ct: self.ct.clone(),
-                rng: Xoshiro256PlusPlus::seed_from_u64(self.rng.random()),
+                rng: Xoshiro256PlusPlus::seed_from_u64(seed),
                 sample_config: self.config.sample_config.clone(),
+                rot_reuse_weight: self.config.rot_reuse_weight,
+                pole_precheck: self.config.pole_precheck,
+                edge_margin: self.config.edge_margin,
+                gravity_bias: self.config.gravity_bias,
+                shape_pool: std::mem::take(&mut opt.shape_pool),
+                bbox_cache: opt.bbox_cache.clone(),
+                decisions: self.recorder.as_ref().map(|_| vec![]),
+                locked_items: self.locked_items.clone(),
+                warm_samples: SecondaryMap::new(),
+                allowed_regions: self.allowed_regions.clone(),
             };
         });
         debug!("[SEP] changed strip width to {:.3}", new_width);
     }
+
+    /// The x-coordinate of the weighted (by area) median of all items' centroids
+    fn area_balanced_split_position(&self) -> f32 {
+        let centroids = self
+            .prob
+            .layout
+            .placed_items
+            .values()
+            .map(|pi| (pi.shape.centroid().0, pi.shape.area))
+            .sorted_by_key(|(x, _)| OrderedFloat(*x))
+            .collect_vec();
+        let half_area = centroids.iter().map(|(_, a)| a).sum::<f32>() / 2.0;
+
+        let mut cumulative_area = 0.0;
+        for (x, area) in centroids {
+            cumulative_area += area;
+            if cumulative_area >= half_area {
+                return x;
+            }
+        }
+        //no items placed
+        self.prob.strip_width() / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_SPARROW_CONFIG;
+    use crate::consts::LBF_SAMPLE_CONFIG;
+    use crate::optimizer::lbf::LBFBuilder;
+    use crate::util::fixtures::mixed_rects;
+    use crate::util::listener::DummySolListener;
+    use crate::util::terminator::BasicTerminator;
+
+    /// Outcome of [`shrink_and_separate`]
+    struct SepRun {
+        /// Width and loss after every separation
+        trajectory: Vec<(f32, f32)>,
+        /// Index of the worker selected in every move
+        selected: Vec<usize>,
+        /// Final placements as `(item_id, [x, y, rotation])` bits, sorted
+        placements: Vec<(usize, [u32; 3])>,
+    }
+
+    /// Shrinks the strip a few times, separating after every shrink
+    fn shrink_and_separate(
+        n_workers: usize,
+        worker_seeding: WorkerSeeding,
+        deterministic: bool,
+    ) -> SepRun {
+        let instance = mixed_rects();
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let prob = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct().prob;
+        let config = SeparatorConfig {
+            n_workers,
+            worker_seeding,
+            deterministic,
+            iter_no_imprv_limit: 20,
+            strike_limit: 2,
+            record_decisions: true,
+            ..DEFAULT_SPARROW_CONFIG.expl_cfg.separator_config
+        };
+        let rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let mut sep = Separator::new(instance, prob, rng, config);
+
+        let mut trajectory = vec![];
+        for _ in 0..5 {
+            let width = sep.prob.strip_width();
+            sep.change_strip_width(width * 0.95, SplitStrategy::Center);
+            sep.separate(&BasicTerminator::new(), &mut DummySolListener);
+            trajectory.push((sep.prob.strip_width(), sep.ct.get_total_loss()));
+        }
+        let selected = sep
+            .recorder
+            .iter()
+            .flat_map(|r| r.decisions.iter())
+            .filter_map(|d| match d {
+                Decision::WorkerSelected(idx) => Some(*idx),
+                _ => None,
+            })
+            .collect_vec();
+        let placements = sep
+            .prob
+            .layout
+            .placed_items
+            .values()
+            .map(|pi| {
+                let (x, y) = pi.d_transf.translation();
+                (pi.item_id, [x.to_bits(), y.to_bits(), pi.d_transf.rotation().to_bits()])
+            })
+            .sorted()
+            .collect_vec();
+        SepRun {
+            trajectory,
+            selected,
+            placements,
+        }
+    }
+
+    #[test]
+    fn identical_workers_give_the_same_widths_as_a_single_worker() {
+        let single = shrink_and_separate(1, WorkerSeeding::Shared, false);
+        let multi = shrink_and_separate(3, WorkerSeeding::Shared, false);
+        assert_eq!(single.trajectory, multi.trajectory);
+        // All workers tie, so the first one should always be selected
+        assert!(multi.selected.iter().all(|&idx| idx == 0));
+    }
+
+    #[test]
+    fn selected_workers_are_reproducible() {
+        let a = shrink_and_separate(3, WorkerSeeding::Independent, false);
+        let b = shrink_and_separate(3, WorkerSeeding::Independent, false);
+        assert!(!a.selected.is_empty());
+        assert_eq!(a.selected, b.selected);
+        assert_eq!(a.trajectory, b.trajectory);
+    }
+
+    #[test]
+    fn deterministic_runs_are_identical() {
+        let a = shrink_and_separate(3, WorkerSeeding::Independent, true);
+        let b = shrink_and_separate(3, WorkerSeeding::Independent, true);
+        assert_eq!(a.trajectory, b.trajectory);
+        assert_eq!(a.selected, b.selected);
+        assert_eq!(a.placements, b.placements);
+    }
+
+    #[test]
+    fn items_remain_within_their_allowed_region() {
+        let instance = mixed_rects();
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let prob = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct().prob;
+        let config = SeparatorConfig {
+            n_workers: 2,
+            iter_no_imprv_limit: 20,
+            strike_limit: 2,
+            ..DEFAULT_SPARROW_CONFIG.expl_cfg.separator_config
+        };
+        let mut sep = Separator::new(instance, prob, Xoshiro256PlusPlus::seed_from_u64(1), config);
+        //the left part of the strip, wide enough for the 3x6 rectangle in either orientation
+        let left_region = Rect {
+            x_min: 0.0,
+            y_min: 0.0,
+            x_max: 7.0,
+            y_max: 10.0,
+        };
+        sep.set_allowed_regions([(3, left_region)].into());
+
+        let within_region = |sep: &Separator| {
+            sep.prob.layout.placed_items.values().filter(|pi| pi.item_id == 3).all(|pi| {
+                let bbox = pi.shape.bbox;
+                bbox.x_min >= left_region.x_min - 1e-3
+                    && bbox.y_min >= left_region.y_min - 1e-3
+                    && bbox.x_max <= left_region.x_max + 1e-3
+                    && bbox.y_max <= left_region.y_max + 1e-3
+            })
+        };
+        assert!(within_region(&sep));
+        for _ in 0..3 {
+            let width = sep.prob.strip_width();
+            sep.change_strip_width(width * 0.95, SplitStrategy::Center);
+            sep.separate(&BasicTerminator::new(), &mut DummySolListener);
+            assert!(within_region(&sep));
+        }
+    }
 }
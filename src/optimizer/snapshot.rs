@@ -0,0 +1,253 @@
+use crate::optimizer::separator::{Separator, SeparatorConfig};
+use itertools::Itertools;
+use jagua_rs::entities::PItemKey;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::primitives::Rect;
+use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem};
+use log::info;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+
+/// Self-contained, serializable state of a [`Separator`], allowing a run to be resumed elsewhere.
+/// The instance itself is not included, it should be provided again when restoring.
+/// Non-serializable parts (thread pool, shape buffers, ...) are rebuilt on restore,
+/// decision recording and stability tracking start afresh.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeparatorSnapshot {
+    pub config: SeparatorConfig,
+    pub strip_width: f32,
+    pub placements: Vec<SnapshotPlacement>,
+    /// Weights of all pairs of placements, upper triangle (including the diagonal) in row-major order
+    pub pair_weights: Vec<f32>,
+    /// Weights of the collisions with the container, in the order of `placements`
+    pub container_weights: Vec<f32>,
+    /// Indices (in `placements`) of the locked items, see [`Separator::lock_items`].
+    /// Pinned stable placements are not included, they are left to be reported again.
+    #[serde(default)]
+    pub locked_items: Vec<usize>,
+    /// Penalty factor per item id, see [`Separator::set_penalty_factors`]
+    #[serde(default)]
+    pub penalty_factors: Vec<f32>,
+    /// Keep-in zone per item id, see [`Separator::set_allowed_regions`]
+    #[serde(default)]
+    pub allowed_regions: Vec<SnapshotRegion>,
+    pub rng: Xoshiro256PlusPlus,
+    pub worker_rngs: Vec<Xoshiro256PlusPlus>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SnapshotPlacement {
+    pub item_id: usize,
+    pub rotation: f32,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SnapshotRegion {
+    pub item_id: usize,
+    pub x_min: f32,
+    pub y_min: f32,
+    pub x_max: f32,
+    pub y_max: f32,
+}
+
+impl Separator {
+    /// Captures the full state of the separator, see [`SeparatorSnapshot`]
+    pub fn to_snapshot(&self) -> SeparatorSnapshot {
+        let pks = self.prob.layout.placed_items.keys().collect_vec();
+        let placements = pks
+            .iter()
+            .map(|pk| {
+                let pi = &self.prob.layout.placed_items[*pk];
+                let (x, y) = pi.d_transf.translation();
+                SnapshotPlacement {
+                    item_id: pi.item_id,
+                    rotation: pi.d_transf.rotation(),
+                    x,
+                    y,
+                }
+            })
+            .collect();
+        let pair_weights = upper_triangle(&pks)
+            .map(|(pk1, pk2)| self.ct.get_pair_weight(pk1, pk2))
+            .collect();
+        let container_weights = pks
+            .iter()
+            .map(|pk| self.ct.get_container_weight(*pk))
+            .collect();
+        let allowed_regions = self
+            .allowed_regions
+            .iter()
+            .map(|(&item_id, r)| SnapshotRegion {
+                item_id,
+                x_min: r.x_min,
+                y_min: r.y_min,
+                x_max: r.x_max,
+                y_max: r.y_max,
+            })
+            .sorted_by_key(|r| r.item_id)
+            .collect();
+        let locked_items = pks
+            .iter()
+            .positions(|pk| self.locked_items.contains(pk) && !self.pinned_items.contains(pk))
+            .collect();
+
+        SeparatorSnapshot {
+            config: self.config,
+            strip_width: self.prob.strip_width(),
+            placements,
+            pair_weights,
+            container_weights,
+            locked_items,
+            penalty_factors: self.penalty_factors.clone(),
+            allowed_regions,
+            rng: self.rng.clone(),
+            worker_rngs: self.workers.iter().map(|w| w.rng.clone()).collect(),
+        }
+    }
+
+    /// Reconstructs a separator from a snapshot of a separator working on the same `instance`
+    pub fn from_snapshot(instance: SPInstance, snapshot: SeparatorSnapshot) -> Self {
+        assert_eq!(
+            snapshot.worker_rngs.len(),
+            snapshot.config.n_workers,
+            "snapshot should contain a random number generator for every worker"
+        );
+        let mut prob = SPProblem::new(instance.clone());
+        prob.change_strip_width(snapshot.strip_width);
+        let pks = snapshot
+            .placements
+            .iter()
+            .map(|p| {
+                prob.place_item(SPPlacement {
+                    item_id: p.item_id,
+                    d_transf: DTransformation::new(p.rotation, (p.x, p.y)),
+                })
+            })
+            .collect_vec();
+
+        let mut sep = Separator::new(instance, prob, snapshot.rng.clone(), snapshot.config);
+        //rebuilds the tracker, so before the weights are restored
+        sep.set_penalty_factors(snapshot.penalty_factors);
+        //restore the state which was altered by the construction
+        sep.rng = snapshot.rng;
+        for (worker, rng) in sep.workers.iter_mut().zip(snapshot.worker_rngs) {
+            worker.rng = rng;
+        }
+        for ((pk1, pk2), w) in upper_triangle(&pks).zip(snapshot.pair_weights) {
+            sep.ct.set_pair_weight(pk1, pk2, w);
+        }
+        for (pk, w) in pks.iter().zip(snapshot.container_weights) {
+            sep.ct.set_container_weight(*pk, w);
+        }
+        sep.ct.sync_total_weighted_loss();
+        sep.lock_items(snapshot.locked_items.iter().map(|&i| pks[i]));
+        //the snapshotted placements already reside within their regions, so none are moved
+        let allowed_regions = snapshot.allowed_regions.iter().map(|r| {
+            let rect = Rect {
+                x_min: r.x_min,
+                y_min: r.y_min,
+                x_max: r.x_max,
+                y_max: r.y_max,
+            };
+            (r.item_id, rect)
+        });
+        sep.set_allowed_regions(allowed_regions.collect());
+
+        info!(
+            "[SEP] restored separator from snapshot with {} items at width: {:.3}",
+            pks.len(),
+            sep.prob.strip_width()
+        );
+        sep
+    }
+}
+
+fn upper_triangle(pks: &[PItemKey]) -> impl Iterator<Item = (PItemKey, PItemKey)> + '_ {
+    (0..pks.len()).flat_map(move |i| (i..pks.len()).map(move |j| (pks[i], pks[j])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_SPARROW_CONFIG;
+    use crate::consts::LBF_SAMPLE_CONFIG;
+    use crate::optimizer::lbf::LBFBuilder;
+    use crate::util::fixtures::mixed_rects;
+    use rand::SeedableRng;
+
+    fn constructed_separator() -> Separator {
+        let instance = mixed_rects();
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let prob = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct().prob;
+        let config = DEFAULT_SPARROW_CONFIG.expl_cfg.separator_config;
+        Separator::new(instance, prob, Xoshiro256PlusPlus::seed_from_u64(1), config)
+    }
+
+    /// Placements of the locked items of a separator, as `(item_id, x, y, rotation)`
+    fn locked_placements(sep: &Separator) -> Vec<(usize, f32, f32, f32)> {
+        sep.locked_items
+            .iter()
+            .map(|pk| {
+                let pi = &sep.prob.layout.placed_items[*pk];
+                let (x, y) = pi.d_transf.translation();
+                (pi.item_id, x, y, pi.d_transf.rotation())
+            })
+            .sorted_by(|a, b| a.partial_cmp(b).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn locked_items_survive_a_snapshot_round_trip() {
+        let mut sep = constructed_separator();
+        let pks = sep.prob.layout.placed_items.keys().take(2).collect_vec();
+        sep.lock_items(pks);
+
+        let snapshot = sep.to_snapshot();
+        assert_eq!(snapshot.locked_items.len(), 2);
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot = serde_json::from_str(&json).unwrap();
+        let restored = Separator::from_snapshot(sep.instance.clone(), snapshot);
+
+        assert_eq!(locked_placements(&restored), locked_placements(&sep));
+        assert!(restored.workers.iter().all(|w| w.locked_items == restored.locked_items));
+    }
+
+    #[test]
+    fn penalty_factors_survive_a_snapshot_round_trip() {
+        let mut sep = constructed_separator();
+        //stack two items on top of each other, so the penalty factors affect the loss
+        let pks = sep.prob.layout.placed_items.keys().take(2).collect_vec();
+        let d_transf = sep.prob.layout.placed_items[pks[0]].d_transf;
+        sep.move_item(pks[1], d_transf);
+        let penalty_factors = (0..sep.instance.items.len()).map(|i| 1.0 + i as f32).collect_vec();
+        sep.set_penalty_factors(penalty_factors.clone());
+        assert!(sep.ct.get_total_loss() > 0.0);
+
+        let restored = Separator::from_snapshot(sep.instance.clone(), sep.to_snapshot());
+        assert_eq!(restored.penalty_factors, penalty_factors);
+        let (loss, restored_loss) = (sep.ct.get_total_loss(), restored.ct.get_total_loss());
+        assert!((loss - restored_loss).abs() <= 1e-4 * loss, "{loss} != {restored_loss}");
+    }
+
+    #[test]
+    fn allowed_regions_survive_a_snapshot_round_trip() {
+        let mut sep = constructed_separator();
+        let width = sep.prob.strip_width();
+        let left_half = Rect {
+            x_min: 0.0,
+            y_min: 0.0,
+            x_max: width / 2.0,
+            y_max: 10.0,
+        };
+        sep.set_allowed_regions([(3, left_half)].into());
+
+        let json = serde_json::to_string(&sep.to_snapshot()).unwrap();
+        let snapshot = serde_json::from_str(&json).unwrap();
+        let restored = Separator::from_snapshot(sep.instance.clone(), snapshot);
+        assert_eq!(restored.allowed_region(3), Some(left_half));
+        assert_eq!(restored.allowed_region(0), None);
+        assert!(restored.workers.iter().all(|w| w.allowed_regions == restored.allowed_regions));
+    }
+}
@@ -1,17 +1,27 @@
-use crate::eval::sep_evaluator::SeparationEvaluator;
+use crate::eval::margin_evaluator::{EdgeMargin, MarginEvaluator};
+use crate::eval::sample_eval::SampleEval;
+use crate::eval::sep_evaluator::{GravityBias, SeparationEvaluator};
+use crate::eval::shape_buffer_pool::ShapeBufferPool;
 use crate::quantify::tracker::CollisionTracker;
+use crate::sample::best_samples::SampleMetrics;
 use crate::sample::search;
 use crate::sample::search::SampleConfig;
+use crate::sample::uniform_sampler::RotatedBBoxCache;
 use crate::util::assertions::tracker_matches_layout;
+use crate::util::decisions::Decision;
 use itertools::Itertools;
 use jagua_rs::entities::{Instance, PItemKey};
 use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::primitives::Rect;
 use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
-use log::debug;
+use log::{Level, debug, log_enabled};
 use rand::prelude::SliceRandom;
 use rand_xoshiro::Xoshiro256PlusPlus;
+use slotmap::SecondaryMap;
+use std::collections::{HashMap, HashSet};
 use std::iter::Sum;
 use std::ops::AddAssign;
+use std::sync::Arc;
 use tap::Tap;
 
 pub struct SeparatorWorker {
@@ -20,6 +30,24 @@ pub struct SeparatorWorker {
     pub ct: CollisionTracker,
     pub rng: Xoshiro256PlusPlus,
     pub sample_config: SampleConfig,
+    pub rot_reuse_weight: f32,
+    pub pole_precheck: bool,
+    /// Margin to keep from the container's edges, see [`MarginEvaluator`]
+    pub edge_margin: Option<EdgeMargin>,
+    /// Tie-break between clear placements, see [`SeparationEvaluator::with_gravity_bias`]
+    pub gravity_bias: Option<GravityBias>,
+    /// Reusable shape buffers for the evaluators
+    pub shape_pool: ShapeBufferPool,
+    /// Rotated bounding boxes of the items with a fixed set of rotations, shared between all workers
+    pub bbox_cache: Arc<RotatedBBoxCache>,
+    /// Moves executed since the last load, only kept if decisions are being recorded
+    pub decisions: Option<Vec<Decision>>,
+    /// Items which are never moved, but still collide with other items, see [`crate::optimizer::separator::Separator::lock_items`]
+    pub locked_items: HashSet<PItemKey>,
+    /// Best samples of the last search per item (and its id), only kept if [`SampleConfig::reuse_best_samples`] is enabled
+    pub warm_samples: SecondaryMap<PItemKey, (usize, Vec<(DTransformation, SampleEval)>)>,
+    /// Keep-in zone per item id, see [`crate::optimizer::separator::Separator::set_allowed_regions`]
+    pub allowed_regions: HashMap<usize, Rect>,
 }
 
 impl SeparatorWorker {
@@ -28,6 +56,9 @@ impl SeparatorWorker {
         debug_assert!(sol.strip_width() == self.prob.strip_width());
         self.prob.restore(sol);
         self.ct = ct.clone();
+        if let Some(decisions) = &mut self.decisions {
+            decisions.clear();
+        }
     }
 
     /// Algorithm 5 from https://doi.org/10.48550/arXiv.2509.13329
@@ -38,12 +69,13 @@ impl SeparatorWorker {
             .layout
             .placed_items
             .keys()
-            .filter(|pk| self.ct.get_loss(*pk) > 0.0)
+            .filter(|pk| self.ct.get_loss(*pk) > 0.0 && !self.locked_items.contains(pk))
             .collect_vec()
             .tap_mut(|v| v.shuffle(&mut self.rng));
 
         let mut total_moves = 0;
         let mut total_evals = 0;
+        let mut sample_metrics = SampleMetrics::default();
 
         //give each item the opportunity to move to a better (eval) position
         for &pk in candidates.iter() {
@@ -53,30 +85,74 @@ impl SeparatorWorker {
                 let item = self.instance.item(item_id);
 
                 //create an evaluator to evaluate the samples during the search
-                let evaluator = SeparationEvaluator::new(&self.prob.layout, item, pk, &self.ct);
+                let evaluator = SeparationEvaluator::new(
+                    &self.prob.layout,
+                    item,
+                    pk,
+                    &self.ct,
+                    self.rot_reuse_weight,
+                    &mut self.shape_pool,
+                )
+                .with_pole_precheck(self.pole_precheck)
+                .with_gravity_bias(self.gravity_bias);
+                let evaluator = MarginEvaluator::new(
+                    evaluator,
+                    self.prob.layout.container.outer_cd.bbox,
+                    self.edge_margin,
+                );
+
+                //keys are reused after a load, make sure the warm samples belong to the same item
+                let warm_samples: &[_] = match self.warm_samples.get(pk) {
+                    Some((id, samples)) if *id == item_id => samples.as_slice(),
+                    _ => &[],
+                };
 
                 //search for a better position for the item
-                let (best_sample, n_evals) = search::search_placement(
+                let (best_sample, n_evals, best_samples) = search::search_placement(
                     &self.prob.layout,
                     item,
                     Some(pk),
                     evaluator,
-                    self.sample_config,
+                    self.sample_config.budgeted_for(item),
+                    Some(&self.bbox_cache),
+                    warm_samples,
+                    self.allowed_regions.get(&item_id).copied(),
                     &mut self.rng,
                 );
 
-                let (new_dt, _eval) =
+                let (new_dt, eval) =
                     best_sample.expect("search_placement should always return a sample");
 
+                if log_enabled!(Level::Debug) {
+                    //log the outcome of the search, to diagnose which items are stuck and why
+                    let cur_w_loss = self.ct.get_weighted_loss(pk);
+                    let outcome = match eval {
+                        SampleEval::Clear { .. } => "cleared",
+                        SampleEval::Collision { loss } if loss < cur_w_loss => "improved",
+                        SampleEval::Collision { .. } => "stuck",
+                        SampleEval::Invalid => "invalid",
+                    };
+                    debug!(
+                        "[MV] item {} search {}: wl: {} -> {:?}, {} evals",
+                        item_id, outcome, cur_w_loss, eval, n_evals
+                    );
+                }
+
                 //move the item to the new position
-                self.move_item(pk, new_dt);
+                let new_pk = self.move_item(pk, new_dt);
                 total_moves += 1;
                 total_evals += n_evals;
+                sample_metrics += best_samples.metrics;
+                if self.sample_config.reuse_best_samples {
+                    self.warm_samples.remove(pk);
+                    self.warm_samples.insert(new_pk, (item_id, best_samples.samples));
+                }
             }
         }
         SepStats {
             total_moves,
             total_evals,
+            sample_metrics,
         }
     }
 
@@ -112,6 +188,14 @@ impl SeparatorWorker {
         //update the collision tracker to reflect the changes
         self.ct.register_item_move(&self.prob.layout, pk, new_pk);
 
+        if let Some(decisions) = &mut self.decisions {
+            decisions.push(Decision::MoveItem {
+                item_id: item.id,
+                from: old_placement.d_transf,
+                to: d_transf,
+            });
+        }
+
         let (new_l, new_w_l) = (self.ct.get_loss(new_pk), self.ct.get_weighted_loss(new_pk));
 
         debug!(
@@ -135,24 +219,30 @@ impl SeparatorWorker {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default)]
 pub struct SepStats {
     pub total_moves: usize,
     pub total_evals: usize,
+    /// Outcomes of all samples reported during the searches
+    pub sample_metrics: SampleMetrics,
 }
 
 impl Sum for SepStats {
     fn sum<I: Iterator<Item = SepStats>>(iter: I) -> Self {
         let mut total_moves = 0;
         let mut total_evals = 0;
+        let mut sample_metrics = SampleMetrics::default();
 
         for report in iter {
             total_moves += report.total_moves;
             total_evals += report.total_evals;
+            sample_metrics += report.sample_metrics;
         }
 
         SepStats {
             total_moves,
             total_evals,
+            sample_metrics,
         }
     }
 }
@@ -161,5 +251,6 @@ impl AddAssign for SepStats {
     fn add_assign(&mut self, other: Self) {
         self.total_moves += other.total_moves;
         self.total_evals += other.total_evals;
+        self.sample_metrics += other.sample_metrics;
     }
 }
@@ -0,0 +1,17 @@
+//! Re-exports of the API commonly needed to embed sparrow, import with `use sparrow::prelude::*`.
+
+pub use crate::{InfeasibleOutput, SPOutput};
+pub use crate::api::{run, run_from_json};
+pub use crate::config::{CompressionConfig, DEFAULT_SPARROW_CONFIG, ExplorationConfig, SparrowConfig};
+pub use crate::optimizer::report::RunReport;
+pub use crate::optimizer::{OptimizeWorker, optimize, optimize_with_report};
+pub use crate::util::io::{
+    OutputPaths, read_spp_instance_json, read_spp_instance_json_lenient, write_json,
+};
+pub use crate::util::listener::{
+    CompositeSolListener, DummySolListener, ReportType, SolutionListener,
+};
+pub use crate::util::terminator::{
+    BasicTerminator, CombinedTerminator, DensityTerminator, FlagTerminator, Terminator,
+    TimedTerminator,
+};
@@ -0,0 +1,167 @@
+use crate::consts::PROXY_FIDELITY_GRID_RES;
+use crate::quantify::{calc_shape_penalty, quantify_collision_poly_poly};
+use itertools::Itertools;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_traits::{CollidesWith, TransformableFrom};
+use jagua_rs::geometry::primitives::{Point, Rect, SPolygon};
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// Statistics on how faithfully the overlap proxy ([`quantify_collision_poly_poly`]) follows the true overlap.
+/// The reference loss is the true overlap area scaled the same way as the proxy: `sqrt(area) * penalty`.
+#[derive(Debug, Clone, Copy)]
+pub struct ProxyFidelityReport {
+    pub n_samples: usize,
+    /// Number of samples in which the shapes overlapped (only these are included in the statistics)
+    pub n_colliding: usize,
+    /// Pearson correlation between the proxy and reference loss, 0.0 if either of them is constant
+    pub correlation: f32,
+    /// Fraction of sample pairs which are ordered the same way by the proxy and reference loss
+    pub concordance: f32,
+    /// Mean of the ratio between proxy and reference loss
+    pub ratio_mean: f32,
+    /// Standard deviation of the ratio between proxy and reference loss
+    pub ratio_stddev: f32,
+}
+
+/// Places `s2` randomly (translation and rotation) over `s1` for `n_samples` times,
+/// and compares the proxy loss of every overlapping placement with the true overlap.
+/// The true overlap area is estimated by sampling a dense grid of [`PROXY_FIDELITY_GRID_RES`]² points
+/// over the intersection of both bounding boxes. Not intended for the hot path.
+/// Returns `None` if fewer than two samples overlapped, too few to compute any statistics from.
+pub fn validate_proxy_fidelity(
+    s1: &SPolygon,
+    s2: &SPolygon,
+    n_samples: usize,
+    rng: &mut impl Rng,
+) -> Option<ProxyFidelityReport> {
+    let penalty = calc_shape_penalty(s1, s2);
+    let mut buffer = s2.clone();
+
+    let losses = (0..n_samples)
+        .filter_map(|_| {
+            //rotate randomly and move the center of the shape to a random point within the bbox of s1
+            let r = rng.random_range(0.0..2.0 * PI);
+            let rotated_center = buffer
+                .transform_from(s2, &DTransformation::new(r, (0.0, 0.0)).compose())
+                .bbox
+                .centroid();
+            //inclusive ranges, which remain valid for a degenerate (zero width or height) bbox
+            let target = (
+                rng.random_range(s1.bbox.x_min..=s1.bbox.x_max),
+                rng.random_range(s1.bbox.y_min..=s1.bbox.y_max),
+            );
+            let dt = DTransformation::new(
+                r,
+                (target.0 - rotated_center.0, target.1 - rotated_center.1),
+            );
+            let placed = buffer.transform_from(s2, &dt.compose());
+
+            let area = estimate_overlap_area(s1, placed);
+            (area > 0.0).then(|| {
+                let proxy_loss = quantify_collision_poly_poly(s1, placed);
+                let reference_loss = area.sqrt() * penalty;
+                (proxy_loss, reference_loss)
+            })
+        })
+        .collect_vec();
+
+    if losses.len() < 2 {
+        return None;
+    }
+
+    let n = losses.len() as f32;
+    let ratios = losses.iter().map(|(p, r)| p / r).collect_vec();
+    let ratio_mean = ratios.iter().sum::<f32>() / n;
+    let ratio_stddev =
+        (ratios.iter().map(|r| (r - ratio_mean).powi(2)).sum::<f32>() / n).sqrt();
+
+    let concordant_pairs = losses
+        .iter()
+        .tuple_combinations()
+        .filter(|((p1, r1), (p2, r2))| (p1 < p2) == (r1 < r2))
+        .count();
+    let n_pairs = losses.len() * losses.len().saturating_sub(1) / 2;
+
+    Some(ProxyFidelityReport {
+        n_samples,
+        n_colliding: losses.len(),
+        correlation: pearson_correlation(&losses),
+        concordance: concordant_pairs as f32 / n_pairs as f32,
+        ratio_mean,
+        ratio_stddev,
+    })
+}
+
+/// Estimates the area of the intersection of two polygons by sampling a grid of points
+fn estimate_overlap_area(s1: &SPolygon, s2: &SPolygon) -> f32 {
+    let Some(bbox) = Rect::intersection(s1.bbox, s2.bbox) else {
+        return 0.0;
+    };
+    let res = PROXY_FIDELITY_GRID_RES;
+    let (dx, dy) = (bbox.width() / res as f32, bbox.height() / res as f32);
+    let n_inside = (0..res)
+        .cartesian_product(0..res)
+        .map(|(i, j)| {
+            Point(
+                bbox.x_min + (i as f32 + 0.5) * dx,
+                bbox.y_min + (j as f32 + 0.5) * dy,
+            )
+        })
+        .filter(|p| s1.collides_with(p) && s2.collides_with(p))
+        .count();
+    n_inside as f32 * dx * dy
+}
+
+fn pearson_correlation(xy: &[(f32, f32)]) -> f32 {
+    let n = xy.len() as f32;
+    let mean_x = xy.iter().map(|(x, _)| x).sum::<f32>() / n;
+    let mean_y = xy.iter().map(|(_, y)| y).sum::<f32>() / n;
+    let cov = xy
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f32>();
+    let var_x = xy.iter().map(|(x, _)| (x - mean_x).powi(2)).sum::<f32>();
+    let var_y = xy.iter().map(|(_, y)| (y - mean_y).powi(2)).sum::<f32>();
+    match var_x * var_y > 0.0 {
+        true => cov / (var_x * var_y).sqrt(),
+        false => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::mixed_rects;
+    use jagua_rs::entities::Instance;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    #[test]
+    fn proxy_follows_the_overlap_of_rectangles() {
+        let instance = mixed_rects();
+        let (s1, s2) = (&instance.item(0).shape_cd, &instance.item(3).shape_cd);
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let report = validate_proxy_fidelity(s1, s2, 200, &mut rng)
+            .expect("centered placements over a rectangle should overlap");
+        assert!(report.n_colliding >= 2 && report.n_colliding <= 200);
+        assert!(report.correlation.is_finite() && report.correlation > 0.0);
+        assert!((0.0..=1.0).contains(&report.concordance));
+        assert!(report.ratio_mean.is_finite() && report.ratio_mean > 0.0);
+        assert!(report.ratio_stddev.is_finite());
+    }
+
+    #[test]
+    fn no_report_without_overlapping_samples() {
+        let instance = mixed_rects();
+        let s1 = &instance.item(0).shape_cd;
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        assert!(validate_proxy_fidelity(s1, s1, 0, &mut rng).is_none());
+        assert!(validate_proxy_fidelity(s1, s1, 1, &mut rng).is_none());
+    }
+
+    #[test]
+    fn constant_losses_have_no_correlation() {
+        assert_eq!(pearson_correlation(&[(1.0, 2.0), (1.0, 3.0)]), 0.0);
+    }
+}
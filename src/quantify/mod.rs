@@ -1,39 +1,197 @@
 use crate::consts::OVERLAP_PROXY_EPSILON_DIAM_RATIO;
-use crate::quantify::overlap_proxy::overlap_area_proxy;
+use crate::quantify::overlap_proxy::{boundary_penetration_proxy, overlap_area_proxy_bounded};
+use float_cmp::approx_eq;
+use jagua_rs::collision_detection::hazards::HazardEntity;
 use jagua_rs::geometry::geo_traits::DistanceTo;
 use jagua_rs::geometry::primitives::{Rect, SPolygon};
+use jagua_rs::probs::spp::entities::SPInstance;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
+pub mod fidelity;
 pub mod overlap_proxy;
 mod pair_matrix;
+pub mod proxy_core;
 #[cfg(feature = "simd")]
 pub mod simd;
 pub mod tracker;
 
+pub use fidelity::{ProxyFidelityReport, validate_proxy_fidelity};
+
 /// Quantifies a collision between two simple polygons.
 /// Algorithm 4 from https://doi.org/10.48550/arXiv.2509.13329
 #[inline(always)]
 pub fn quantify_collision_poly_poly(s1: &SPolygon, s2: &SPolygon) -> f32 {
-    let epsilon = f32::max(s1.diameter, s2.diameter) * OVERLAP_PROXY_EPSILON_DIAM_RATIO;
+    quantify_collision_poly_poly_of_kind(s1, s2, PenaltyKind::default())
+}
+
+/// Same as [`quantify_collision_poly_poly`], but penalized by the given kind of shape penalty
+#[inline(always)]
+pub fn quantify_collision_poly_poly_of_kind(
+    s1: &SPolygon,
+    s2: &SPolygon,
+    kind: PenaltyKind,
+) -> f32 {
+    let penalty = kind.shape_penalty(s1, s2);
+    quantify_collision_poly_poly_bounded(s1, s2, penalty, OVERLAP_PROXY_EPSILON_DIAM_RATIO, None)
+}
+
+/// Same as [`quantify_collision_poly_poly`], but with an optional upper bound on the loss.
+/// Once the loss is certain to exceed the bound, quantification stops early and a value above the bound is returned.
+/// `penalty` should match [`calc_shape_penalty`] of both shapes, which allows it to be cached
+/// (see [`tracker::CollisionTracker::get_pair_penalty`]).
+/// `epsilon_ratio` (of the largest diameter of both shapes) controls the smoothness of the proxy,
+/// see [`crate::optimizer::separator::SeparatorConfig::overlap_proxy_epsilon_ratio`].
+#[inline(always)]
+pub fn quantify_collision_poly_poly_bounded(
+    s1: &SPolygon,
+    s2: &SPolygon,
+    penalty: f32,
+    epsilon_ratio: f32,
+    bound: Option<f32>,
+) -> f32 {
+    let epsilon = f32::max(s1.diameter, s2.diameter) * epsilon_ratio;
+
+    //translate the bound on the loss into a bound on the overlap proxy
+    let proxy_bound = match bound {
+        Some(bound) => (bound / penalty).powi(2) - epsilon.powi(2),
+        None => f32::INFINITY,
+    };
 
     let overlap_proxy =
-        overlap_area_proxy(&s1.surrogate(), &s2.surrogate(), epsilon) + epsilon.powi(2);
+        overlap_area_proxy_bounded(&s1.surrogate(), &s2.surrogate(), epsilon, proxy_bound)
+            + epsilon.powi(2);
 
     debug_assert!(overlap_proxy.is_normal());
 
-    let penalty = calc_shape_penalty(s1, s2);
-
     overlap_proxy.sqrt() * penalty
 }
 
 pub fn calc_shape_penalty(s1: &SPolygon, s2: &SPolygon) -> f32 {
-    let p1 = f32::sqrt(s1.surrogate().convex_hull_area);
-    let p2 = f32::sqrt(s2.surrogate().convex_hull_area);
-    (p1 * p2).sqrt() //geometric mean
+    calc_penalty_from_sqrt_ch_areas(sqrt_ch_area(s1), sqrt_ch_area(s2))
+}
+
+/// How the penalty of a collision is derived from the [`sqrt_ch_area`] of both shapes.
+/// The penalty of a collision with the container uses the shape's own component twice,
+/// for which every kind reduces to that component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PenaltyKind {
+    /// Geometric mean of both components, see [`calc_penalty_from_sqrt_ch_areas`]
+    #[default]
+    GeometricMean,
+    /// Arithmetic mean of both components, penalizes collisions involving a large item more heavily
+    ArithmeticMean,
+    /// Largest of both components
+    Max,
+    /// Geometric mean scaled by the ratio between the smallest and largest component,
+    /// such that collisions between items of very different sizes are penalized less
+    AreaRatio,
+}
+
+impl PenaltyKind {
+    /// Penalty of a collision from the precomputed [`sqrt_ch_area`] of both shapes
+    #[inline(always)]
+    pub fn penalty(&self, p1: f32, p2: f32) -> f32 {
+        match self {
+            PenaltyKind::GeometricMean => calc_penalty_from_sqrt_ch_areas(p1, p2),
+            PenaltyKind::ArithmeticMean => (p1 + p2) / 2.0,
+            PenaltyKind::Max => f32::max(p1, p2),
+            PenaltyKind::AreaRatio => {
+                let (min, max) = (f32::min(p1, p2), f32::max(p1, p2));
+                calc_penalty_from_sqrt_ch_areas(p1, p2) * min / max
+            }
+        }
+    }
+
+    /// Penalty of a collision between two shapes, [`calc_shape_penalty`] for the default kind
+    pub fn shape_penalty(&self, s1: &SPolygon, s2: &SPolygon) -> f32 {
+        self.penalty(sqrt_ch_area(s1), sqrt_ch_area(s2))
+    }
+}
+
+/// Square root of the convex hull area of a shape, the component of [`calc_shape_penalty`] per shape
+pub fn sqrt_ch_area(s: &SPolygon) -> f32 {
+    f32::sqrt(s.surrogate().convex_hull_area)
+}
+
+/// Penalty factor per item id, for items which require more clearance than the global `min_item_separation`
+/// (which is applied geometrically by the importer).
+/// The factor is `1 + (separation - global) / diameter` for items separated more than the global value, 1.0 otherwise.
+///
+/// The factor multiplies the per-shape component of the penalty ([`sqrt_ch_area`]), so collisions between two items
+/// are penalized by the geometric mean of both factors and collisions with the container by the item's own factor.
+/// See [`tracker::CollisionTracker::with_penalty_factors`].
+/// Returns an empty vector (no factors) if no item overrides the separation.
+pub fn separation_penalty_factors(
+    instance: &SPInstance,
+    separations: &HashMap<usize, f32>,
+    global_separation: Option<f32>,
+) -> Vec<f32> {
+    if separations.is_empty() {
+        return vec![];
+    }
+    let global = global_separation.unwrap_or(0.0);
+    instance
+        .items
+        .iter()
+        .map(|(item, _)| match separations.get(&item.id) {
+            Some(&sep) if sep > global => 1.0 + (sep - global) / item.shape_cd.diameter,
+            _ => 1.0,
+        })
+        .collect()
+}
+
+/// [`calc_shape_penalty`] from the precomputed [`sqrt_ch_area`] of both shapes
+#[inline(always)]
+pub fn calc_penalty_from_sqrt_ch_areas(p1: f32, p2: f32) -> f32 {
+    proxy_core::penalty_from_sqrt_ch_areas(p1, p2)
+}
+
+/// Quantifies a collision between two simple polygons using their exact intersection area,
+/// scaled the same way as the overlap proxy: `sqrt(area) * penalty`. Returns 0.0 if the polygons do not overlap.
+/// Far slower than [`quantify_collision_poly_poly`], intended for validation and offline scoring of solutions.
+#[cfg(feature = "exact_overlap")]
+pub fn quantify_collision_poly_poly_exact(s1: &SPolygon, s2: &SPolygon) -> f32 {
+    exact_overlap_area(s1, s2).sqrt() * calc_shape_penalty(s1, s2)
+}
+
+/// Area of the intersection of two simple polygons, computed by polygon clipping
+#[cfg(feature = "exact_overlap")]
+pub fn exact_overlap_area(s1: &SPolygon, s2: &SPolygon) -> f32 {
+    use geo::{Area, BooleanOps};
+
+    if Rect::intersection(s1.bbox, s2.bbox).is_none() {
+        return 0.0;
+    }
+    let to_geo = |s: &SPolygon| {
+        let exterior = s
+            .vertices
+            .iter()
+            .map(|p| geo::Coord {
+                x: p.0 as f64,
+                y: p.1 as f64,
+            })
+            .collect::<Vec<_>>();
+        geo::Polygon::new(geo::LineString::new(exterior), vec![])
+    };
+    to_geo(s1).intersection(&to_geo(s2)).unsigned_area() as f32
 }
 
 /// Quantifies a collision between a simple polygon and the exterior of the container.
 #[inline(always)]
 pub fn quantify_collision_poly_container(s: &SPolygon, c_bbox: Rect) -> f32 {
+    quantify_collision_poly_container_penalized(s, c_bbox, calc_shape_penalty(s, s))
+}
+
+/// Same as [`quantify_collision_poly_container`], but with a given penalty.
+/// `penalty` should match [`calc_shape_penalty`] of the shape with itself
+/// (see [`tracker::CollisionTracker::get_container_penalty`]).
+#[inline(always)]
+pub fn quantify_collision_poly_container_penalized(
+    s: &SPolygon,
+    c_bbox: Rect,
+    penalty: f32,
+) -> f32 {
     let s_bbox = s.bbox;
     let overlap = match Rect::intersection(s_bbox, c_bbox) {
         Some(r) => {
@@ -48,7 +206,114 @@ pub fn quantify_collision_poly_container(s: &SPolygon, c_bbox: Rect) -> f32 {
     };
     debug_assert!(overlap.is_normal());
 
-    let penalty = calc_shape_penalty(s, s);
-
     2.0 * overlap.sqrt() * penalty
 }
+
+/// Quantifies a collision between a simple polygon and the exterior of an arbitrarily shaped container,
+/// using the overlap proxy of the poles penetrating the container's boundary.
+/// Falls back to [`quantify_collision_poly_container_penalized`] if the container is a plain rectangle.
+/// `epsilon_ratio` (of the shape's diameter) should match the one used between items,
+/// see [`quantify_collision_poly_poly_bounded`].
+#[inline(always)]
+pub fn quantify_collision_poly_exterior(
+    s: &SPolygon,
+    c_outer: &SPolygon,
+    penalty: f32,
+    epsilon_ratio: f32,
+) -> f32 {
+    if is_rectangle(c_outer) {
+        return quantify_collision_poly_container_penalized(s, c_outer.bbox, penalty);
+    }
+    let epsilon = s.diameter * epsilon_ratio;
+    let overlap_proxy =
+        boundary_penetration_proxy(&s.surrogate(), c_outer, false, epsilon) + epsilon.powi(2);
+
+    2.0 * overlap_proxy.sqrt() * penalty
+}
+
+/// Quantifies a collision between a simple polygon and a hole (e.g. a defect) in the container,
+/// using the overlap proxy of the poles penetrating the hole.
+/// `epsilon_ratio` as in [`quantify_collision_poly_exterior`].
+#[inline(always)]
+pub fn quantify_collision_poly_hole(
+    s: &SPolygon,
+    hole: &SPolygon,
+    penalty: f32,
+    epsilon_ratio: f32,
+) -> f32 {
+    let epsilon = s.diameter * epsilon_ratio;
+    let overlap_proxy =
+        boundary_penetration_proxy(&s.surrogate(), hole, true, epsilon) + epsilon.powi(2);
+
+    2.0 * overlap_proxy.sqrt() * penalty
+}
+
+/// Quantifies a collision between a simple polygon and any hazard of the container (not a placed item):
+/// its exterior or a hole within it. `haz_shape` is the shape of the hazard as registered in the CDE.
+/// `penalty` should match [`calc_shape_penalty`] of the shape with itself and `epsilon_ratio` the one
+/// used between items (see [`tracker::CollisionTracker::epsilon_ratio`]).
+#[inline(always)]
+pub fn quantify_collision_poly_container_hazard(
+    s: &SPolygon,
+    haz: &HazardEntity,
+    haz_shape: &SPolygon,
+    penalty: f32,
+    epsilon_ratio: f32,
+) -> f32 {
+    match haz {
+        HazardEntity::Exterior => {
+            quantify_collision_poly_exterior(s, haz_shape, penalty, epsilon_ratio)
+        }
+        HazardEntity::PlacedItem { .. } => unreachable!("placed items are not container hazards"),
+        _ => quantify_collision_poly_hole(s, haz_shape, penalty, epsilon_ratio),
+    }
+}
+
+/// Whether the polygon is an axis-aligned rectangle (coincides with its bounding box)
+fn is_rectangle(s: &SPolygon) -> bool {
+    s.n_vertices() == 4 && approx_eq!(f32, s.area, s.bbox.area(), epsilon = 1e-6 * s.area)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::rect_instance;
+    use jagua_rs::entities::Instance;
+
+    #[test]
+    fn each_penalty_kind_gives_the_expected_penalty() {
+        //a 1x1 and a 4x4 square, with sqrt(ch_area) of 1 and 4
+        let instance = rect_instance(10.0, &[(1.0, 1.0, 1), (4.0, 4.0, 1)]);
+        let small = instance.item(0).shape_cd.as_ref();
+        let large = instance.item(1).shape_cd.as_ref();
+
+        let expected = [
+            (PenaltyKind::GeometricMean, 2.0),
+            (PenaltyKind::ArithmeticMean, 2.5),
+            (PenaltyKind::Max, 4.0),
+            (PenaltyKind::AreaRatio, 0.5),
+        ];
+        for (kind, penalty) in expected {
+            assert!(approx_eq!(f32, kind.shape_penalty(small, large), penalty, epsilon = 1e-5));
+            assert!(approx_eq!(f32, kind.shape_penalty(large, small), penalty, epsilon = 1e-5));
+            //a shape paired with itself (as for container collisions) reduces to its own component
+            assert!(approx_eq!(f32, kind.shape_penalty(large, large), 4.0, epsilon = 1e-5));
+        }
+        let default = PenaltyKind::default().shape_penalty(small, large);
+        assert_eq!(default, calc_shape_penalty(small, large));
+    }
+
+    #[test]
+    fn hole_collisions_follow_the_epsilon_ratio() {
+        //a 4x4 square overlapping a 2x2 'hole'
+        let instance = rect_instance(10.0, &[(4.0, 4.0, 1), (2.0, 2.0, 1)]);
+        let s = instance.item(0).shape_cd.as_ref();
+        let hole = instance.item(1).shape_cd.as_ref();
+        let penalty = calc_shape_penalty(s, s);
+
+        let sharp = quantify_collision_poly_hole(s, hole, penalty, 0.01);
+        let smooth = quantify_collision_poly_hole(s, hole, penalty, 0.1);
+        assert!(sharp > 0.0 && smooth > 0.0);
+        assert_ne!(sharp, smooth, "the epsilon ratio should not be ignored");
+    }
+}
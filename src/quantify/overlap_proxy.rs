@@ -1,27 +1,130 @@
+use crate::quantify::proxy_core::decay_penetration_depth;
 use jagua_rs::geometry::fail_fast::SPSurrogate;
-use jagua_rs::geometry::geo_traits::DistanceTo;
+use jagua_rs::geometry::geo_traits::{CollidesWith, DistanceTo};
+use jagua_rs::geometry::primitives::{Circle, SPolygon};
 use std::f32::consts::PI;
 
 /// Calculates a proxy for the overlap area between two simple polygons (using poles).
 /// Algorithm 3 from https://doi.org/10.48550/arXiv.2509.13329
 #[inline(always)]
 pub fn overlap_area_proxy<'a>(sp1: &SPSurrogate, sp2: &SPSurrogate, epsilon: f32) -> f32 {
+    overlap_area_proxy_bounded(sp1, sp2, epsilon, f32::INFINITY)
+}
+
+/// Same as [`overlap_area_proxy`], but returns early as soon as the proxy exceeds `bound`.
+/// The returned value is then only guaranteed to be larger than `bound`, not the full proxy.
+#[inline(always)]
+pub fn overlap_area_proxy_bounded(
+    sp1: &SPSurrogate,
+    sp2: &SPSurrogate,
+    epsilon: f32,
+    bound: f32,
+) -> f32 {
     let mut total_overlap = 0.0;
     for p1 in &sp1.poles {
         for p2 in &sp2.poles {
             //penetration depth between the two poles (circles)
             let pd = (p1.radius + p2.radius) - p1.center.distance_to(&p2.center);
 
-            let pd_decay = match pd >= epsilon {
-                true => pd,
-                false => epsilon.powi(2) / (-pd + 2.0 * epsilon),
-            };
+            let pd_decay = decay_penetration_depth(pd, epsilon);
 
             total_overlap += pd_decay * f32::min(p1.radius, p2.radius);
         }
+        if total_overlap * PI > bound {
+            //the remaining poles can only increase the proxy further
+            break;
+        }
+    }
+    total_overlap *= PI;
+    debug_assert!(total_overlap.is_normal());
+
+    total_overlap
+}
+
+/// Calculates a proxy for the area of a simple polygon (its poles) penetrating the boundary of a region.
+/// If `obstacle_inside` is set, the interior of `boundary` is the obstacle (e.g. a hole in the container),
+/// otherwise its exterior is (e.g. the outside of the container).
+/// The penetration depth of every pole is decayed in the same way as in [`overlap_area_proxy`].
+pub fn boundary_penetration_proxy(
+    sp: &SPSurrogate,
+    boundary: &SPolygon,
+    obstacle_inside: bool,
+    epsilon: f32,
+) -> f32 {
+    let mut total_overlap = 0.0;
+    for pole in &sp.poles {
+        total_overlap += pole_boundary_penetration(pole, boundary, obstacle_inside, epsilon);
     }
     total_overlap *= PI;
     debug_assert!(total_overlap.is_normal());
 
     total_overlap
 }
+
+/// Contribution of a single pole to [`boundary_penetration_proxy`] (before the multiplication by PI)
+#[inline(always)]
+pub fn pole_boundary_penetration(
+    pole: &Circle,
+    boundary: &SPolygon,
+    obstacle_inside: bool,
+    epsilon: f32,
+) -> f32 {
+    let dist_to_boundary = boundary
+        .edge_iter()
+        .map(|e| e.distance_to(&pole.center))
+        .fold(f32::INFINITY, f32::min);
+    let in_obstacle = boundary.collides_with(&pole.center) == obstacle_inside;
+
+    //penetration depth of the pole (circle) into the obstacle
+    let pd = match in_obstacle {
+        true => pole.radius + dist_to_boundary,
+        false => pole.radius - dist_to_boundary,
+    };
+
+    let pd_decay = decay_penetration_depth(pd, epsilon);
+
+    pd_decay * pole.radius
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantify::proxy_core;
+    use crate::util::fixtures::mixed_rects;
+    use float_cmp::approx_eq;
+    use jagua_rs::entities::Instance;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::geometry::geo_traits::TransformableFrom;
+
+    fn pole_tuples(sp: &SPSurrogate) -> Vec<(f32, f32, f32)> {
+        sp.poles.iter().map(|p| (p.center.0, p.center.1, p.radius)).collect()
+    }
+
+    #[test]
+    fn core_overlap_proxy_matches_the_one_on_surrogates() {
+        let instance = mixed_rects();
+        let s1 = instance.item(0).shape_cd.as_ref().clone();
+        let reference = instance.item(3).shape_cd.as_ref();
+        let mut s2 = reference.clone();
+        s2.transform_from(reference, &DTransformation::new(0.3, (0.5, 0.2)).compose());
+        let (sp1, sp2) = (s1.surrogate(), s2.surrogate());
+        let epsilon = f32::max(s1.diameter, s2.diameter) * 0.01;
+
+        let expected = overlap_area_proxy(sp1, sp2, epsilon);
+        let (poles1, poles2) = (pole_tuples(sp1), pole_tuples(sp2));
+        let core = proxy_core::poles_overlap_area_proxy(&poles1, &poles2, epsilon);
+        assert!(
+            approx_eq!(f32, core, expected, epsilon = expected * 1e-5),
+            "{core} vs {expected}"
+        );
+    }
+
+    #[test]
+    fn core_sqrt_matches_std() {
+        //with the `quantify_no_std` feature, this is computed by `libm`
+        for x in [0.0, 1e-6, 0.5, 2.0, 1e6] {
+            let (core, expected) = (proxy_core::sqrt(x), x.sqrt());
+            assert!(approx_eq!(f32, core, expected, ulps = 1), "sqrt({x}): {core} vs {expected}");
+        }
+    }
+}
@@ -25,6 +25,13 @@ impl PairMatrix {
     }
 }
 
+impl PairMatrix {
+    /// Index in `data` of the entry at `(row, col)`
+    pub fn flat_idx(&self, row: usize, col: usize) -> usize {
+        calc_idx(row, col, self.size)
+    }
+}
+
 impl Index<(usize, usize)> for PairMatrix {
     type Output = CTEntry;
 
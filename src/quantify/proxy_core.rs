@@ -0,0 +1,70 @@
+//! Core math of the overlap proxy and collision penalties, operating on plain `f32`s.
+//! Only depends on `core` (the implicit `std` prelude is disabled to enforce this), so the module can be reused
+//! in `no_std` crates (e.g. embedded WASM), for instance through `#[path = ".../proxy_core.rs"] mod proxy_core;`.
+//! Floating point functions which are not part of `core` (e.g. `sqrt`) are taken from `libm`
+//! when the `quantify_no_std` feature is enabled.
+#![no_implicit_prelude]
+
+use ::core::f32::consts::PI;
+
+/// Square root, computed by `libm` with the `quantify_no_std` feature
+#[inline(always)]
+pub fn sqrt(x: f32) -> f32 {
+    #[cfg(feature = "quantify_no_std")]
+    {
+        ::libm::sqrtf(x)
+    }
+    #[cfg(not(feature = "quantify_no_std"))]
+    {
+        x.sqrt()
+    }
+}
+
+/// Decays a penetration depth below `epsilon` smoothly towards zero (but never reaching it),
+/// such that poles which are close, but do not overlap, still contribute a little.
+#[inline(always)]
+pub fn decay_penetration_depth(pd: f32, epsilon: f32) -> f32 {
+    match pd >= epsilon {
+        true => pd,
+        false => epsilon * epsilon / (-pd + 2.0 * epsilon),
+    }
+}
+
+/// Contribution of a pair of poles (circles) to the overlap proxy (before the multiplication by PI)
+#[inline(always)]
+pub fn pole_pair_overlap(c1: (f32, f32), r1: f32, c2: (f32, f32), r2: f32, epsilon: f32) -> f32 {
+    let (dx, dy) = (c1.0 - c2.0, c1.1 - c2.1);
+
+    //penetration depth between the two poles
+    let pd = (r1 + r2) - sqrt(dx * dx + dy * dy);
+
+    decay_penetration_depth(pd, epsilon) * f32::min(r1, r2)
+}
+
+/// Overlap proxy between two sets of poles, each given as `(x, y, radius)`.
+/// Equivalent to [`crate::quantify::overlap_proxy::overlap_area_proxy`] for the poles of two shapes.
+pub fn poles_overlap_area_proxy(
+    poles1: &[(f32, f32, f32)],
+    poles2: &[(f32, f32, f32)],
+    epsilon: f32,
+) -> f32 {
+    let mut total_overlap = 0.0;
+    for &(x1, y1, r1) in poles1 {
+        for &(x2, y2, r2) in poles2 {
+            total_overlap += pole_pair_overlap((x1, y1), r1, (x2, y2), r2, epsilon);
+        }
+    }
+    total_overlap * PI
+}
+
+/// Penalty of a collision between two shapes: the geometric mean of the square roots of their convex hull areas
+#[inline(always)]
+pub fn penalty_from_sqrt_ch_areas(p1: f32, p2: f32) -> f32 {
+    sqrt(p1 * p2)
+}
+
+/// Loss of a collision from its overlap proxy, see [`crate::quantify::quantify_collision_poly_poly`]
+#[inline(always)]
+pub fn loss_from_overlap_proxy(overlap_proxy: f32, epsilon: f32, penalty: f32) -> f32 {
+    sqrt(overlap_proxy + epsilon * epsilon) * penalty
+}
@@ -1,7 +1,9 @@
-use crate::consts::OVERLAP_PROXY_EPSILON_DIAM_RATIO;
-use crate::quantify::calc_shape_penalty;
 use crate::quantify::simd::circles_soa::CirclesSoA;
-use crate::quantify::simd::overlap_proxy_simd::poles_overlap_area_proxy_simd;
+use crate::quantify::simd::overlap_proxy_simd::{
+    boundary_penetration_proxy_simd, poles_overlap_area_proxy_simd,
+};
+use crate::quantify::{is_rectangle, quantify_collision_poly_container_penalized};
+use jagua_rs::collision_detection::hazards::HazardEntity;
 use jagua_rs::geometry::primitives::SPolygon;
 
 pub mod circles_soa;
@@ -9,9 +11,18 @@ pub mod overlap_proxy_simd;
 
 /// Quantifies a collision between two simple polygons using SIMD.
 /// Mirrors the functionality of `quantify_collision_poly_poly` but leverages SIMD instructions.
+/// `penalty` should match [`crate::quantify::PenaltyKind::shape_penalty`] of both shapes,
+/// as configured in the collision tracker (see [`crate::quantify::tracker::CollisionTracker::get_pair_penalty`]).
+/// `epsilon_ratio` should match the one of the scalar variant, see [`crate::quantify::quantify_collision_poly_poly_bounded`].
 #[inline(always)]
-pub fn quantify_collision_poly_poly_simd(s1: &SPolygon, s2: &SPolygon, poles2: &CirclesSoA) -> f32 {
-    let epsilon = f32::max(s1.diameter, s2.diameter) * OVERLAP_PROXY_EPSILON_DIAM_RATIO;
+pub fn quantify_collision_poly_poly_simd(
+    s1: &SPolygon,
+    s2: &SPolygon,
+    poles2: &CirclesSoA,
+    penalty: f32,
+    epsilon_ratio: f32,
+) -> f32 {
+    let epsilon = f32::max(s1.diameter, s2.diameter) * epsilon_ratio;
 
     let overlap_proxy =
         poles_overlap_area_proxy_simd(&s1.surrogate(), &s2.surrogate(), epsilon, poles2)
@@ -19,7 +30,35 @@ pub fn quantify_collision_poly_poly_simd(s1: &SPolygon, s2: &SPolygon, poles2: &
 
     debug_assert!(overlap_proxy.is_normal());
 
-    let penalty = calc_shape_penalty(s1, s2);
-
     overlap_proxy.sqrt() * penalty
 }
+
+/// Quantifies a collision between a simple polygon and any hazard of the container using SIMD.
+/// Mirrors the functionality of [`crate::quantify::quantify_collision_poly_container_hazard`],
+/// vectorizing the penetration of the poles into the hazard's boundary.
+/// `poles` should match the poles of `s`.
+#[inline(always)]
+pub fn quantify_collision_poly_container_simd(
+    s: &SPolygon,
+    haz: &HazardEntity,
+    haz_shape: &SPolygon,
+    poles: &CirclesSoA,
+    penalty: f32,
+    epsilon_ratio: f32,
+) -> f32 {
+    let obstacle_inside = match haz {
+        HazardEntity::Exterior if is_rectangle(haz_shape) => {
+            //a rectangular exterior is quantified by bounding boxes, nothing to vectorize
+            return quantify_collision_poly_container_penalized(s, haz_shape.bbox, penalty);
+        }
+        HazardEntity::Exterior => false,
+        HazardEntity::PlacedItem { .. } => unreachable!("placed items are not container hazards"),
+        _ => true,
+    };
+    let epsilon = s.diameter * epsilon_ratio;
+    let overlap_proxy =
+        boundary_penetration_proxy_simd(&s.surrogate(), haz_shape, obstacle_inside, epsilon, poles)
+            + epsilon.powi(2);
+
+    2.0 * overlap_proxy.sqrt() * penalty
+}
@@ -1,19 +1,24 @@
-use crate::quantify::overlap_proxy::overlap_area_proxy;
+use crate::quantify::overlap_proxy::{
+    boundary_penetration_proxy, overlap_area_proxy, pole_boundary_penetration,
+};
 use crate::quantify::simd::circles_soa::CirclesSoA;
 use float_cmp::approx_eq;
 use jagua_rs::geometry::fail_fast::SPSurrogate;
 use jagua_rs::geometry::geo_traits::DistanceTo;
-use jagua_rs::geometry::primitives::{Circle, Point};
+use jagua_rs::geometry::primitives::{Circle, Point, SPolygon};
 use std::f32::consts::PI;
-use std::simd::Simd;
+use std::simd::{LaneCount, Mask, Simd, SupportedLaneCount};
 
-/// Width of the SIMD vector
-const SIMD_WIDTH: usize = 4;
+/// Width of the SIMD vector, 8 lanes with the `simd_wide` feature (e.g. for AVX-512), 4 otherwise
+#[cfg(not(feature = "simd_wide"))]
+pub const SIMD_WIDTH: usize = 4;
+#[cfg(feature = "simd_wide")]
+pub const SIMD_WIDTH: usize = 8;
 
 #[allow(non_camel_case_types)]
-type f32xN = Simd<f32, SIMD_WIDTH>;
+type f32xN<const N: usize> = Simd<f32, N>;
 
-/// SIMD version of [`poles_overlap_area_proxy`] with configurable vector width.
+/// SIMD version of [`overlap_area_proxy`] with the vector width selected by [`SIMD_WIDTH`].
 /// `p2` should match the poles of `sp2`.
 #[inline(always)]
 pub fn poles_overlap_area_proxy_simd(
@@ -22,31 +27,46 @@ pub fn poles_overlap_area_proxy_simd(
     epsilon: f32,
     p2: &CirclesSoA,
 ) -> f32 {
+    poles_overlap_area_proxy_simd_n::<SIMD_WIDTH>(sp1, sp2, epsilon, p2)
+}
+
+/// SIMD version of [`overlap_area_proxy`] with a vector width of `N` lanes.
+/// `p2` should match the poles of `sp2`.
+#[inline(always)]
+pub fn poles_overlap_area_proxy_simd_n<const N: usize>(
+    sp1: &SPSurrogate,
+    sp2: &SPSurrogate,
+    epsilon: f32,
+    p2: &CirclesSoA,
+) -> f32
+where
+    LaneCount<N>: SupportedLaneCount,
+{
     use std::simd::StdFloat;
     use std::simd::prelude::{SimdFloat, SimdPartialOrd};
 
-    let e_n = f32xN::splat(epsilon);
-    let e_sq_n = f32xN::splat(epsilon * epsilon);
-    let two_e_n = f32xN::splat(2.0 * epsilon);
+    let e_n = f32xN::<N>::splat(epsilon);
+    let e_sq_n = f32xN::<N>::splat(epsilon * epsilon);
+    let two_e_n = f32xN::<N>::splat(2.0 * epsilon);
 
     let mut total_overlap = 0.0;
     for p1 in sp1.poles.iter() {
         //common values for all chunks
         let r1 = p1.radius;
-        let x1_n = f32xN::splat(p1.center.x());
-        let y1_n = f32xN::splat(p1.center.y());
-        let r1_n = f32xN::splat(r1);
+        let x1_n = f32xN::<N>::splat(p1.center.x());
+        let y1_n = f32xN::<N>::splat(p1.center.y());
+        let r1_n = f32xN::<N>::splat(r1);
 
         //process complete chunks with SIMD
-        let chunks = p2.x.len() / SIMD_WIDTH;
+        let chunks = p2.x.len() / N;
 
         for chunk in 0..chunks {
-            let idx = chunk * SIMD_WIDTH;
+            let idx = chunk * N;
 
             // load the next N elements from p2
-            let x2 = f32xN::from_slice(&p2.x[idx..idx + SIMD_WIDTH]);
-            let y2 = f32xN::from_slice(&p2.y[idx..idx + SIMD_WIDTH]);
-            let r2 = f32xN::from_slice(&p2.r[idx..idx + SIMD_WIDTH]);
+            let x2 = f32xN::<N>::from_slice(&p2.x[idx..idx + N]);
+            let y2 = f32xN::<N>::from_slice(&p2.y[idx..idx + N]);
+            let r2 = f32xN::<N>::from_slice(&p2.r[idx..idx + N]);
 
             // calculate pd
             let dx = x1_n - x2;
@@ -66,7 +86,7 @@ pub fn poles_overlap_area_proxy_simd(
         }
 
         //process remaining elements with scalar operations
-        let remaining_idx = chunks * SIMD_WIDTH;
+        let remaining_idx = chunks * N;
         for j in remaining_idx..p2.x.len() {
             let p2 = Circle {
                 center: Point(p2.x[j], p2.y[j]),
@@ -102,3 +122,183 @@ pub fn poles_overlap_area_proxy_simd(
     debug_assert!(total_overlap.is_normal());
     total_overlap
 }
+
+/// SIMD version of [`boundary_penetration_proxy`] with the vector width selected by [`SIMD_WIDTH`].
+/// `poles` should match the poles of `sp`.
+#[inline(always)]
+pub fn boundary_penetration_proxy_simd(
+    sp: &SPSurrogate,
+    boundary: &SPolygon,
+    obstacle_inside: bool,
+    epsilon: f32,
+    poles: &CirclesSoA,
+) -> f32 {
+    boundary_penetration_proxy_simd_n::<SIMD_WIDTH>(sp, boundary, obstacle_inside, epsilon, poles)
+}
+
+/// SIMD version of [`boundary_penetration_proxy`] with a vector width of `N` lanes.
+/// Every edge of the boundary is processed for `N` poles at once.
+/// `poles` should match the poles of `sp`.
+#[inline(always)]
+pub fn boundary_penetration_proxy_simd_n<const N: usize>(
+    sp: &SPSurrogate,
+    boundary: &SPolygon,
+    obstacle_inside: bool,
+    epsilon: f32,
+    poles: &CirclesSoA,
+) -> f32
+where
+    LaneCount<N>: SupportedLaneCount,
+{
+    use std::simd::StdFloat;
+    use std::simd::prelude::{SimdFloat, SimdPartialOrd};
+
+    let e_n = f32xN::<N>::splat(epsilon);
+    let e_sq_n = f32xN::<N>::splat(epsilon * epsilon);
+    let two_e_n = f32xN::<N>::splat(2.0 * epsilon);
+    let zero_n = f32xN::<N>::splat(0.0);
+    let one_n = f32xN::<N>::splat(1.0);
+
+    let mut total_overlap = 0.0;
+
+    //process complete chunks with SIMD
+    let chunks = poles.x.len() / N;
+
+    for chunk in 0..chunks {
+        let idx = chunk * N;
+
+        // load the next N poles
+        let x = f32xN::<N>::from_slice(&poles.x[idx..idx + N]);
+        let y = f32xN::<N>::from_slice(&poles.y[idx..idx + N]);
+        let r = f32xN::<N>::from_slice(&poles.r[idx..idx + N]);
+
+        // squared distance to the closest edge and whether the center lies within the boundary
+        let mut dist_sq = f32xN::<N>::splat(f32::INFINITY);
+        let mut inside = Mask::<i32, N>::splat(false);
+
+        for edge in boundary.edge_iter() {
+            let (ax, ay) = (edge.start.x(), edge.start.y());
+            let (ex, ey) = (edge.end.x() - ax, edge.end.y() - ay);
+            let len_sq = ex * ex + ey * ey;
+            let inv_len_sq = match len_sq > 0.0 {
+                true => 1.0 / len_sq,
+                false => 0.0,
+            };
+            let ax_n = f32xN::<N>::splat(ax);
+            let ay_n = f32xN::<N>::splat(ay);
+            let ex_n = f32xN::<N>::splat(ex);
+            let ey_n = f32xN::<N>::splat(ey);
+
+            // project the centers onto the edge, clamped to its endpoints
+            let dx = x - ax_n;
+            let dy = y - ay_n;
+            let t = ((dx * ex_n + dy * ey_n) * f32xN::<N>::splat(inv_len_sq))
+                .simd_clamp(zero_n, one_n);
+            let cx = dx - t * ex_n;
+            let cy = dy - t * ey_n;
+            dist_sq = dist_sq.simd_min(cx * cx + cy * cy);
+
+            // crossing number: toggle for every edge crossed by a ray cast from the center along +x
+            let straddles = y.simd_lt(ay_n) ^ y.simd_lt(ay_n + ey_n);
+            let x_cross = ax_n + dy * ex_n / ey_n;
+            inside ^= straddles & x.simd_lt(x_cross);
+        }
+
+        // calculate pd
+        let dist = dist_sq.sqrt();
+        let in_obstacle = match obstacle_inside {
+            true => inside,
+            false => !inside,
+        };
+        let pd = in_obstacle.select(r + dist, r - dist);
+
+        // calculate pd_decay
+        let pd_mask = pd.simd_ge(e_n);
+        let decay_values = e_sq_n / (-pd + two_e_n);
+        let pd_decay = pd_mask.select(pd, decay_values);
+
+        total_overlap += (pd_decay * r).reduce_sum();
+    }
+
+    //process remaining poles with scalar operations
+    for pole in &sp.poles[chunks * N..] {
+        total_overlap += pole_boundary_penetration(pole, boundary, obstacle_inside, epsilon);
+    }
+
+    total_overlap *= PI;
+
+    debug_assert!(
+        approx_eq!(
+            f32,
+            total_overlap,
+            boundary_penetration_proxy(sp, boundary, obstacle_inside, epsilon),
+            epsilon = total_overlap * 1e-3
+        ),
+        "SIMD and SEQ results do not match: {} vs {}",
+        total_overlap,
+        boundary_penetration_proxy(sp, boundary, obstacle_inside, epsilon)
+    );
+
+    debug_assert!(total_overlap.is_normal());
+    total_overlap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::mixed_rects;
+    use jagua_rs::entities::Instance;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::geometry::geo_traits::TransformableFrom;
+
+    /// Two overlapping shapes of the fixture: the 3x6 rectangle rotated and shifted over the 4x3 one
+    fn overlapping_shapes() -> (SPolygon, SPolygon) {
+        let instance = mixed_rects();
+        let s1 = instance.item(0).shape_cd.as_ref().clone();
+        let reference = instance.item(3).shape_cd.as_ref();
+        let mut s2 = reference.clone();
+        s2.transform_from(reference, &DTransformation::new(0.3, (0.5, 0.2)).compose());
+        (s1, s2)
+    }
+
+    fn assert_matches_scalar<const N: usize>()
+    where
+        LaneCount<N>: SupportedLaneCount,
+    {
+        let (s1, s2) = overlapping_shapes();
+        let (sp1, sp2) = (s1.surrogate(), s2.surrogate());
+        let epsilon = f32::max(s1.diameter, s2.diameter) * 0.01;
+
+        let mut poles2 = CirclesSoA::new();
+        poles2.load(&sp2.poles);
+        let simd = poles_overlap_area_proxy_simd_n::<N>(sp1, sp2, epsilon, &poles2);
+        let scalar = overlap_area_proxy(sp1, sp2, epsilon);
+        assert!(
+            approx_eq!(f32, simd, scalar, epsilon = scalar * 1e-3),
+            "overlap proxy with {N} lanes: {simd} vs {scalar}"
+        );
+
+        //the boundary variant, with the number of poles not necessarily a multiple of N (scalar tail)
+        for n_poles in [sp2.poles.len(), sp2.poles.len() - 1] {
+            let mut sp2 = sp2.clone();
+            sp2.poles.truncate(n_poles);
+            poles2.load(&sp2.poles);
+            let simd = boundary_penetration_proxy_simd_n::<N>(&sp2, &s1, true, epsilon, &poles2);
+            let scalar = boundary_penetration_proxy(&sp2, &s1, true, epsilon);
+            assert!(
+                approx_eq!(f32, simd, scalar, epsilon = scalar * 1e-3),
+                "boundary proxy with {N} lanes and {n_poles} poles: {simd} vs {scalar}"
+            );
+        }
+    }
+
+    #[test]
+    fn four_lanes_match_scalar() {
+        assert_matches_scalar::<4>();
+    }
+
+    #[test]
+    fn eight_lanes_match_scalar() {
+        assert_matches_scalar::<8>();
+    }
+}
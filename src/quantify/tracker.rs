@@ -1,11 +1,20 @@
-use crate::consts::{GLS_WEIGHT_DECAY, GLS_WEIGHT_MAX_INC_RATIO, GLS_WEIGHT_MIN_INC_RATIO};
+use crate::consts::{
+    GLS_WEIGHT_DECAY, GLS_WEIGHT_MAX_INC_RATIO, GLS_WEIGHT_MIN_INC_RATIO,
+    OVERLAP_PROXY_EPSILON_DIAM_RATIO, TRACKER_LOSS_TOLERANCE_RATIO,
+};
 use crate::quantify::pair_matrix::PairMatrix;
-use crate::quantify::{quantify_collision_poly_container, quantify_collision_poly_poly};
+use crate::quantify::{
+    PenaltyKind, quantify_collision_poly_container_hazard, quantify_collision_poly_poly_bounded,
+    sqrt_ch_area,
+};
 use crate::util::assertions::tracker_matches_layout;
+use float_cmp::approx_eq;
+use itertools::Itertools;
 use jagua_rs::collision_detection::hazards::HazardEntity;
 use jagua_rs::collision_detection::hazards::collector::{BasicHazardCollector, HazardCollector};
 use jagua_rs::entities::{Layout, PItemKey};
 use ordered_float::Float;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use slotmap::SecondaryMap;
 
 /// Tracker of both collisions between pair of items and collisions with the container.
@@ -16,24 +25,68 @@ pub struct CollisionTracker {
     pub pk_idx_map: SecondaryMap<PItemKey, usize>,
     pub pair_collisions: PairMatrix,
     pub container_collisions: Vec<CTEntry>,
+    /// Running sum of all weighted losses, kept in sync with the entries above.
+    /// Entries modified outside of the tracker's methods require a call to [`CollisionTracker::sync_total_weighted_loss`].
+    total_weighted_loss: f32,
+    /// Square root of the convex hull area of every item (times its penalty factor), by index.
+    /// Constant for the lifetime of the tracker, since moving an item keeps its index.
+    sqrt_ch_areas: Vec<f32>,
+    /// Indices (in `pair_collisions.data`) of all pairs which are colliding or have a weight above the minimum.
+    /// All other pairs are left untouched by [`CollisionTracker::update_weights`], so they can be skipped.
+    active_pairs: Vec<usize>,
+    pair_is_active: Vec<bool>,
+    /// Epsilon ratio of the overlap proxy, see [`crate::quantify::quantify_collision_poly_poly_bounded`]
+    epsilon_ratio: f32,
+    /// How the penalty of a collision is derived from the convex hull areas, see [`PenaltyKind`]
+    penalty_kind: PenaltyKind,
 }
 
 pub type CTSnapshot = CollisionTracker;
 
 impl CollisionTracker {
     pub fn new(l: &Layout) -> Self {
+        Self::with_penalty_factors(l, &[])
+    }
+
+    /// Creates a tracker in which the penalty of every item is multiplied by its factor (indexed by item id).
+    /// Items without a factor default to 1.0. See [`crate::quantify::separation_penalty_factors`].
+    pub fn with_penalty_factors(l: &Layout, penalty_factors: &[f32]) -> Self {
+        Self::with_epsilon_ratio(l, penalty_factors, OVERLAP_PROXY_EPSILON_DIAM_RATIO)
+    }
+
+    /// Same as [`CollisionTracker::with_penalty_factors`], but collisions between items are quantified
+    /// with the given epsilon ratio of the overlap proxy instead of the default.
+    pub fn with_epsilon_ratio(l: &Layout, penalty_factors: &[f32], epsilon_ratio: f32) -> Self {
+        Self::with_penalty_kind(l, penalty_factors, epsilon_ratio, PenaltyKind::default())
+    }
+
+    /// Same as [`CollisionTracker::with_epsilon_ratio`], but collisions are penalized by the given kind of penalty
+    pub fn with_penalty_kind(
+        l: &Layout,
+        penalty_factors: &[f32],
+        epsilon_ratio: f32,
+        penalty_kind: PenaltyKind,
+    ) -> Self {
         let size = l.placed_items.len();
+        let pk_idx_map: SecondaryMap<PItemKey, usize> = l
+            .placed_items
+            .keys()
+            .enumerate()
+            .map(|(i, pk)| (pk, i))
+            .collect();
+        let pair_collisions = PairMatrix::new(size);
+        let pair_is_active = vec![false; pair_collisions.data.len()];
+        let mut sqrt_ch_areas = vec![0.0; size];
+        for (pk, pi) in l.placed_items.iter() {
+            let factor = penalty_factors.get(pi.item_id).copied().unwrap_or(1.0);
+            sqrt_ch_areas[pk_idx_map[pk]] = sqrt_ch_area(&pi.shape) * factor;
+        }
 
         // Create the tracker
         let mut ot = Self {
             size,
-            pk_idx_map: l
-                .placed_items
-                .keys()
-                .enumerate()
-                .map(|(i, pk)| (pk, i))
-                .collect(),
-            pair_collisions: PairMatrix::new(size),
+            pk_idx_map,
+            pair_collisions,
             container_collisions: vec![
                 CTEntry {
                     weight: 1.0,
@@ -41,12 +94,31 @@ impl CollisionTracker {
                 };
                 size
             ],
+            total_weighted_loss: 0.0,
+            sqrt_ch_areas,
+            active_pairs: vec![],
+            pair_is_active,
+            epsilon_ratio,
+            penalty_kind,
         };
 
-        // Recompute the loss for all items
-        l.placed_items
-            .keys()
-            .for_each(|pk| ot.recompute_loss_for_item(pk, l));
+        // Compute the losses of all items, the queries are independent so they can run in parallel
+        let pks = l.placed_items.keys().collect_vec();
+        let item_losses = if cfg!(target_arch = "wasm32") {
+            // On wasm32, only the global thread pool is available
+            pks.iter()
+                .map(|pk| ot.compute_losses_of_item(*pk, l))
+                .collect_vec()
+        } else {
+            pks.par_iter()
+                .map(|pk| ot.compute_losses_of_item(*pk, l))
+                .collect::<Vec<_>>()
+        };
+
+        // Store them in the same order as a sequential recompute would
+        for (pk, losses) in pks.into_iter().zip(item_losses) {
+            ot.store_losses_of_item(pk, losses);
+        }
 
         debug_assert!(tracker_matches_layout(&ot, l));
 
@@ -54,15 +126,19 @@ impl CollisionTracker {
     }
 
     fn recompute_loss_for_item(&mut self, pk: PItemKey, l: &Layout) {
+        let losses = self.compute_losses_of_item(pk, l);
+        self.store_losses_of_item(pk, losses);
+    }
+
+    /// Quantifies all collisions of an item in the layout, without modifying the tracker
+    fn compute_losses_of_item(&self, pk: PItemKey, l: &Layout) -> ItemLosses {
         let idx = self.pk_idx_map[pk];
         let pi = &l.placed_items[pk];
         let shape = &pi.shape;
-
-        // Reset all current loss values for the item
-        for i in 0..self.size {
-            self.pair_collisions[(idx, i)].loss = 0.0;
-        }
-        self.container_collisions[idx].loss = 0.0;
+        let mut losses = ItemLosses {
+            pairs: vec![],
+            container: 0.0,
+        };
 
         // Compute which hazards are currently colliding with the item
         let mut collector = BasicHazardCollector::with_capacity(l.placed_items.len() + 1);
@@ -70,25 +146,66 @@ impl CollisionTracker {
         // Remove the item itself from the detector
         collector.remove_by_entity(&HazardEntity::from((pk, pi)));
 
-        // For each colliding hazard, quantify the collision and store it in the tracker
-        for (_, haz) in collector.iter() {
+        // For each colliding hazard, quantify the collision
+        for (hkey, haz) in collector.iter() {
             match haz {
                 HazardEntity::PlacedItem { pk: other_pk, .. } => {
                     let shape_other = &l.placed_items[*other_pk].shape;
                     let idx_other = self.pk_idx_map[*other_pk];
 
-                    let loss = quantify_collision_poly_poly(shape, shape_other);
+                    let penalty = self
+                        .penalty_kind
+                        .penalty(self.sqrt_ch_areas[idx], self.sqrt_ch_areas[idx_other]);
+                    let loss = quantify_collision_poly_poly_bounded(
+                        shape,
+                        shape_other,
+                        penalty,
+                        self.epsilon_ratio,
+                        None,
+                    );
                     assert!(loss > 0.0, "loss for a collision should be > 0.0");
-                    self.pair_collisions[(idx, idx_other)].loss = loss;
+                    losses.pairs.push((idx_other, loss));
                 }
-                HazardEntity::Exterior => {
-                    let loss = quantify_collision_poly_container(shape, l.container.outer_cd.bbox);
+                _ => {
+                    //the exterior and any holes of the container all add up to the container loss
+                    let penalty = self
+                        .penalty_kind
+                        .penalty(self.sqrt_ch_areas[idx], self.sqrt_ch_areas[idx]);
+                    let haz_shape = &l.cde().hazards_map[hkey].shape;
+                    let loss = quantify_collision_poly_container_hazard(
+                        shape,
+                        haz,
+                        haz_shape,
+                        penalty,
+                        self.epsilon_ratio,
+                    );
                     assert!(loss > 0.0, "loss for a collision should be > 0.0");
-                    self.container_collisions[idx].loss = loss;
+                    losses.container += loss;
                 }
-                _ => unimplemented!("unsupported hazard entity"),
             }
         }
+        losses
+    }
+
+    /// Replaces all loss values of an item with the given ones
+    fn store_losses_of_item(&mut self, pk: PItemKey, losses: ItemLosses) {
+        let idx = self.pk_idx_map[pk];
+
+        // Reset all current loss values for the item
+        self.total_weighted_loss -= self.weighted_loss_of_idx(idx);
+        for i in 0..self.size {
+            self.pair_collisions[(idx, i)].loss = 0.0;
+        }
+        self.container_collisions[idx].loss = 0.0;
+
+        for (idx_other, loss) in losses.pairs {
+            self.pair_collisions[(idx, idx_other)].loss = loss;
+            self.activate_pair(self.pair_collisions.flat_idx(idx, idx_other));
+        }
+        self.container_collisions[idx].loss = losses.container;
+        self.total_weighted_loss += self.weighted_loss_of_idx(idx);
+
+        debug_assert!(self.total_weighted_loss_is_in_sync());
     }
 
     pub fn restore_but_keep_weights(&mut self, cts: &CTSnapshot, layout: &Layout) {
@@ -103,6 +220,8 @@ impl CollisionTracker {
             .iter_mut()
             .zip(cts.container_collisions.iter())
             .for_each(|(a, b)| a.loss = b.loss);
+        self.rebuild_active_pairs();
+        self.sync_total_weighted_loss();
         debug_assert!(tracker_matches_layout(self, layout));
     }
 
@@ -125,30 +244,55 @@ impl CollisionTracker {
     }
 
     /// Algorithm 8 from https://doi.org/10.48550/arXiv.2509.13329
+    /// Only the active pairs are updated: a non-colliding pair with minimum weight would remain unchanged anyway.
+    /// Inactive pairs have no loss, so the total weighted loss is summed over the updated entries alone.
     pub fn update_weights(&mut self) {
         let max_loss = self
-            .pair_collisions
-            .data
+            .active_pairs
             .iter()
+            .map(|&i| &self.pair_collisions.data[i])
             .chain(self.container_collisions.iter())
             .map(|e| e.loss)
             .fold(0.0, |a, b| a.max(b));
 
-        for e in self
-            .pair_collisions
-            .data
-            .iter_mut()
-            .chain(self.container_collisions.iter_mut())
-        {
-            let multiplier = match e.loss == 0.0 {
-                true => GLS_WEIGHT_DECAY, // no collision
-                false => {
-                    GLS_WEIGHT_MIN_INC_RATIO
-                        + (GLS_WEIGHT_MAX_INC_RATIO - GLS_WEIGHT_MIN_INC_RATIO)
-                            * (e.loss / max_loss)
-                }
-            };
-            e.weight = (e.weight * multiplier).max(1.0);
+        let mut total_weighted_loss = 0.0;
+        for &i in self.active_pairs.iter() {
+            let e = &mut self.pair_collisions.data[i];
+            e.update_weight(max_loss);
+            total_weighted_loss += e.weighted_loss();
+        }
+        for e in self.container_collisions.iter_mut() {
+            e.update_weight(max_loss);
+            total_weighted_loss += e.weighted_loss();
+        }
+        self.total_weighted_loss = total_weighted_loss;
+
+        //deactivate the pairs which have returned to their resting state
+        let (data, pair_is_active) = (&self.pair_collisions.data, &mut self.pair_is_active);
+        self.active_pairs.retain(|&i| {
+            let active = data[i].is_active();
+            pair_is_active[i] = active;
+            active
+        });
+
+        debug_assert!(self.total_weighted_loss_is_in_sync());
+    }
+
+    fn activate_pair(&mut self, flat_idx: usize) {
+        if !self.pair_is_active[flat_idx] {
+            self.pair_is_active[flat_idx] = true;
+            self.active_pairs.push(flat_idx);
+        }
+    }
+
+    /// Recomputes the set of active pairs from scratch, required after losses or weights are modified in bulk
+    fn rebuild_active_pairs(&mut self) {
+        self.active_pairs.clear();
+        for (i, e) in self.pair_collisions.data.iter().enumerate() {
+            self.pair_is_active[i] = e.is_active();
+            if self.pair_is_active[i] {
+                self.active_pairs.push(i);
+            }
         }
     }
 
@@ -157,11 +301,81 @@ impl CollisionTracker {
         self.pair_collisions[(idx1, idx2)].weight
     }
 
+    /// Shape penalty of a pair of items (see [`crate::quantify::calc_shape_penalty`]), from the cached convex hull areas
+    pub fn get_pair_penalty(&self, pk1: PItemKey, pk2: PItemKey) -> f32 {
+        let (idx1, idx2) = (self.pk_idx_map[pk1], self.pk_idx_map[pk2]);
+        self.penalty_kind.penalty(self.sqrt_ch_areas[idx1], self.sqrt_ch_areas[idx2])
+    }
+
+    /// Epsilon ratio of the overlap proxy used to quantify collisions, both between items and with the container
+    pub fn epsilon_ratio(&self) -> f32 {
+        self.epsilon_ratio
+    }
+
+    pub fn penalty_kind(&self) -> PenaltyKind {
+        self.penalty_kind
+    }
+
+    /// Shape penalty of an item's collision with the container, from the cached convex hull area
+    pub fn get_container_penalty(&self, pk: PItemKey) -> f32 {
+        let idx = self.pk_idx_map[pk];
+        self.penalty_kind.penalty(self.sqrt_ch_areas[idx], self.sqrt_ch_areas[idx])
+    }
+
     pub fn get_container_weight(&self, pk: PItemKey) -> f32 {
         let idx = self.pk_idx_map[pk];
         self.container_collisions[idx].weight
     }
 
+    /// The (GLS) weights of all distinct pairs of placed items, as `(item_id_1, item_id_2, weight)`.
+    /// Items with a demand above one appear in multiple pairs with the same ids. See [`CollisionTracker::export_container_weights`].
+    pub fn export_weights(&self, l: &Layout) -> Vec<(usize, usize, f32)> {
+        let item_ids = self.item_ids_by_idx(l);
+        (0..self.size)
+            .tuple_combinations()
+            .map(|(idx1, idx2)| {
+                let weight = self.pair_collisions[(idx1, idx2)].weight;
+                (item_ids[idx1], item_ids[idx2], weight)
+            })
+            .collect()
+    }
+
+    /// The (GLS) weights of the collisions of all placed items with the container, as `(item_id, weight)`
+    pub fn export_container_weights(&self, l: &Layout) -> Vec<(usize, f32)> {
+        let item_ids = self.item_ids_by_idx(l);
+        self.container_collisions
+            .iter()
+            .enumerate()
+            .map(|(idx, e)| (item_ids[idx], e.weight))
+            .collect()
+    }
+
+    /// The item id of every placed item, by its index in the tracker
+    fn item_ids_by_idx(&self, l: &Layout) -> Vec<usize> {
+        let mut item_ids = vec![0; self.size];
+        for (pk, &idx) in self.pk_idx_map.iter() {
+            item_ids[idx] = l.placed_items[pk].item_id;
+        }
+        item_ids
+    }
+
+    /// Overwrites the weight of a pair, the cached total is not updated.
+    /// Requires a call to [`CollisionTracker::sync_total_weighted_loss`] afterwards.
+    pub fn set_pair_weight(&mut self, pk1: PItemKey, pk2: PItemKey, weight: f32) {
+        let (idx1, idx2) = (self.pk_idx_map[pk1], self.pk_idx_map[pk2]);
+        self.pair_collisions[(idx1, idx2)].weight = weight;
+        if weight > 1.0 {
+            self.activate_pair(self.pair_collisions.flat_idx(idx1, idx2));
+        }
+    }
+
+    /// Overwrites the weight of an item's collision with the container, the cached total is not updated.
+    /// Requires a call to [`CollisionTracker::sync_total_weighted_loss`] afterwards.
+    pub fn set_container_weight(&mut self, pk: PItemKey, weight: f32) {
+        let idx = self.pk_idx_map[pk];
+        self.container_collisions[idx].weight = weight;
+    }
+
     /// Algorithm 1 from https://doi.org/10.48550/arXiv.2509.13329
     pub fn get_pair_loss(&self, pk1: PItemKey, pk2: PItemKey) -> f32 {
         let (idx1, idx2) = (self.pk_idx_map[pk1], self.pk_idx_map[pk2]);
@@ -184,8 +398,10 @@ impl CollisionTracker {
     }
 
     pub fn get_weighted_loss(&self, pk: PItemKey) -> f32 {
-        let idx = self.pk_idx_map[pk];
+        self.weighted_loss_of_idx(self.pk_idx_map[pk])
+    }
 
+    fn weighted_loss_of_idx(&self, idx: usize) -> f32 {
         let w_pair_loss = (0..self.size)
             .map(|i| self.pair_collisions[(idx, i)].weighted_loss())
             .sum::<f32>();
@@ -193,6 +409,29 @@ impl CollisionTracker {
         self.container_collisions[idx].weighted_loss() + w_pair_loss
     }
 
+    /// Recomputes the total loss of the layout from scratch and checks whether it matches [`CollisionTracker::get_total_loss`]
+    /// (within [`TRACKER_LOSS_TOLERANCE_RATIO`]). A lightweight alternative to [`tracker_matches_layout`],
+    /// suited to validate solutions outside of debug builds.
+    pub fn total_loss_matches(&self, l: &Layout) -> bool {
+        if l.placed_items.len() != self.size
+            || l.placed_items.keys().any(|pk| !self.pk_idx_map.contains_key(pk))
+        {
+            return false;
+        }
+        let (pair_loss, container_loss) = l
+            .placed_items
+            .keys()
+            .map(|pk| self.compute_losses_of_item(pk, l))
+            .fold((0.0, 0.0), |(pair_loss, container_loss), losses| {
+                let item_pair_loss = losses.pairs.iter().map(|(_, loss)| loss).sum::<f32>();
+                (pair_loss + item_pair_loss, container_loss + losses.container)
+            });
+        //every colliding pair is quantified from both sides
+        let fresh_loss = pair_loss / 2.0 + container_loss;
+        let tolerance = fresh_loss * TRACKER_LOSS_TOLERANCE_RATIO;
+        approx_eq!(f32, fresh_loss, self.get_total_loss(), epsilon = tolerance)
+    }
+
     pub fn get_total_loss(&self) -> f32 {
         let cont_o = self
             .container_collisions
@@ -210,7 +449,28 @@ impl CollisionTracker {
         cont_o + pair_o
     }
 
+    /// Returns the cached sum of all weighted losses, see [`CollisionTracker::compute_total_weighted_loss`].
     pub fn get_total_weighted_loss(&self) -> f32 {
+        self.total_weighted_loss
+    }
+
+    /// Recomputes the running sum of the weighted losses from scratch.
+    pub fn sync_total_weighted_loss(&mut self) {
+        self.total_weighted_loss = self.compute_total_weighted_loss();
+    }
+
+    fn total_weighted_loss_is_in_sync(&self) -> bool {
+        let full = self.compute_total_weighted_loss();
+        approx_eq!(
+            f32,
+            self.total_weighted_loss,
+            full,
+            epsilon = f32::max(full, 1.0) * 1e-3
+        )
+    }
+
+    /// Computes the sum of all weighted losses by iterating over all entries
+    pub fn compute_total_weighted_loss(&self) -> f32 {
         let cont_w_o = self
             .container_collisions
             .iter()
@@ -228,6 +488,12 @@ impl CollisionTracker {
     }
 }
 
+/// Losses of all collisions of a single item: (index of the other item, loss) and the loss with the container
+struct ItemLosses {
+    pairs: Vec<(usize, f32)>,
+    container: f32,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct CTEntry {
     pub loss: f32,
@@ -238,4 +504,208 @@ impl CTEntry {
     pub fn weighted_loss(&self) -> f32 {
         self.weight * self.loss
     }
+
+    fn update_weight(&mut self, max_loss: f32) {
+        let multiplier = match self.loss == 0.0 {
+            true => GLS_WEIGHT_DECAY, // no collision
+            false => {
+                GLS_WEIGHT_MIN_INC_RATIO
+                    + (GLS_WEIGHT_MAX_INC_RATIO - GLS_WEIGHT_MIN_INC_RATIO)
+                        * (self.loss / max_loss)
+            }
+        };
+        self.weight = (self.weight * multiplier).max(1.0);
+    }
+
+    /// Whether the entry would be affected by a weight update
+    fn is_active(&self) -> bool {
+        self.loss > 0.0 || self.weight > 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::LBF_SAMPLE_CONFIG;
+    use crate::optimizer::lbf::LBFBuilder;
+    use crate::optimizer::separator::{Separator, SplitStrategy};
+    use crate::util::fixtures::{TempPath, mixed_rects, quick_config, rect_instance};
+    use crate::util::io::write_json;
+    use crate::util::listener::DummySolListener;
+    use crate::util::terminator::TimedTerminator;
+    use jagua_rs::Instant;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::probs::spp::entities::SPPlacement;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use std::time::Duration;
+
+    /// Eager reference of [`CollisionTracker::update_weights`]: every entry is updated, whether active or not.
+    /// Returns the total weighted loss, recomputed over all entries.
+    fn update_weights_dense(pairs: &mut [CTEntry], container: &mut [CTEntry]) -> f32 {
+        let max_loss = pairs
+            .iter()
+            .chain(container.iter())
+            .map(|e| e.loss)
+            .fold(0.0, f32::max);
+        for e in pairs.iter_mut().chain(container.iter_mut()) {
+            e.update_weight(max_loss);
+        }
+        pairs.iter().chain(container.iter()).map(|e| e.weighted_loss()).sum()
+    }
+
+    #[test]
+    fn sparse_weights_match_dense_weights() {
+        let instance = mixed_rects();
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let mut prob = LBFBuilder::new(instance, rng, LBF_SAMPLE_CONFIG).construct().prob;
+        prob.change_strip_width(prob.strip_width() * 0.8);
+        let mut ct = CollisionTracker::new(&prob.layout);
+        let mut dense_pairs = ct.pair_collisions.data.clone();
+        let mut dense_container = ct.container_collisions.clone();
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let (width, height) = (prob.strip_width(), 10.0);
+        for iter in 0..50 {
+            //move items around for a while, afterwards only let the weights decay
+            if iter < 30 {
+                let pks = prob.layout.placed_items.keys().collect_vec();
+                let pk = pks[rng.random_range(0..pks.len())];
+                let pi = &prob.layout.placed_items[pk];
+                let (item_id, rotation) = (pi.item_id, pi.d_transf.rotation());
+                let translation = (rng.random_range(0.0..width), rng.random_range(0.0..height));
+                prob.remove_item(pk);
+                let d_transf = DTransformation::new(rotation, translation);
+                let new_pk = prob.place_item(SPPlacement { item_id, d_transf });
+                ct.register_item_move(&prob.layout, pk, new_pk);
+            }
+
+            //losses do not depend on the weights, so the reference takes them over from the tracker
+            for (dense, sparse) in dense_pairs.iter_mut().zip(ct.pair_collisions.data.iter()) {
+                dense.loss = sparse.loss;
+            }
+            for (dense, sparse) in dense_container.iter_mut().zip(ct.container_collisions.iter()) {
+                dense.loss = sparse.loss;
+            }
+            let dense_total = update_weights_dense(&mut dense_pairs, &mut dense_container);
+            ct.update_weights();
+            let (sparse_total, eps) = (ct.get_total_weighted_loss(), dense_total.max(1.0) * 1e-5);
+            assert!(approx_eq!(f32, sparse_total, dense_total, epsilon = eps));
+
+            let pairs = dense_pairs.iter().zip(&ct.pair_collisions.data);
+            for (i, (dense, sparse)) in pairs.enumerate() {
+                assert_eq!(dense.weight, sparse.weight, "pair {i} differs after {iter} iterations");
+            }
+            for (dense, sparse) in dense_container.iter().zip(&ct.container_collisions) {
+                assert_eq!(dense.weight, sparse.weight);
+            }
+        }
+        let total = ct.compute_total_weighted_loss();
+        assert!(approx_eq!(f32, ct.get_total_weighted_loss(), total, epsilon = total * 1e-5));
+    }
+
+    #[test]
+    fn dumped_weights_are_at_least_one() {
+        let instance = mixed_rects();
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let prob = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct().prob;
+        let config = quick_config(Duration::from_secs(10)).expl_cfg.separator_config;
+        let mut sep = Separator::new(instance, prob, Xoshiro256PlusPlus::seed_from_u64(1), config);
+        let width = sep.prob.strip_width() * 0.8;
+        sep.change_strip_width(width, SplitStrategy::Center);
+        let term = TimedTerminator::new_duration(Duration::from_secs(10));
+        sep.separate(&term, &mut DummySolListener);
+
+        let path = TempPath::new("weights_dump.json");
+        let dump = (
+            sep.ct.export_weights(&sep.prob.layout),
+            sep.ct.export_container_weights(&sep.prob.layout),
+        );
+        write_json(&dump, &path, log::Level::Debug).unwrap();
+        let (pair_weights, container_weights): (Vec<(usize, usize, f32)>, Vec<(usize, f32)>) =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        let n = sep.prob.layout.placed_items.len();
+        assert_eq!(pair_weights.len(), n * (n - 1) / 2);
+        assert_eq!(container_weights.len(), n);
+        assert!(pair_weights.iter().all(|&(_, _, w)| w >= 1.0));
+        assert!(container_weights.iter().all(|&(_, w)| w >= 1.0));
+        //the narrowed strip caused collisions, which increased some of the weights
+        assert!(pair_weights.iter().any(|&(_, _, w)| w > 1.0));
+    }
+
+    /// Throughput of the separator (sample evaluations per second), dominated by collision quantification.
+    /// Run on two revisions to compare them: `cargo test --release bench_separation -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_separation_evals_per_sec() {
+        let instance = rect_instance(
+            20.0,
+            &[
+                (4.0, 3.0, 6),
+                (2.0, 2.0, 8),
+                (5.0, 1.0, 6),
+                (3.0, 6.0, 4),
+                (1.5, 4.5, 6),
+                (2.5, 3.5, 6),
+            ],
+        );
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let builder = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct();
+        let config = quick_config(Duration::from_secs(10)).expl_cfg.separator_config;
+        let sep_rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let mut sep = Separator::new(instance, builder.prob, sep_rng, config);
+
+        let duration = Duration::from_secs(10);
+        let term = TimedTerminator::new_duration(duration);
+        let start = Instant::now();
+        while start.elapsed() < duration {
+            //keep the layout under pressure, so every separation has collisions to resolve
+            let width = sep.prob.strip_width() * 0.98;
+            sep.change_strip_width(width, SplitStrategy::Center);
+            sep.separate(&term, &mut DummySolListener);
+        }
+        let secs = start.elapsed().as_secs_f64();
+        println!(
+            "evals/s: {:.0}, moves/s: {:.0} ({} separations in {:.1}s)",
+            sep.stats.total_evals as f64 / secs,
+            sep.stats.total_moves as f64 / secs,
+            sep.n_separations,
+            secs
+        );
+    }
+
+    /// Startup time of the collision tracker (initial quantification of every placed item) on a layout of 200 items.
+    /// Run on two revisions to compare them: `cargo test --release bench_tracker_new -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_tracker_new() {
+        let instance = rect_instance(
+            40.0,
+            &[
+                (4.0, 3.0, 40),
+                (2.0, 2.0, 40),
+                (5.0, 1.0, 30),
+                (3.0, 6.0, 30),
+                (1.5, 4.5, 30),
+                (2.5, 3.5, 30),
+            ],
+        );
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let mut prob = LBFBuilder::new(instance, rng, LBF_SAMPLE_CONFIG).construct().prob;
+        //squeeze the layout, so the tracker has collisions to quantify
+        prob.change_strip_width(prob.strip_width() * 0.8);
+
+        let n_reps = 20;
+        let start = Instant::now();
+        for _ in 0..n_reps {
+            std::hint::black_box(CollisionTracker::new(&prob.layout));
+        }
+        let per_rep = start.elapsed() / n_reps;
+        println!(
+            "CollisionTracker::new: {:.3}ms per tracker ({} items)",
+            per_rep.as_secs_f64() * 1000.0,
+            prob.layout.placed_items.len()
+        );
+    }
 }
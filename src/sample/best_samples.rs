@@ -1,8 +1,13 @@
+use crate::consts::UNIQUE_SAMPLE_THRESHOLD;
 use crate::eval::sample_eval::SampleEval;
 use itertools::Itertools;
+use jagua_rs::entities::Item;
 use jagua_rs::geometry::DTransformation;
+use ordered_float::OrderedFloat;
+use std::cmp::Ordering;
 use std::f32::consts::PI;
 use std::fmt::Debug;
+use std::ops::AddAssign;
 
 /// Datastructure to store the N best samples, automatically keeps them sorted and evicts the worst.
 /// It makes sure that no two included samples are too similar.
@@ -10,24 +15,73 @@ use std::fmt::Debug;
 pub struct BestSamples {
     pub size: usize,
     pub samples: Vec<(DTransformation, SampleEval)>,
-    pub unique_thresh: f32,
+    /// Distance along the x-axis below which two samples are considered similar, see [`dtransfs_are_similar`]
+    pub x_unique_thresh: f32,
+    /// Distance along the y-axis below which two samples are considered similar
+    pub y_unique_thresh: f32,
+    pub metrics: SampleMetrics,
+}
+
+/// Outcomes of the samples reported to [`BestSamples`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SampleMetrics {
+    pub n_accepted: usize,
+    /// Rejected because they did not beat the upper bound
+    pub n_rejected_bound: usize,
+    /// Rejected because a similar sample with a better evaluation was already present
+    pub n_rejected_similar: usize,
+}
+
+impl SampleMetrics {
+    /// Ratio of all reported samples which were accepted
+    pub fn acceptance_rate(&self) -> f32 {
+        let total = self.n_accepted + self.n_rejected_bound + self.n_rejected_similar;
+        match total {
+            0 => 0.0,
+            _ => self.n_accepted as f32 / total as f32,
+        }
+    }
+}
+
+impl AddAssign for SampleMetrics {
+    fn add_assign(&mut self, other: Self) {
+        self.n_accepted += other.n_accepted;
+        self.n_rejected_bound += other.n_rejected_bound;
+        self.n_rejected_similar += other.n_rejected_similar;
+    }
 }
 
 impl BestSamples {
-    pub fn new(size: usize, unique_thresh: f32) -> Self {
+    pub fn new(size: usize, x_unique_thresh: f32, y_unique_thresh: f32) -> Self {
+        assert!(size > 0, "BestSamples should be able to hold at least one sample");
         Self {
             size,
             samples: vec![],
-            unique_thresh,
+            x_unique_thresh,
+            y_unique_thresh,
+            metrics: SampleMetrics::default(),
         }
     }
 
+    /// Samples of `item` are considered similar relative to the item's size along each axis,
+    /// see [`UNIQUE_SAMPLE_THRESHOLD`]
+    pub fn for_item(size: usize, item: &Item) -> Self {
+        Self::new(
+            size,
+            item.shape_cd.bbox.width() * UNIQUE_SAMPLE_THRESHOLD,
+            item.shape_cd.bbox.height() * UNIQUE_SAMPLE_THRESHOLD,
+        )
+    }
+
     pub fn report(&mut self, dt: DTransformation, eval: SampleEval) -> bool {
-        let accept = match eval < self.upper_bound() {
-            false => false,
+        let accept = match self.size > 0 && eval < self.upper_bound() {
+            false => {
+                self.metrics.n_rejected_bound += 1;
+                false
+            }
             true => {
                 let any_similar = self.samples.iter().any(|(d, _)| {
-                    dtransfs_are_similar(*d, dt, self.unique_thresh, self.unique_thresh)
+                    dtransfs_are_similar(*d, dt, self.x_unique_thresh, self.y_unique_thresh)
                 });
 
                 match any_similar {
@@ -44,7 +98,12 @@ impl BestSamples {
                             .samples
                             .iter()
                             .filter(|(d, _)| {
-                                dtransfs_are_similar(*d, dt, self.unique_thresh, self.unique_thresh)
+                                dtransfs_are_similar(
+                                    *d,
+                                    dt,
+                                    self.x_unique_thresh,
+                                    self.y_unique_thresh,
+                                )
                             })
                             .all(|(_, sim_eval)| eval < *sim_eval);
 
@@ -54,12 +113,13 @@ impl BestSamples {
                                 !dtransfs_are_similar(
                                     *d,
                                     dt,
-                                    self.unique_thresh,
-                                    self.unique_thresh,
+                                    self.x_unique_thresh,
+                                    self.y_unique_thresh,
                                 )
                             });
                             true
                         } else {
+                            self.metrics.n_rejected_similar += 1;
                             false
                         }
                     }
@@ -67,22 +127,27 @@ impl BestSamples {
             }
         };
         if accept {
-            self.samples.push((dt, eval));
-            self.samples.sort_by_key(|(_, eval)| *eval);
+            //the samples are already sorted, insert the new one in place
+            let sample = (dt, eval);
+            let idx = self.samples.partition_point(|s| cmp_samples(s, &sample).is_lt());
+            self.samples.insert(idx, sample);
+            self.metrics.n_accepted += 1;
+            debug_assert!(self.samples.is_sorted_by(|a, b| cmp_samples(a, b).is_le()));
             debug_assert!(
                 self.samples
                     .iter()
                     .filter(|(_, eval)| *eval != SampleEval::Invalid)
                     .tuple_combinations()
                     .all(|(a, b)| {
-                        !dtransfs_are_similar(a.0, b.0, self.unique_thresh, self.unique_thresh)
+                        let (x_thresh, y_thresh) = (self.x_unique_thresh, self.y_unique_thresh);
+                        !dtransfs_are_similar(a.0, b.0, x_thresh, y_thresh)
                     }),
                 "BestSamples: samples are not unique: {:?}",
                 &self.samples
             );
             true
         } else {
-            debug_assert!(self.samples.is_sorted_by_key(|(_, eval)| *eval));
+            debug_assert!(self.samples.is_sorted_by(|a, b| cmp_samples(a, b).is_le()));
             false
         }
     }
@@ -91,7 +156,12 @@ impl BestSamples {
         self.samples.first().cloned()
     }
 
+    /// Returns the evaluation a sample has to beat to be accepted.
+    /// As long as fewer than `size` samples are stored, any valid sample is accepted.
     pub fn upper_bound(&self) -> SampleEval {
+        if self.size == 0 {
+            return SampleEval::Invalid;
+        }
         if let Some((_, eval)) = self.samples.get(self.size - 1) {
             *eval
         } else {
@@ -100,6 +170,19 @@ impl BestSamples {
     }
 }
 
+/// Orders samples by their evaluation. Samples with equal evaluations are ordered by their transformation
+/// (x, y, rotation), so that their order does not depend on the order in which they were reported.
+pub fn cmp_samples(
+    a: &(DTransformation, SampleEval),
+    b: &(DTransformation, SampleEval),
+) -> Ordering {
+    let dt_key = |dt: DTransformation| {
+        let (x, y) = dt.translation();
+        (OrderedFloat(x), OrderedFloat(y), OrderedFloat(dt.rotation()))
+    };
+    a.1.cmp(&b.1).then_with(|| dt_key(a.0).cmp(&dt_key(b.0)))
+}
+
 pub fn dtransfs_are_similar(
     dt1: DTransformation,
     dt2: DTransformation,
@@ -110,11 +193,135 @@ pub fn dtransfs_are_similar(
     let y_diff = f32::abs(dt1.translation().1 - dt2.translation().1);
 
     if x_diff < x_threshold && y_diff < y_threshold {
-        let r1 = dt1.rotation() % 2.0 * PI;
-        let r2 = dt2.rotation() % 2.0 * PI;
-        let angle_diff = f32::abs(r1 - r2);
+        let r1 = dt1.rotation().rem_euclid(2.0 * PI);
+        let r2 = dt2.rotation().rem_euclid(2.0 * PI);
+        //take the shortest way around the circle, so angles close to 0 and 2π are considered similar
+        let abs_diff = f32::abs(r1 - r2);
+        let angle_diff = f32::min(abs_diff, 2.0 * PI - abs_diff);
         angle_diff < (1.0f32).to_radians()
     } else {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::rect_instance;
+    use jagua_rs::entities::Instance;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    fn dt(r: f32, x: f32, y: f32) -> DTransformation {
+        DTransformation::new(r, (x, y))
+    }
+
+    #[test]
+    fn rotations_are_compared_around_the_circle() {
+        let base = dt(0.0, 1.0, 1.0);
+        assert!(dtransfs_are_similar(base, dt(2.0 * PI - 0.0001, 1.0, 1.0), 0.1, 0.1));
+        assert!(dtransfs_are_similar(base, dt(2.0 * PI, 1.0, 1.0), 0.1, 0.1));
+        assert!(dtransfs_are_similar(base, dt(-0.0001, 1.0, 1.0), 0.1, 0.1));
+        assert!(dtransfs_are_similar(dt(PI, 1.0, 1.0), dt(3.0 * PI, 1.0, 1.0), 0.1, 0.1));
+        assert!(!dtransfs_are_similar(base, dt(PI, 1.0, 1.0), 0.1, 0.1));
+        assert!(!dtransfs_are_similar(base, dt(0.5 * PI, 1.0, 1.0), 0.1, 0.1));
+    }
+
+    #[test]
+    fn distant_translations_are_not_similar() {
+        let base = dt(0.0, 1.0, 1.0);
+        assert!(!dtransfs_are_similar(base, dt(0.0, 1.2, 1.0), 0.1, 0.1));
+        assert!(!dtransfs_are_similar(base, dt(0.0, 1.0, 1.2), 0.1, 0.1));
+    }
+
+    #[test]
+    fn one_slot_keeps_the_best_of_two_samples() {
+        let mut samples = BestSamples::new(1, 0.1, 0.1);
+        assert_eq!(samples.upper_bound(), SampleEval::Invalid);
+
+        assert!(samples.report(dt(0.0, 0.0, 0.0), SampleEval::Collision { loss: 2.0 }));
+        assert_eq!(samples.upper_bound(), SampleEval::Collision { loss: 2.0 });
+
+        //a distant, better sample evicts the first one
+        assert!(samples.report(dt(0.0, 5.0, 5.0), SampleEval::Collision { loss: 1.0 }));
+        assert_eq!(samples.samples.len(), 1);
+        assert_eq!(samples.best().unwrap().1, SampleEval::Collision { loss: 1.0 });
+
+        //a worse one is rejected by the bound
+        assert!(!samples.report(dt(0.0, 9.0, 9.0), SampleEval::Collision { loss: 3.0 }));
+        assert_eq!(samples.samples.len(), 1);
+        assert_eq!(samples.metrics.n_accepted, 2);
+        assert_eq!(samples.metrics.n_rejected_bound, 1);
+    }
+
+    #[test]
+    fn equal_evaluations_are_ordered_regardless_of_report_order() {
+        let eval = SampleEval::Collision { loss: 1.0 };
+        let reported = [dt(0.0, 5.0, 0.0), dt(0.0, 0.0, 5.0), dt(0.0, 9.0, 9.0)];
+        let order_after = |order: [usize; 3]| {
+            let mut samples = BestSamples::new(3, 0.1, 0.1);
+            for i in order {
+                assert!(samples.report(reported[i], eval));
+            }
+            samples.samples.iter().map(|(dt, _)| dt.translation()).collect_vec()
+        };
+        let expected = vec![(0.0, 5.0), (5.0, 0.0), (9.0, 9.0)];
+        assert_eq!(order_after([0, 1, 2]), expected);
+        assert_eq!(order_after([2, 1, 0]), expected);
+        assert_eq!(order_after([1, 2, 0]), expected);
+    }
+
+    #[test]
+    fn samples_stay_sorted_after_random_reports() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let mut samples = BestSamples::new(8, 0.5, 0.5);
+        for _ in 0..100 {
+            let d_transf = dt(0.0, rng.random_range(0.0..10.0), rng.random_range(0.0..10.0));
+            let loss = rng.random_range(0.0..100.0);
+            let eval = match rng.random_bool(0.5) {
+                true => SampleEval::Clear { loss },
+                false => SampleEval::Collision { loss },
+            };
+            samples.report(d_transf, eval);
+            assert!(samples.samples.len() <= 8);
+            assert!(samples.samples.is_sorted_by(|a, b| cmp_samples(a, b).is_le()));
+        }
+        let metrics = samples.metrics;
+        assert_eq!(metrics.n_accepted + metrics.n_rejected_bound + metrics.n_rejected_similar, 100);
+    }
+
+    #[test]
+    fn similarity_is_relative_to_the_item_size_along_each_axis() {
+        //a long, thin strip: 20 wide and 1 high
+        let instance = rect_instance(10.0, &[(20.0, 1.0, 1)]);
+        let mut samples = BestSamples::for_item(3, instance.item(0));
+        assert!(samples.x_unique_thresh > samples.y_unique_thresh);
+
+        let eval = SampleEval::Collision { loss: 1.0 };
+        assert!(samples.report(dt(0.0, 5.0, 5.0), eval));
+        //an offset of 0.5 is small relative to the length of the strip, but not to its thickness
+        assert!(samples.report(dt(0.0, 5.0, 5.5), eval));
+        assert!(!samples.report(dt(0.0, 5.5, 5.0), eval));
+        assert_eq!(samples.samples.len(), 2);
+        assert_eq!(samples.metrics.n_rejected_similar, 1);
+    }
+
+    #[test]
+    fn zero_capacity_accepts_nothing() {
+        //`size` is public, so it can be zeroed after construction
+        let mut samples = BestSamples::new(1, 0.1, 0.1);
+        samples.size = 0;
+        assert_eq!(samples.upper_bound(), SampleEval::Invalid);
+
+        assert!(!samples.report(dt(0.0, 0.0, 0.0), SampleEval::Clear { loss: 0.0 }));
+        assert!(samples.samples.is_empty());
+        assert!(samples.best().is_none());
+        assert_eq!(samples.metrics.n_rejected_bound, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_slots_are_rejected() {
+        BestSamples::new(0, 0.1, 0.1);
+    }
+}
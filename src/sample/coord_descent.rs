@@ -1,8 +1,12 @@
 use crate::consts::{CD_STEP_FAIL, CD_STEP_SUCCESS};
 use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
+use crate::sample::uniform_sampler::convert_sample_to_closest_feasible;
+use jagua_rs::entities::Item;
 use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::primitives::Rect;
 use log::trace;
 use rand::Rng;
+use smallvec::SmallVec;
 use std::cmp::Ordering;
 use std::fmt::Debug;
 
@@ -18,6 +22,16 @@ pub struct CDConfig {
     pub r_step_limit: f32,
     /// Defines whether the wiggle axis (rotation) is enabled
     pub wiggle: bool,
+    /// If enabled, candidates are snapped to the closest allowed rotation of the item before evaluation.
+    /// Allows the wiggle axis to be used for items with a discrete set of rotations.
+    pub discrete_wiggle: bool,
+    /// Number of candidates generated along the active axis per step, symmetrically around the current position.
+    /// Odd values are rounded up to the next even number. The default of 2 only evaluates a full step on either side,
+    /// larger values additionally evaluate intermediate steps (e.g. 4 evaluates ±step/2 and ±step).
+    pub candidates_per_axis: usize,
+    /// Keep-in zone of the item, candidates are clamped into it before evaluation.
+    /// See [`convert_sample_to_closest_feasible`].
+    pub allowed_region: Option<Rect>,
 }
 
 /// Refines an initial 'sample' (transformation and evaluation) into a local minimum using a coordinate descent inspired algorithm.
@@ -25,6 +39,7 @@ pub fn refine_coord_desc(
     (init_dt, init_eval): (DTransformation, SampleEval),
     evaluator: &mut impl SampleEvaluator,
     cd_config: CDConfig,
+    item: &Item,
     rng: &mut impl Rng,
 ) -> (DTransformation, SampleEval) {
     let n_evals_init = evaluator.n_evals();
@@ -40,16 +55,19 @@ pub fn refine_coord_desc(
         r_step: cd_config.r_step_init,
         r_step_limit: cd_config.r_step_limit,
         wiggle: cd_config.wiggle,
+        candidates_per_side: usize::max(cd_config.candidates_per_axis.div_ceil(2), 1),
     };
 
     // From the CD state, ask for candidate positions to evaluate. If none provided, stop.
     while let Some(c) = cd.ask() {
-        // Evaluate the candidates using the evaluator.
-        let c_eval = c.map(|c| evaluator.evaluate_sample(c, Some(cd.eval)));
-
+        // Evaluate the candidates using the evaluator and keep the best one.
         let best = c
             .into_iter()
-            .zip(c_eval)
+            .map(|c| match cd_config.discrete_wiggle || cd_config.allowed_region.is_some() {
+                true => convert_sample_to_closest_feasible(c, item, cd_config.allowed_region),
+                false => c,
+            })
+            .map(|c| (c, evaluator.evaluate_sample(c, Some(cd.eval))))
             .min_by_key(|(_, eval)| *eval)
             .expect("At least one candidate should be present");
 
@@ -57,8 +75,9 @@ pub fn refine_coord_desc(
         cd.tell(best, rng);
         trace!("CD: {:?}", cd);
         debug_assert!(
-            evaluator.n_evals() - n_evals_init < 1000,
-            "coordinate descent exceeded 1000 evals"
+            evaluator.n_evals() - n_evals_init < 500 * cd.n_candidates(),
+            "coordinate descent exceeded {} evals",
+            500 * cd.n_candidates()
         );
     }
     trace!(
@@ -72,6 +91,9 @@ pub fn refine_coord_desc(
     (cd.pos, cd.eval)
 }
 
+/// Candidates generated by a single step of the coordinate descent, stored inline for up to 8 candidates per axis
+type CDCandidates = SmallVec<[DTransformation; 8]>;
+
 #[derive(Debug)]
 struct CoordinateDescent {
     /// The current position in the coordinate descent
@@ -90,11 +112,13 @@ struct CoordinateDescent {
     pub r_step_limit: f32,
     /// Defines whether the wiggle axis is enabled
     pub wiggle: bool,
+    /// Number of candidates generated on either side of the current position
+    pub candidates_per_side: usize,
 }
 
 impl CoordinateDescent {
     /// Generates candidates to be evaluated.
-    pub fn ask(&self) -> Option<[DTransformation; 2]> {
+    pub fn ask(&self) -> Option<CDCandidates> {
         let (sx, sy) = self.t_steps;
         let sr = self.r_step;
 
@@ -105,24 +129,37 @@ impl CoordinateDescent {
             // Stop generating candidates if both steps have reached the limit
             None
         } else {
-            // Generate two candidates on either side of the current position, according to the active axis.
+            // Generate candidates on either side of the current position, according to the active axis.
+            // The outermost candidates are a full step away, the others are evenly spaced in between.
             let (tx, ty) = self.pos.translation();
             let r = self.pos.rotation();
-            let transformations = match self.axis {
-                CDAxis::Horizontal => [(tx + sx, ty, r), (tx - sx, ty, r)],
-                CDAxis::Vertical => [(tx, ty + sy, r), (tx, ty - sy, r)],
-                CDAxis::ForwardDiag => [(tx + sx, ty + sy, r), (tx - sx, ty - sy, r)],
-                CDAxis::BackwardDiag => [(tx - sx, ty + sy, r), (tx + sx, ty - sy, r)],
-                CDAxis::Wiggle => [(tx, ty, r + sr), (tx, ty, r - sr)],
-            };
-
-            let c = transformations.map(|(tx, ty, r)| DTransformation::new(r, (tx, ty)));
+            let n = self.candidates_per_side;
+            let c = (1..=n)
+                .flat_map(|k| {
+                    let f = k as f32 / n as f32;
+                    let (sx, sy, sr) = (sx * f, sy * f, sr * f);
+                    match self.axis {
+                        CDAxis::Horizontal => [(tx + sx, ty, r), (tx - sx, ty, r)],
+                        CDAxis::Vertical => [(tx, ty + sy, r), (tx, ty - sy, r)],
+                        CDAxis::ForwardDiag => [(tx + sx, ty + sy, r), (tx - sx, ty - sy, r)],
+                        CDAxis::BackwardDiag => [(tx - sx, ty + sy, r), (tx + sx, ty - sy, r)],
+                        CDAxis::Wiggle => [(tx, ty, r + sr), (tx, ty, r - sr)],
+                    }
+                })
+                .map(|(tx, ty, r)| DTransformation::new(r, (tx, ty)))
+                .collect();
 
             Some(c)
         }
     }
 
-    /// Updates the coordinate descent state with the new position and evaluation.
+    /// Number of candidates generated per step
+    pub fn n_candidates(&self) -> usize {
+        2 * self.candidates_per_side
+    }
+
+    /// Updates the coordinate descent state with the best of the evaluated candidates.
+    /// The step size adapts the same way, regardless of how many candidates were evaluated.
     pub fn tell(&mut self, (pos, eval): (DTransformation, SampleEval), rng: &mut impl Rng) {
         // Check if the reported evaluation is better or worse than the current one.
         let eval_cmp = eval.cmp(&self.eval);
@@ -193,3 +230,88 @@ impl CDAxis {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::mock_evaluator::{MockEvaluator, quadratic_bowl};
+    use crate::util::fixtures::mixed_rects;
+    use jagua_rs::entities::Instance;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    const CENTER: (f32, f32) = (3.0, -2.0);
+
+    fn bowl_cd_config(candidates_per_axis: usize) -> CDConfig {
+        CDConfig {
+            t_step_init: 1.0,
+            t_step_limit: 1e-3,
+            r_step_init: 0.0,
+            r_step_limit: 0.0,
+            wiggle: false,
+            discrete_wiggle: false,
+            candidates_per_axis,
+            allowed_region: None,
+        }
+    }
+
+    fn descend_bowl(candidates_per_axis: usize, seed: u64) -> (DTransformation, SampleEval) {
+        let instance = mixed_rects();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let mut evaluator = MockEvaluator::new(quadratic_bowl(CENTER));
+        let init_dt = DTransformation::new(0.0, (0.0, 0.0));
+        let init_eval = evaluator.evaluate_sample(init_dt, None);
+        refine_coord_desc(
+            (init_dt, init_eval),
+            &mut evaluator,
+            bowl_cd_config(candidates_per_axis),
+            instance.item(0),
+            &mut rng,
+        )
+    }
+
+    fn distance_to_center(dt: DTransformation) -> f32 {
+        let (x, y) = dt.translation();
+        f32::sqrt((x - CENTER.0).powi(2) + (y - CENTER.1).powi(2))
+    }
+
+    #[test]
+    fn converges_to_bowl_minimum() {
+        for seed in 0..10 {
+            let (dt, eval) = descend_bowl(2, seed);
+            assert!(
+                distance_to_center(dt) < 0.05,
+                "seed {seed}: ended at {dt}, too far from the minimum"
+            );
+            assert!(matches!(eval, SampleEval::Collision { loss } if loss < 1.01));
+        }
+    }
+
+    #[test]
+    fn converges_to_bowl_minimum_with_more_candidates_per_axis() {
+        for candidates_per_axis in [4, 8] {
+            let (dt, _) = descend_bowl(candidates_per_axis, 0);
+            assert!(
+                distance_to_center(dt) < 0.05,
+                "{candidates_per_axis} candidates per axis: ended at {dt}, too far from the minimum"
+            );
+        }
+    }
+
+    #[test]
+    fn never_worsens_the_initial_eval() {
+        let mut evaluator = MockEvaluator::new(quadratic_bowl(CENTER));
+        let init_dt = DTransformation::new(0.0, CENTER);
+        let init_eval = evaluator.evaluate_sample(init_dt, None);
+        let instance = mixed_rects();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let (_, eval) = refine_coord_desc(
+            (init_dt, init_eval),
+            &mut evaluator,
+            bowl_cd_config(2),
+            instance.item(0),
+            &mut rng,
+        );
+        assert!(eval <= init_eval);
+    }
+}
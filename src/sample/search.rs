@@ -1,44 +1,121 @@
 use crate::consts::{
-    PRE_REFINE_CD_R_STEPS, PRE_REFINE_CD_TL_RATIOS, SND_REFINE_CD_R_STEPS, SND_REFINE_CD_TL_RATIOS,
-    UNIQUE_SAMPLE_THRESHOLD,
+    PRE_REFINE_CD_R_STEPS, PRE_REFINE_CD_TL_RATIOS, ROTATION_EQUALITY_TOLERANCE,
+    SND_REFINE_CD_R_STEPS, SND_REFINE_CD_TL_RATIOS,
 };
 use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
 use crate::sample::best_samples::BestSamples;
 use crate::sample::coord_descent::{CDConfig, refine_coord_desc};
-use crate::sample::uniform_sampler::UniformBBoxSampler;
+use crate::sample::uniform_sampler::{
+    RotatedBBoxCache, UniformBBoxSampler, adjacent_rotated_bboxes,
+    convert_sample_to_closest_feasible, rotated_bboxes, rotated_bboxes_around,
+};
+use anyhow::{Result, ensure};
+use itertools::Itertools;
 use jagua_rs::entities::{Item, Layout, PItemKey};
 use jagua_rs::geometry::DTransformation;
 use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::geometry::primitives::Rect;
 use log::debug;
+use ordered_float::OrderedFloat;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::iter;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct SampleConfig {
     pub n_container_samples: usize,
     pub n_focussed_samples: usize,
     pub n_coord_descents: usize,
+    /// Number of candidates evaluated per step of the coordinate descent, see [`CDConfig::candidates_per_axis`]
+    pub cd_candidates_per_axis: usize,
+    /// Number of evenly spaced rotations sampled for items which can be rotated continuously
+    pub n_continuous_rot_samples: usize,
+    /// Enables the rotation wiggle during coordinate descent for items with a discrete set of rotations,
+    /// snapping every wiggled rotation to the closest allowed one
+    pub discrete_wiggle: bool,
+    /// Seeds the search of an item with its best samples of the previous search (re-evaluated),
+    /// see the `warm_samples` of [`search_placement`]
+    pub reuse_best_samples: bool,
+    /// If set, the focussed samples around an item's current placement are biased toward its current rotation:
+    /// items with continuous rotation are only sampled within this spread (in radians) of their current rotation,
+    /// items with discrete rotations only in their current and adjacent rotations.
+    /// If `None`, focussed samples consider all allowed rotations.
+    pub focussed_rotation_spread: Option<f32>,
+    /// If set, the number of container and focussed samples is scaled down for items with more poles than this,
+    /// balancing the evaluation effort between simple and complex shapes. See [`SampleConfig::budgeted_for`]
+    pub pole_budget: Option<usize>,
+}
+
+impl SampleConfig {
+    /// Fails if no coordinate descents are requested, which leaves no room for the best samples of a search
+    pub fn validate(&self) -> Result<()> {
+        ensure!(self.n_coord_descents > 0, "at least one coordinate descent is required");
+        Ok(())
+    }
+
+    /// Returns the sample config to use when searching a placement for `item`.
+    /// With a [`SampleConfig::pole_budget`], the sample counts are scaled by `pole_budget / n_poles` (never upscaled),
+    /// keeping at least one sample of each kind that was requested.
+    pub fn budgeted_for(&self, item: &Item) -> SampleConfig {
+        let n_poles = item.shape_cd.surrogate().poles.len();
+        match self.pole_budget {
+            Some(budget) if n_poles > budget => {
+                let scale = |n: usize| match n {
+                    0 => 0,
+                    n => usize::max(1, n * budget / n_poles),
+                };
+                SampleConfig {
+                    n_container_samples: scale(self.n_container_samples),
+                    n_focussed_samples: scale(self.n_focussed_samples),
+                    ..*self
+                }
+            }
+            _ => *self,
+        }
+    }
 }
 
 /// Algorithm 6 and Figure 7 from https://doi.org/10.48550/arXiv.2509.13329
+/// If a `bbox_cache` is provided, the rotated bounding boxes of the item are taken from it (when present).
+/// The transformations of `warm_samples` (e.g. the best samples of a previous search) are evaluated before any other
+/// samples, their (stale) evaluations are ignored.
+/// If the item has an `allowed_region` (keep-in zone), all samples are restricted to it,
+/// the starting placement (`ref_pk`) and `warm_samples` are clamped into it.
+/// Returns the best sample, the number of evaluations and the best samples (and their metrics) before the final refine.
+#[allow(clippy::too_many_arguments)]
 pub fn search_placement(
     l: &Layout,
     item: &Item,
     ref_pk: Option<PItemKey>,
     mut evaluator: impl SampleEvaluator,
     sample_config: SampleConfig,
+    bbox_cache: Option<&RotatedBBoxCache>,
+    warm_samples: &[(DTransformation, SampleEval)],
+    allowed_region: Option<Rect>,
     rng: &mut impl Rng,
-) -> (Option<(DTransformation, SampleEval)>, usize) {
-    let item_min_dim = f32::min(item.shape_cd.bbox.width(), item.shape_cd.bbox.height());
+) -> (Option<(DTransformation, SampleEval)>, usize, BestSamples) {
+    let mut best_samples = BestSamples::for_item(sample_config.n_coord_descents, item);
 
-    let mut best_samples = BestSamples::new(
-        sample_config.n_coord_descents,
-        item_min_dim * UNIQUE_SAMPLE_THRESHOLD,
-    );
+    //the bounding boxes of the item for every rotation, shared by both samplers
+    let computed_bboxes;
+    let rot_bboxes = match bbox_cache.and_then(|c| c.get(item.id)) {
+        Some(cached) => cached,
+        None => {
+            computed_bboxes = rotated_bboxes(item, sample_config.n_continuous_rot_samples);
+            &computed_bboxes
+        }
+    };
 
     let focussed_sampler = match ref_pk {
         Some(ref_pk) => {
             //report the current placement (and eval)
             let dt = l.placed_items[ref_pk].d_transf;
+            let dt = match allowed_region {
+                //the item does not necessarily reside within its keep-in zone yet
+                Some(_) => convert_sample_to_closest_feasible(dt, item, allowed_region),
+                None => dt,
+            };
             let eval = evaluator.evaluate_sample(dt, Some(best_samples.upper_bound()));
 
             debug!("[S] Starting from: {:?}", (dt, eval));
@@ -46,11 +123,23 @@ pub fn search_placement(
 
             //create a sampler around the current placement
             let pi_bbox = l.placed_items[ref_pk].shape.bbox;
-            UniformBBoxSampler::new(pi_bbox, item, l.container.outer_cd.bbox)
+            let c_bbox = l.container.outer_cd.bbox;
+            let focussed = focussed_rotated_bboxes(item, dt.rotation(), rot_bboxes, &sample_config);
+            let rot_bboxes = focussed.as_deref().unwrap_or(rot_bboxes);
+            UniformBBoxSampler::from_rotated_bboxes(pi_bbox, rot_bboxes, c_bbox, allowed_region)
         }
         None => None,
     };
 
+    for (dt, _) in warm_samples {
+        let dt = match allowed_region {
+            Some(_) => convert_sample_to_closest_feasible(*dt, item, allowed_region),
+            None => *dt,
+        };
+        let eval = evaluator.evaluate_sample(dt, Some(best_samples.upper_bound()));
+        best_samples.report(dt, eval);
+    }
+
     if let Some(focussed_sampler) = focussed_sampler {
         for _ in 0..sample_config.n_focussed_samples {
             let dt = focussed_sampler.sample(rng);
@@ -59,8 +148,12 @@ pub fn search_placement(
         }
     }
 
-    let container_sampler =
-        UniformBBoxSampler::new(l.container.outer_cd.bbox, item, l.container.outer_cd.bbox);
+    let container_sampler = UniformBBoxSampler::from_rotated_bboxes(
+        l.container.outer_cd.bbox,
+        rot_bboxes,
+        l.container.outer_cd.bbox,
+        allowed_region,
+    );
 
     if let Some(container_sampler) = container_sampler {
         for _ in 0..sample_config.n_container_samples {
@@ -75,7 +168,8 @@ pub fn search_placement(
         let descended = refine_coord_desc(
             start.clone(),
             &mut evaluator,
-            prerefine_cd_config(item),
+            prerefine_cd_config(item, &sample_config, allowed_region),
+            item,
             rng,
         );
         best_samples.report(descended.0, descended.1);
@@ -84,36 +178,160 @@ pub fn search_placement(
     //Do a final refine on the best one
     let final_sample = best_samples
         .best()
-        .map(|s| refine_coord_desc(s, &mut evaluator, final_refine_cd_config(item), rng));
+        .map(|s| {
+            let cd_config = final_refine_cd_config(item, &sample_config, allowed_region);
+            refine_coord_desc(s, &mut evaluator, cd_config, item, rng)
+        });
 
     debug!(
         "[S] {} samples evaluated, final: {:?}",
         evaluator.n_evals(),
         final_sample
     );
-    (final_sample, evaluator.n_evals())
+    (final_sample, evaluator.n_evals(), best_samples)
 }
 
-fn prerefine_cd_config(item: &Item) -> CDConfig {
+/// The rotated bounding boxes considered by the focussed sampler, biased toward the current rotation `r`.
+/// Returns `None` if all rotations should be considered, see [`SampleConfig::focussed_rotation_spread`].
+fn focussed_rotated_bboxes(
+    item: &Item,
+    r: f32,
+    rot_bboxes: &[(f32, Rect)],
+    sample_config: &SampleConfig,
+) -> Option<Vec<(f32, Rect)>> {
+    let spread = sample_config.focussed_rotation_spread?;
+    match item.allowed_rotation {
+        RotationRange::None => None,
+        RotationRange::Discrete(_) => Some(adjacent_rotated_bboxes(rot_bboxes, r)),
+        RotationRange::Continuous => Some(rotated_bboxes_around(
+            item,
+            r,
+            spread,
+            sample_config.n_continuous_rot_samples,
+        )),
+    }
+}
+
+fn prerefine_cd_config(
+    item: &Item,
+    sample_config: &SampleConfig,
+    allowed_region: Option<Rect>,
+) -> CDConfig {
     let item_min_dim = f32::min(item.shape_cd.bbox.width(), item.shape_cd.bbox.height());
-    let wiggle = item.allowed_rotation == RotationRange::Continuous;
+    let discrete_wiggle = discrete_wiggle_r_steps(item, sample_config);
+    let wiggle = item.allowed_rotation == RotationRange::Continuous || discrete_wiggle.is_some();
+    let (r_step_init, r_step_limit) = discrete_wiggle.unwrap_or(PRE_REFINE_CD_R_STEPS);
     CDConfig {
         t_step_init: item_min_dim * PRE_REFINE_CD_TL_RATIOS.0,
         t_step_limit: item_min_dim * PRE_REFINE_CD_TL_RATIOS.1,
-        r_step_init: PRE_REFINE_CD_R_STEPS.0,
-        r_step_limit: PRE_REFINE_CD_R_STEPS.1,
+        r_step_init,
+        r_step_limit,
         wiggle,
+        discrete_wiggle: discrete_wiggle.is_some(),
+        candidates_per_axis: sample_config.cd_candidates_per_axis,
+        allowed_region,
     }
 }
 
-fn final_refine_cd_config(item: &Item) -> CDConfig {
+fn final_refine_cd_config(
+    item: &Item,
+    sample_config: &SampleConfig,
+    allowed_region: Option<Rect>,
+) -> CDConfig {
     let item_min_dim = f32::min(item.shape_cd.bbox.width(), item.shape_cd.bbox.height());
-    let wiggle = item.allowed_rotation == RotationRange::Continuous;
+    let discrete_wiggle = discrete_wiggle_r_steps(item, sample_config);
+    let wiggle = item.allowed_rotation == RotationRange::Continuous || discrete_wiggle.is_some();
+    let (r_step_init, r_step_limit) = discrete_wiggle.unwrap_or(SND_REFINE_CD_R_STEPS);
     CDConfig {
         t_step_init: item_min_dim * SND_REFINE_CD_TL_RATIOS.0,
         t_step_limit: item_min_dim * SND_REFINE_CD_TL_RATIOS.1,
-        r_step_init: SND_REFINE_CD_R_STEPS.0,
-        r_step_limit: SND_REFINE_CD_R_STEPS.1,
+        r_step_init,
+        r_step_limit,
         wiggle,
+        discrete_wiggle: discrete_wiggle.is_some(),
+        candidates_per_axis: sample_config.cd_candidates_per_axis,
+        allowed_region,
+    }
+}
+
+/// Rotation step sizes (init and limit) of the wiggle axis for items with multiple discrete rotations,
+/// if [`SampleConfig::discrete_wiggle`] is enabled.
+/// The wiggle starts with a half turn and stops once the step is too small to reach any other allowed rotation
+/// (half of the smallest gap between two allowed rotations).
+fn discrete_wiggle_r_steps(item: &Item, sample_config: &SampleConfig) -> Option<(f32, f32)> {
+    match &item.allowed_rotation {
+        RotationRange::Discrete(rotations) if sample_config.discrete_wiggle => {
+            let sorted = rotations
+                .iter()
+                .map(|r| r.rem_euclid(2.0 * PI))
+                .sorted_by_key(|r| OrderedFloat(*r))
+                .collect_vec();
+            let wrap_gap = sorted.first()? + 2.0 * PI - sorted.last()?;
+            let min_gap = sorted
+                .iter()
+                .tuple_windows()
+                .map(|(r1, r2)| r2 - r1)
+                .chain(iter::once(wrap_gap))
+                .filter(|gap| *gap > ROTATION_EQUALITY_TOLERANCE)
+                .min_by_key(|gap| OrderedFloat(*gap))?;
+            (min_gap < 2.0 * PI).then_some((PI, min_gap / 2.0))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::mock_evaluator::{MockEvaluator, quadratic_bowl};
+    use crate::util::fixtures::mixed_rects;
+    use jagua_rs::entities::Instance;
+    use jagua_rs::probs::spp::entities::SPProblem;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    #[test]
+    fn search_placement_finds_bowl_minimum() {
+        let instance = mixed_rects();
+        let prob = SPProblem::new(instance.clone());
+        let item = instance.item(1);
+        //place the minimum in the middle of the container, reachable by every sampler
+        let c_bbox = prob.layout.container.outer_cd.bbox;
+        let center = (
+            (c_bbox.x_min + c_bbox.x_max) / 2.0,
+            (c_bbox.y_min + c_bbox.y_max) / 2.0,
+        );
+        let sample_config = SampleConfig {
+            n_container_samples: 100,
+            n_focussed_samples: 0,
+            n_coord_descents: 3,
+            cd_candidates_per_axis: 2,
+            n_continuous_rot_samples: 16,
+            discrete_wiggle: false,
+            reuse_best_samples: false,
+            focussed_rotation_spread: None,
+            pole_budget: None,
+        };
+
+        for seed in 0..5 {
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+            let evaluator = MockEvaluator::new(quadratic_bowl(center));
+            let (best, n_evals, _) = search_placement(
+                &prob.layout,
+                item,
+                None,
+                evaluator,
+                sample_config,
+                None,
+                &[],
+                None,
+                &mut rng,
+            );
+            let (dt, _) = best.expect("a sample should have been found");
+            let (x, y) = dt.translation();
+            let distance = f32::sqrt((x - center.0).powi(2) + (y - center.1).powi(2));
+            assert!(distance < 0.05, "seed {seed}: ended at {dt}, {distance} from the minimum");
+            assert!(n_evals >= sample_config.n_container_samples);
+        }
     }
 }
@@ -1,19 +1,20 @@
+use crate::consts::ROTATION_EQUALITY_TOLERANCE;
 use itertools::Itertools;
 use jagua_rs::entities::Item;
 use jagua_rs::geometry::geo_enums::RotationRange;
 use jagua_rs::geometry::geo_traits::TransformableFrom;
 use jagua_rs::geometry::primitives::Rect;
 use jagua_rs::geometry::{DTransformation, Transformation, normalize_rotation};
+use jagua_rs::probs::spp::entities::SPInstance;
 use ordered_float::OrderedFloat;
 use rand::Rng;
 use rand::prelude::IndexedRandom;
 use std::f32::consts::PI;
 use std::ops::Range;
 
-const ROT_N_SAMPLES: usize = 16; // number of rotations to sample for continuous rotation
-
+/// `n` evenly spaced values in [start, end[ (the endpoint is excluded)
 fn linspace(start: f32, end: f32, n: usize) -> Vec<f32> {
-    let step = (end - start) / (n - 1) as f32;
+    let step = (end - start) / n as f32;
     (0..n).map(|i| start + i as f32 * step).collect()
 }
 
@@ -32,30 +33,40 @@ struct RotEntry {
 }
 
 impl UniformBBoxSampler {
-    pub fn new(sample_bbox: Rect, item: &Item, container_bbox: Rect) -> Option<Self> {
-        let rotations = match &item.allowed_rotation {
-            RotationRange::None => &vec![0.0],
-            RotationRange::Discrete(r) => r,
-            RotationRange::Continuous => {
-                // for continuous rotation, we sample a set of rotations spaced evenly
-                &linspace(0.0, 2.0 * PI, ROT_N_SAMPLES)
-            }
-        };
-
-        let mut shape_buffer = item.shape_cd.as_ref().clone();
+    /// For items with continuous rotation, `n_continuous_rot_samples` evenly spaced rotations are considered.
+    /// If the item has an `allowed_region` (keep-in zone), it is sampled such that it resides fully within it.
+    pub fn new(
+        sample_bbox: Rect,
+        item: &Item,
+        container_bbox: Rect,
+        n_continuous_rot_samples: usize,
+        allowed_region: Option<Rect>,
+    ) -> Option<Self> {
+        let rotated_bboxes = rotated_bboxes(item, n_continuous_rot_samples);
+        Self::from_rotated_bboxes(sample_bbox, &rotated_bboxes, container_bbox, allowed_region)
+    }
 
+    /// Same as [`UniformBBoxSampler::new`], but with the bounding boxes of the item for each rotation already computed.
+    /// See [`rotated_bboxes`] and [`RotatedBBoxCache`].
+    pub fn from_rotated_bboxes(
+        sample_bbox: Rect,
+        rotated_bboxes: &[(f32, Rect)],
+        container_bbox: Rect,
+        allowed_region: Option<Rect>,
+    ) -> Option<Self> {
+        //the item has to reside within both the container and its allowed region
+        let container_bbox = match allowed_region {
+            Some(region) => Rect::intersection(container_bbox, region)?,
+            None => container_bbox,
+        };
         let sample_x_range = sample_bbox.x_min..sample_bbox.x_max;
         let sample_y_range = sample_bbox.y_min..sample_bbox.y_max;
 
         // for each possible rotation, calculate the sample ranges (x and y)
         // where the item resides fully inside the container and is within the sample bounding box
-        let rot_entries = rotations
+        let rot_entries = rotated_bboxes
             .iter()
-            .map(|&r| {
-                let r_shape_bbox = shape_buffer
-                    .transform_from(item.shape_cd.as_ref(), &Transformation::from_rotation(r))
-                    .bbox;
-
+            .map(|&(r, r_shape_bbox)| {
                 //narrow the container range to account for the rotated shape
                 let cont_x_range = (container_bbox.x_min - r_shape_bbox.x_min)
                     ..(container_bbox.x_max - r_shape_bbox.x_max);
@@ -99,14 +110,116 @@ impl UniformBBoxSampler {
     }
 }
 
+/// The bounding box of the item's shape for each rotation considered by the sampler.
+/// For items with continuous rotation, `n_continuous_rot_samples` evenly spaced rotations are considered.
+pub fn rotated_bboxes(item: &Item, n_continuous_rot_samples: usize) -> Vec<(f32, Rect)> {
+    let rotations = match &item.allowed_rotation {
+        RotationRange::None => &vec![0.0],
+        RotationRange::Discrete(r) => r,
+        RotationRange::Continuous => {
+            // for continuous rotation, we sample a set of rotations spaced evenly
+            // (excluding 2π, which is the same rotation as 0)
+            &linspace(0.0, 2.0 * PI, n_continuous_rot_samples.max(1))
+        }
+    };
+    bboxes_for_rotations(item, rotations)
+}
+
+/// The bounding box of the item's shape for `n_rot_samples` evenly spaced rotations within `spread` of rotation `r`.
+/// Meant for items with continuous rotation, to sample around their current orientation.
+pub fn rotated_bboxes_around(
+    item: &Item,
+    r: f32,
+    spread: f32,
+    n_rot_samples: usize,
+) -> Vec<(f32, Rect)> {
+    //beyond a half turn, the range would wrap around onto itself
+    let spread = spread.clamp(0.0, PI);
+    let rotations = linspace(r - spread, r + spread, n_rot_samples.max(1));
+    bboxes_for_rotations(item, &rotations)
+}
+
+/// The entries of `rotated_bboxes` (of an item with a discrete set of rotations) adjacent to rotation `r`:
+/// the one closest to `r` and the closest ones on either side of it.
+pub fn adjacent_rotated_bboxes(rotated_bboxes: &[(f32, Rect)], r: f32) -> Vec<(f32, Rect)> {
+    //angular distance to r, normalized to [-PI, PI]
+    let delta = |(r_i, _): &&(f32, Rect)| normalize_rotation(r_i - r);
+
+    let current = rotated_bboxes
+        .iter()
+        .min_by_key(|e| OrderedFloat(delta(e).abs()));
+    let next = rotated_bboxes
+        .iter()
+        .filter(|e| delta(e) > ROTATION_EQUALITY_TOLERANCE)
+        .min_by_key(|e| OrderedFloat(delta(e)));
+    let prev = rotated_bboxes
+        .iter()
+        .filter(|e| delta(e) < -ROTATION_EQUALITY_TOLERANCE)
+        .max_by_key(|e| OrderedFloat(delta(e)));
+
+    [current, next, prev]
+        .into_iter()
+        .flatten()
+        .unique_by(|(r_i, _)| OrderedFloat(*r_i))
+        .copied()
+        .collect()
+}
+
+fn bboxes_for_rotations(item: &Item, rotations: &[f32]) -> Vec<(f32, Rect)> {
+    let mut shape_buffer = item.shape_cd.as_ref().clone();
+
+    rotations
+        .iter()
+        .map(|&r| {
+            let r_shape_bbox = shape_buffer
+                .transform_from(item.shape_cd.as_ref(), &Transformation::from_rotation(r))
+                .bbox;
+            (r, r_shape_bbox)
+        })
+        .collect()
+}
+
+/// Side table (indexed by item id) of the rotated bounding boxes of all items with a fixed set of rotations.
+/// These never change, so they can be computed once instead of every time a sampler is created.
+/// Items with continuous rotation are not cached, their sampled rotations depend on the [`crate::sample::search::SampleConfig`].
+#[derive(Clone, Debug, Default)]
+pub struct RotatedBBoxCache {
+    entries: Vec<Option<Vec<(f32, Rect)>>>,
+}
+
+impl RotatedBBoxCache {
+    pub fn new(instance: &SPInstance) -> Self {
+        let entries = instance
+            .items
+            .iter()
+            .map(|(item, _)| match item.allowed_rotation {
+                RotationRange::None | RotationRange::Discrete(_) => Some(rotated_bboxes(item, 0)),
+                RotationRange::Continuous => None,
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// The cached rotated bounding boxes of the item, if it has a fixed set of rotations
+    pub fn get(&self, item_id: usize) -> Option<&[(f32, Rect)]> {
+        self.entries.get(item_id)?.as_deref()
+    }
+}
+
 fn intersect_range(a: &Range<f32>, b: &Range<f32>) -> Range<f32> {
     let min = f32::max(a.start, b.start);
     let max = f32::min(a.end, b.end);
     min..max
 }
 
-/// Converts a sample transformation to the closest feasible transformation. (for now just mapping rotation to the closest allowed one)
-pub fn convert_sample_to_closest_feasible(dt: DTransformation, item: &Item) -> DTransformation {
+/// Converts a sample transformation to the closest feasible transformation:
+/// maps the rotation to the closest allowed one and, if the item has an `allowed_region` (keep-in zone),
+/// clamps the translation such that the item's bounding box resides within the region.
+pub fn convert_sample_to_closest_feasible(
+    dt: DTransformation,
+    item: &Item,
+    allowed_region: Option<Rect>,
+) -> DTransformation {
     let feasible_rotation = match &item.allowed_rotation {
         RotationRange::None => 0.0,
         RotationRange::Discrete(v) => {
@@ -125,5 +238,83 @@ pub fn convert_sample_to_closest_feasible(dt: DTransformation, item: &Item) -> D
             dt.rotation()
         }
     };
-    DTransformation::new(feasible_rotation, dt.translation())
+    let translation = match allowed_region {
+        Some(region) => {
+            clamp_translation_to_region(item, feasible_rotation, dt.translation(), region)
+        }
+        None => dt.translation(),
+    };
+    DTransformation::new(feasible_rotation, translation)
+}
+
+/// Clamps the translation of the item (at rotation `r`) such that its bounding box resides within `region`.
+/// Along an axis on which the item does not fit in the region, it is centered in the region instead.
+fn clamp_translation_to_region(
+    item: &Item,
+    r: f32,
+    (tx, ty): (f32, f32),
+    region: Rect,
+) -> (f32, f32) {
+    let (_, r_shape_bbox) = bboxes_for_rotations(item, &[r])[0];
+    let clamp = |t: f32, lower: f32, upper: f32| match lower <= upper {
+        true => t.clamp(lower, upper),
+        false => (lower + upper) / 2.0,
+    };
+    (
+        clamp(tx, region.x_min - r_shape_bbox.x_min, region.x_max - r_shape_bbox.x_max),
+        clamp(ty, region.y_min - r_shape_bbox.y_min, region.y_max - r_shape_bbox.y_max),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::mixed_rects;
+    use jagua_rs::entities::Instance;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    fn contains(region: Rect, bbox: Rect) -> bool {
+        let tol = 1e-3;
+        bbox.x_min >= region.x_min - tol
+            && bbox.y_min >= region.y_min - tol
+            && bbox.x_max <= region.x_max + tol
+            && bbox.y_max <= region.y_max + tol
+    }
+
+    #[test]
+    fn samples_stay_within_the_allowed_region() {
+        let instance = mixed_rects();
+        //the 3x6 rectangle, which can be rotated by 90°
+        let item = instance.item(3);
+        let container = Rect {
+            x_min: 0.0,
+            y_min: 0.0,
+            x_max: 20.0,
+            y_max: 10.0,
+        };
+        let left_half = Rect {
+            x_max: 10.0,
+            ..container
+        };
+        let sampler = UniformBBoxSampler::new(container, item, container, 0, Some(left_half))
+            .expect("the item fits in the left half");
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let shape = item.shape_cd.as_ref();
+        let mut placed = shape.clone();
+        for _ in 0..100 {
+            //samples from the uniform sampler
+            let dt = sampler.sample(&mut rng);
+            placed.transform_from(shape, &dt.compose());
+            assert!(contains(left_half, placed.bbox), "sample {dt} leaves the region");
+
+            //and arbitrary samples converted to the closest feasible one
+            let x = rng.random_range(-5.0..25.0);
+            let y = rng.random_range(-5.0..15.0);
+            let arbitrary = DTransformation::new(rng.random_range(0.0..2.0 * PI), (x, y));
+            let dt = convert_sample_to_closest_feasible(arbitrary, item, Some(left_half));
+            placed.transform_from(shape, &dt.compose());
+            assert!(contains(left_half, placed.bbox), "converted sample {dt} leaves the region");
+        }
+    }
 }
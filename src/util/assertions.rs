@@ -1,6 +1,8 @@
 use crate::eval::specialized_jaguars_pipeline::SpecializedHazardCollector;
 use crate::quantify::tracker::CollisionTracker;
-use crate::quantify::{quantify_collision_poly_container, quantify_collision_poly_poly};
+use crate::quantify::{
+    quantify_collision_poly_container_hazard, quantify_collision_poly_poly_bounded,
+};
 use float_cmp::{approx_eq, assert_approx_eq};
 use itertools::Itertools;
 use jagua_rs::collision_detection::hazards::HazardEntity;
@@ -29,8 +31,22 @@ pub fn tracker_matches_layout(ct: &CollisionTracker, l: &Layout) -> bool {
                 .any(|(_, he)| he == &HazardEntity::from((pk2, pi2)))
             {
                 true => {
-                    let calc_loss = quantify_collision_poly_poly(&pi1.shape, &pi2.shape);
-                    let calc_loss_r = quantify_collision_poly_poly(&pi2.shape, &pi1.shape);
+                    let penalty = ct.get_pair_penalty(pk1, pk2);
+                    let eps = ct.epsilon_ratio();
+                    let calc_loss = quantify_collision_poly_poly_bounded(
+                        &pi1.shape,
+                        &pi2.shape,
+                        penalty,
+                        eps,
+                        None,
+                    );
+                    let calc_loss_r = quantify_collision_poly_poly_bounded(
+                        &pi2.shape,
+                        &pi1.shape,
+                        penalty,
+                        eps,
+                        None,
+                    );
                     if !approx_eq!(f32, calc_loss, stored_loss, epsilon = 0.10 * stored_loss)
                         && !approx_eq!(f32, calc_loss_r, stored_loss, epsilon = 0.10 * stored_loss)
                     {
@@ -85,7 +101,14 @@ pub fn tracker_matches_layout(ct: &CollisionTracker, l: &Layout) -> bool {
                 }
                 false => {
                     if stored_loss != 0.0 {
-                        let calc_loss = quantify_collision_poly_poly(&pi1.shape, &pi2.shape);
+                        let penalty = ct.get_pair_penalty(pk1, pk2);
+                        let calc_loss = quantify_collision_poly_poly_bounded(
+                            &pi1.shape,
+                            &pi2.shape,
+                            penalty,
+                            ct.epsilon_ratio(),
+                            None,
+                        );
                         let mut opp_collector = BasicHazardCollector::new();
                         l.cde()
                             .collect_poly_collisions(&pi2.shape, &mut opp_collector);
@@ -118,10 +141,24 @@ pub fn tracker_matches_layout(ct: &CollisionTracker, l: &Layout) -> bool {
                 }
             }
         }
-        if collector.contains_entity(&HazardEntity::Exterior) {
+        let container_hazards = collector
+            .iter()
+            .filter(|(_, he)| !matches!(he, HazardEntity::PlacedItem { .. }))
+            .collect_vec();
+        if !container_hazards.is_empty() {
             let stored_loss = ct.get_container_loss(pk1);
-            let calc_loss =
-                quantify_collision_poly_container(&pi1.shape, l.container.outer_cd.bbox);
+            let calc_loss = container_hazards
+                .iter()
+                .map(|(hkey, he)| {
+                    quantify_collision_poly_container_hazard(
+                        &pi1.shape,
+                        he,
+                        &l.cde().hazards_map[*hkey].shape,
+                        ct.get_container_penalty(pk1),
+                        ct.epsilon_ratio(),
+                    )
+                })
+                .sum::<f32>();
             assert_approx_eq!(f32, stored_loss, calc_loss, ulps = 5);
         } else {
             assert_eq!(ct.get_container_loss(pk1), 0.0);
@@ -131,6 +168,44 @@ pub fn tracker_matches_layout(ct: &CollisionTracker, l: &Layout) -> bool {
     true
 }
 
+/// Compares the overlap proxy with the exact overlap for (at most) [`EXACT_OVERLAP_CHECK_N_PAIRS`] colliding pairs of the layout.
+/// The proxy is only an approximation, so it is only required to order two collisions the same way as the exact loss
+/// if their exact losses differ by more than a factor [`EXACT_OVERLAP_ORDER_RATIO`].
+#[cfg(feature = "exact_overlap")]
+pub fn proxy_ordering_matches_exact(l: &Layout) -> bool {
+    use crate::consts::{EXACT_OVERLAP_CHECK_N_PAIRS, EXACT_OVERLAP_ORDER_RATIO};
+    use crate::quantify::{quantify_collision_poly_poly, quantify_collision_poly_poly_exact};
+
+    let losses = l
+        .placed_items
+        .iter()
+        .tuple_combinations()
+        .filter(|((_, pi1), (pk2, pi2))| {
+            let mut collector = BasicHazardCollector::new();
+            l.cde().collect_poly_collisions(&pi1.shape, &mut collector);
+            collector.contains_entity(&HazardEntity::from((*pk2, *pi2)))
+        })
+        .map(|((_, pi1), (_, pi2))| {
+            let proxy = quantify_collision_poly_poly(&pi1.shape, &pi2.shape);
+            let exact = quantify_collision_poly_poly_exact(&pi1.shape, &pi2.shape);
+            (proxy, exact)
+        })
+        .filter(|(_, exact)| *exact > 0.0)
+        .take(EXACT_OVERLAP_CHECK_N_PAIRS)
+        .collect_vec();
+
+    for ((p1, e1), (p2, e2)) in losses.iter().tuple_combinations() {
+        if e1 / e2 > EXACT_OVERLAP_ORDER_RATIO || e2 / e1 > EXACT_OVERLAP_ORDER_RATIO {
+            assert_eq!(
+                p1 < p2,
+                e1 < e2,
+                "overlap proxy orders collisions differently than the exact overlap: proxy ({p1}, {p2}), exact ({e1}, {e2})"
+            );
+        }
+    }
+    true
+}
+
 pub fn custom_pipeline_matches_jaguars(shape: &SPolygon, det: &SpecializedHazardCollector) -> bool {
     //Standard colllision collection, provided by jagua-rs, for comparison
     let cde = det.layout.cde();
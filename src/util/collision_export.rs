@@ -0,0 +1,146 @@
+use crate::consts::DRAW_OPTIONS;
+use crate::quantify::tracker::CollisionTracker;
+use itertools::Itertools;
+use jagua_rs::io::svg::s_layout_to_svg;
+use jagua_rs::probs::spp::entities::{SPInstance, SPProblem, SPSolution};
+use serde::Serialize;
+use svg::Document;
+use svg::node::element::{Circle, Line};
+
+/// Overview of all remaining collisions in an (infeasible) solution
+#[derive(Debug, Clone, Serialize)]
+pub struct CollisionReport {
+    pub strip_width: f32,
+    pub total_loss: f32,
+    /// All colliding pairs of items
+    pub pairs: Vec<PairCollision>,
+    /// All items colliding with the exterior of the container
+    pub container: Vec<ContainerCollision>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PairCollision {
+    pub item_id_a: usize,
+    pub item_id_b: usize,
+    pub loss: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContainerCollision {
+    pub item_id: usize,
+    pub loss: f32,
+}
+
+/// Quantifies the collisions of a solution, to export them for analysis.
+pub struct CollisionExport<'a> {
+    solution: &'a SPSolution,
+    instance: &'a SPInstance,
+    prob: SPProblem,
+    ct: CollisionTracker,
+}
+
+impl<'a> CollisionExport<'a> {
+    pub fn new(solution: &'a SPSolution, instance: &'a SPInstance) -> Self {
+        let mut prob = SPProblem::new(instance.clone());
+        prob.change_strip_width(solution.strip_width());
+        prob.restore(solution);
+        let ct = CollisionTracker::new(&prob.layout);
+        Self {
+            solution,
+            instance,
+            prob,
+            ct,
+        }
+    }
+
+    pub fn report(&self) -> CollisionReport {
+        let layout = &self.prob.layout;
+        let pairs = layout
+            .placed_items
+            .keys()
+            .tuple_combinations()
+            .filter(|(pk1, pk2)| self.ct.get_pair_loss(*pk1, *pk2) > 0.0)
+            .map(|(pk1, pk2)| PairCollision {
+                item_id_a: layout.placed_items[pk1].item_id,
+                item_id_b: layout.placed_items[pk2].item_id,
+                loss: self.ct.get_pair_loss(pk1, pk2),
+            })
+            .collect_vec();
+        let container = layout
+            .placed_items
+            .iter()
+            .filter(|(pk, _)| self.ct.get_container_loss(*pk) > 0.0)
+            .map(|(pk, pi)| ContainerCollision {
+                item_id: pi.item_id,
+                loss: self.ct.get_container_loss(pk),
+            })
+            .collect_vec();
+
+        CollisionReport {
+            strip_width: self.solution.strip_width(),
+            total_loss: self.ct.get_total_loss(),
+            pairs,
+            container,
+        }
+    }
+
+    /// Draws the solution with all collisions highlighted on top of it.
+    /// Colliding pairs are connected by a line between their poles of inaccessibility,
+    /// items colliding with the container are marked with a circle.
+    /// The opacity of both is proportional to the loss of the collision.
+    pub fn to_svg(&self) -> Document {
+        let layout = &self.prob.layout;
+        let mut document = s_layout_to_svg(
+            &self.solution.layout_snapshot,
+            self.instance,
+            DRAW_OPTIONS,
+            "collisions",
+        );
+
+        let pair_losses = layout
+            .placed_items
+            .keys()
+            .tuple_combinations()
+            .map(|(pk1, pk2)| (pk1, pk2, self.ct.get_pair_loss(pk1, pk2)))
+            .filter(|(_, _, loss)| *loss > 0.0)
+            .collect_vec();
+        let container_losses = layout
+            .placed_items
+            .keys()
+            .map(|pk| (pk, self.ct.get_container_loss(pk)))
+            .filter(|(_, loss)| *loss > 0.0)
+            .collect_vec();
+
+        let max_loss = pair_losses
+            .iter()
+            .map(|(_, _, l)| *l)
+            .chain(container_losses.iter().map(|(_, l)| *l))
+            .fold(0.0, f32::max);
+        let stroke_width = layout.container.outer_cd.bbox.height() * 0.005;
+
+        for (pk1, pk2, loss) in pair_losses {
+            let c1 = layout.placed_items[pk1].shape.poi.center;
+            let c2 = layout.placed_items[pk2].shape.poi.center;
+            let line = Line::new()
+                .set("x1", c1.0)
+                .set("y1", c1.1)
+                .set("x2", c2.0)
+                .set("y2", c2.1)
+                .set("stroke", "red")
+                .set("stroke-width", stroke_width)
+                .set("stroke-opacity", loss / max_loss);
+            document = document.add(line);
+        }
+        for (pk, loss) in container_losses {
+            let poi = layout.placed_items[pk].shape.poi;
+            let circle = Circle::new()
+                .set("cx", poi.center.0)
+                .set("cy", poi.center.1)
+                .set("r", poi.radius)
+                .set("fill", "red")
+                .set("fill-opacity", loss / max_loss);
+            document = document.add(circle);
+        }
+        document
+    }
+}
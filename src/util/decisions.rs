@@ -0,0 +1,220 @@
+use crate::optimizer::separator::{Separator, SplitStrategy};
+use anyhow::{Context, Result, bail};
+use itertools::Itertools;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::probs::spp::entities::{SPPlacement, SPSolution};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::str::SplitWhitespace;
+
+/// High-level decision taken by the [`Separator`] which modified its state.
+#[derive(Debug, Clone)]
+pub enum Decision {
+    /// An item was removed and placed again with a new transformation
+    MoveItem {
+        item_id: usize,
+        from: DTransformation,
+        to: DTransformation,
+    },
+    /// The solution was disrupted by swapping two items (the moves themselves are recorded separately)
+    Disruption { item_id_1: usize, item_id_2: usize },
+    /// The strip width was changed, shifting all items right of the split position
+    StripWidth { width: f32, split_position: f32 },
+    /// The state of the worker with this index was selected as the new master state (its moves follow)
+    WorkerSelected(usize),
+    /// The state was restored to a solution with these placements
+    Restore {
+        placements: Vec<(usize, DTransformation)>,
+    },
+}
+
+impl Display for Decision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let fmt_dt = |dt: &DTransformation| {
+            format!(
+                "{} {} {}",
+                dt.rotation(),
+                dt.translation().0,
+                dt.translation().1
+            )
+        };
+        match self {
+            Decision::MoveItem { item_id, from, to } => {
+                write!(f, "M {} {} {}", item_id, fmt_dt(from), fmt_dt(to))
+            }
+            Decision::Disruption {
+                item_id_1,
+                item_id_2,
+            } => write!(f, "D {} {}", item_id_1, item_id_2),
+            Decision::StripWidth {
+                width,
+                split_position,
+            } => write!(f, "W {} {}", width, split_position),
+            Decision::WorkerSelected(idx) => write!(f, "S {}", idx),
+            Decision::Restore { placements } => {
+                write!(f, "R {}", placements.len())?;
+                for (item_id, dt) in placements {
+                    write!(f, " {} {}", item_id, fmt_dt(dt))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Decision {
+    /// Parses a decision from a line formatted by its [`Display`] implementation
+    pub fn parse(line: &str) -> Result<Self> {
+        let mut tokens = line.split_whitespace();
+        let decision = match tokens.next() {
+            Some("M") => Decision::MoveItem {
+                item_id: next_token(&mut tokens)?,
+                from: next_dt(&mut tokens)?,
+                to: next_dt(&mut tokens)?,
+            },
+            Some("D") => Decision::Disruption {
+                item_id_1: next_token(&mut tokens)?,
+                item_id_2: next_token(&mut tokens)?,
+            },
+            Some("W") => Decision::StripWidth {
+                width: next_token(&mut tokens)?,
+                split_position: next_token(&mut tokens)?,
+            },
+            Some("S") => Decision::WorkerSelected(next_token(&mut tokens)?),
+            Some("R") => {
+                let n: usize = next_token(&mut tokens)?;
+                let placements = (0..n)
+                    .map(|_| -> Result<(usize, DTransformation)> {
+                        Ok((next_token(&mut tokens)?, next_dt(&mut tokens)?))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Decision::Restore { placements }
+            }
+            other => bail!("unknown decision type: {:?}", other),
+        };
+        Ok(decision)
+    }
+}
+
+fn next_token<T: std::str::FromStr>(tokens: &mut SplitWhitespace) -> Result<T> {
+    tokens
+        .next()
+        .context("unexpected end of decision")?
+        .parse::<T>()
+        .ok()
+        .context("could not parse decision field")
+}
+
+fn next_dt(tokens: &mut SplitWhitespace) -> Result<DTransformation> {
+    let r = next_token(tokens)?;
+    let x = next_token(tokens)?;
+    let y = next_token(tokens)?;
+    Ok(DTransformation::new(r, (x, y)))
+}
+
+/// Records the decisions taken by a [`Separator`] (see [`Separator::enable_recording`]).
+#[derive(Debug, Clone, Default)]
+pub struct DecisionRecorder {
+    pub decisions: Vec<Decision>,
+}
+
+impl DecisionRecorder {
+    pub fn new() -> Self {
+        Self { decisions: vec![] }
+    }
+
+    pub fn record(&mut self, decision: Decision) {
+        self.decisions.push(decision);
+    }
+
+    pub fn record_restore(&mut self, sol: &SPSolution) {
+        let placements = sol
+            .layout_snapshot
+            .placed_items
+            .values()
+            .map(|pi| (pi.item_id, pi.d_transf))
+            .collect_vec();
+        self.record(Decision::Restore { placements });
+    }
+
+    /// Writes all recorded decisions to a file, one per line
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        let content = self.decisions.iter().map(|d| d.to_string()).join("\n");
+        fs::write(path, content).context("could not write decisions to file")
+    }
+}
+
+/// Re-executes a sequence of recorded decisions on a [`Separator`].
+/// Items are identified by their item id and transformation (not by their key), so the replay does not depend on
+/// the internal state of the layout.
+/// All moves go through [`Separator::move_item`], so in debug builds the consistency of the collision tracker
+/// is verified after every single decision.
+pub struct DecisionReplayer {
+    pub decisions: Vec<Decision>,
+}
+
+impl DecisionReplayer {
+    pub fn new(decisions: Vec<Decision>) -> Self {
+        Self { decisions }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).context("could not read decisions file")?;
+        let decisions = content
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| Decision::parse(l).with_context(|| format!("line {}", i + 1)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { decisions })
+    }
+
+    /// Replays all decisions on the separator, which should be in the same state as the recorded one at the start.
+    /// Recordings started by [`Separator::enable_recording`] begin by restoring the recorded state,
+    /// so any separator of the same instance (with the same locked items and keep-in zones) will do.
+    pub fn replay(&self, sep: &mut Separator) -> Result<()> {
+        for (i, decision) in self.decisions.iter().enumerate() {
+            match decision {
+                Decision::MoveItem { item_id, from, to } => {
+                    let pk = sep
+                        .prob
+                        .layout
+                        .placed_items
+                        .iter()
+                        .find(|(_, pi)| {
+                            pi.item_id == *item_id
+                                && pi.d_transf.rotation() == from.rotation()
+                                && pi.d_transf.translation() == from.translation()
+                        })
+                        .map(|(pk, _)| pk)
+                        .with_context(|| {
+                            format!("decision {i}: no item {item_id} placed at [{from}]")
+                        })?;
+                    sep.move_item(pk, *to);
+                }
+                Decision::StripWidth {
+                    width,
+                    split_position,
+                } => sep.change_strip_width(*width, SplitStrategy::Fixed(*split_position)),
+                Decision::Restore { placements } => {
+                    let pks = sep.prob.layout.placed_items.keys().collect_vec();
+                    for pk in pks {
+                        sep.prob.remove_item(pk);
+                    }
+                    for (item_id, d_transf) in placements {
+                        sep.prob.place_item(SPPlacement {
+                            item_id: *item_id,
+                            d_transf: *d_transf,
+                        });
+                    }
+                    sep.ct = sep.fresh_tracker();
+                }
+                Decision::Disruption { .. } | Decision::WorkerSelected(_) => {
+                    // purely informational, the resulting moves are recorded separately
+                }
+            }
+        }
+        Ok(())
+    }
+}
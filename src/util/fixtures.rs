@@ -0,0 +1,93 @@
+//! Small synthetic instances and configs for tests, which do not depend on any instance files.
+
+use crate::api::import_instance;
+use crate::config::{DEFAULT_SPARROW_CONFIG, SparrowConfig};
+use itertools::Itertools;
+use jagua_rs::probs::spp::entities::SPInstance;
+use jagua_rs::probs::spp::io::ext_repr::ExtSPInstance;
+use serde_json::json;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Instance of rectangles, each given as `(width, height, demand)`, in a strip of height `strip_height`.
+/// Every rectangle can be rotated by 90°.
+pub fn rect_ext_instance(strip_height: f32, rects: &[(f32, f32, usize)]) -> ExtSPInstance {
+    let items = rects
+        .iter()
+        .enumerate()
+        .map(|(id, &(w, h, demand))| {
+            json!({
+                "id": id,
+                "demand": demand,
+                "allowed_orientations": [0.0, 90.0],
+                "shape": {
+                    "type": "simple_polygon",
+                    "data": [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]
+                }
+            })
+        })
+        .collect_vec();
+    let instance = json!({
+        "name": "rects",
+        "strip_height": strip_height,
+        "items": items
+    });
+    serde_json::from_value(instance).expect("fixture should be a valid instance")
+}
+
+/// Imported [`rect_ext_instance`], with the default config
+pub fn rect_instance(strip_height: f32, rects: &[(f32, f32, usize)]) -> SPInstance {
+    import_instance(&rect_ext_instance(strip_height, rects), &DEFAULT_SPARROW_CONFIG)
+        .expect("fixture should import")
+}
+
+/// A dozen rectangles of mixed sizes in a strip of height 10, small enough to optimize in a fraction of a second
+pub fn mixed_rects() -> SPInstance {
+    rect_instance(10.0, &[(4.0, 3.0, 2), (2.0, 2.0, 4), (5.0, 1.0, 3), (3.0, 6.0, 1)])
+}
+
+/// Seeded default config with a single worker per phase and the given time limit per phase
+pub fn quick_config(time_limit: Duration) -> SparrowConfig {
+    let mut config = DEFAULT_SPARROW_CONFIG;
+    config.rng_seed = Some(0);
+    config.expl_cfg.time_limit = time_limit;
+    config.cmpr_cfg.time_limit = time_limit;
+    config.expl_cfg.separator_config.n_workers = 1;
+    config.cmpr_cfg.separator_config.n_workers = 1;
+    config
+}
+
+/// File in the temp directory, unique to the process and the call, so concurrent tests never share it.
+/// The file (if created) is removed when the path is dropped.
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    pub fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let unique_name = format!("sparrow_{}_{}_{}", std::process::id(), n, name);
+        Self(std::env::temp_dir().join(unique_name))
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
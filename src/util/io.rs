@@ -0,0 +1,453 @@
+use crate::EPOCH;
+use crate::api::import_instance;
+use crate::config::SparrowConfig;
+use crate::consts::{DEFAULT_LIVE_DIR, DEFAULT_OUTPUT_DIR, DEGENERATE_ITEM_AREA_RATIO};
+use crate::util::listener::{ReportType, SolutionListener};
+use crate::util::svg_exporter::SvgExporter;
+use anyhow::{Context, Result, bail};
+use itertools::Itertools;
+use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
+use jagua_rs::probs::spp::io::ext_repr::{ExtItem, ExtSPInstance};
+use log::{Level, debug, log, warn};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Paths of all artifacts written during and after an optimization run.
+/// Derived from an output directory and base name, every path can be overridden individually afterward.
+#[derive(Debug, Clone)]
+pub struct OutputPaths {
+    /// Path of the final solution in SVG format
+    pub final_svg: String,
+    /// Path of the final solution in JSON format
+    pub final_json: String,
+    /// Directory for the intermediate solutions
+    pub intermediate_dir: String,
+    /// Path of the continuously overwritten live solution
+    pub live_svg: String,
+}
+
+impl OutputPaths {
+    /// Default artifact paths for a base name (usually the instance name) within an output directory
+    pub fn new(output_dir: &str, name: &str) -> Self {
+        Self {
+            final_svg: format!("{output_dir}/final_{name}.svg"),
+            final_json: format!("{output_dir}/final_{name}.json"),
+            intermediate_dir: format!("{output_dir}/sols_{name}"),
+            live_svg: format!("{DEFAULT_LIVE_DIR}/.live_solution.svg"),
+        }
+    }
+
+    /// Default artifact paths within [`DEFAULT_OUTPUT_DIR`]
+    pub fn with_default_dir(name: &str) -> Self {
+        Self::new(DEFAULT_OUTPUT_DIR, name)
+    }
+
+    /// Creates an [`SvgExporter`] writing to these paths.
+    /// Intermediate and live solutions are only exported if requested.
+    pub fn svg_exporter(&self, intermediate: bool, live: bool) -> SvgExporter {
+        SvgExporter::new(
+            Some(self.final_svg.clone()),
+            intermediate.then(|| self.intermediate_dir.clone()),
+            live.then(|| self.live_svg.clone()),
+        )
+    }
+}
+
+pub fn write_json(json: &impl Serialize, path: &Path, log_lvl: Level) -> Result<()> {
+    //make sure the parent directory exists
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("could not create parent directory for json file")?;
+    }
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(file, json)?;
+    log!(
+        log_lvl,
+        "[IO] json exported to file://{}",
+        fs::canonicalize(path)
+            .expect("could not canonicalize path")
+            .to_str()
+            .context("could not convert path to str")?
+    );
+    Ok(())
+}
+
+pub fn read_spp_instance_json(path: &Path) -> Result<ExtSPInstance> {
+    let file = File::open(path).context("could not open instance file")?;
+    serde_json::from_reader(BufReader::new(file))
+        .context("not a valid strip packing instance (ExtSPInstance)")
+}
+
+/// Rejects instances containing degenerate items (zero area, collinear vertices, ...), see [`DEGENERATE_ITEM_AREA_RATIO`].
+/// The penalty of a collision is derived from the convex hull area of both shapes,
+/// which would be zero for degenerate items and break the ordering of the losses.
+pub fn validate_instance(instance: &SPInstance) -> Result<()> {
+    for (item, _) in instance.items.iter() {
+        let shape = item.shape_cd.as_ref();
+        let min_area = DEGENERATE_ITEM_AREA_RATIO * shape.diameter.powi(2);
+        let ch_area = shape.surrogate().convex_hull_area;
+        if !(shape.area > min_area && ch_area > min_area) {
+            bail!(
+                "item {} is degenerate (area: {}, convex hull area: {}), its shape should enclose a non-zero area",
+                item.id,
+                shape.area,
+                ch_area
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Reads the per-item minimum separation overrides from an instance file.
+/// These are an extension of the instance format: every item may contain an optional `"min_separation"` (number),
+/// overriding the global [`crate::config::SparrowConfig::min_item_separation`] for that item:
+/// ```json
+/// { "id": 3, "demand": 2, "min_separation": 5.0, "shape": { ... } }
+/// ```
+/// Returns the overrides by item id, see [`crate::quantify::separation_penalty_factors`].
+pub fn read_item_separations(path: &Path) -> Result<HashMap<usize, f32>> {
+    let file = File::open(path).context("could not open instance file")?;
+    let json: Value = serde_json::from_reader(BufReader::new(file)).context("not valid JSON")?;
+    let raw_items = json.get("items").and_then(Value::as_array);
+
+    let mut separations = HashMap::new();
+    for raw_item in raw_items.into_iter().flatten() {
+        let Some(separation) = raw_item.get("min_separation") else {
+            continue;
+        };
+        let id = raw_item
+            .get("id")
+            .and_then(Value::as_u64)
+            .context("item with 'min_separation' lacks a valid 'id'")?;
+        let separation = separation
+            .as_f64()
+            .filter(|s| *s >= 0.0)
+            .with_context(|| {
+                format!("'min_separation' of item {id} is not a non-negative number")
+            })?;
+        separations.insert(id as usize, separation as f32);
+    }
+    Ok(separations)
+}
+
+/// A problem encountered while leniently reading an instance, see [`read_spp_instance_json_lenient`].
+#[derive(Debug, Clone)]
+pub struct ParseWarning {
+    /// Index of the item in the `items` array, `None` if the problem is not related to a specific item
+    pub item_index: Option<usize>,
+    /// Name of the offending field, if it could be determined
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.item_index {
+            Some(idx) => write!(f, "item {idx}")?,
+            None => write!(f, "instance")?,
+        }
+        if let Some(field) = &self.field {
+            write!(f, ", field '{field}'")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Reads an instance, validating every item individually instead of failing on the first problem.
+/// Items which cannot be parsed or are invalid are skipped, all problems are collected and returned alongside
+/// the best-effort instance (containing all valid items).
+/// Only fails if the file cannot be read, is not valid JSON or lacks a valid strip height.
+pub fn read_spp_instance_json_lenient(path: &Path) -> Result<(ExtSPInstance, Vec<ParseWarning>)> {
+    let file = File::open(path).context("could not open instance file")?;
+    let json: Value = serde_json::from_reader(BufReader::new(file)).context("not valid JSON")?;
+    let mut warnings = vec![];
+
+    let name = match json.get("name").and_then(Value::as_str) {
+        Some(name) => name.to_string(),
+        None => {
+            warnings.push(ParseWarning {
+                item_index: None,
+                field: Some("name".to_string()),
+                message: "missing or not a string, using file name instead".to_string(),
+            });
+            path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unnamed")
+                .to_string()
+        }
+    };
+
+    let strip_height = match json.get("strip_height").and_then(Value::as_f64) {
+        Some(h) if h > 0.0 => h as f32,
+        _ => bail!("instance field 'strip_height' is missing or not a positive number"),
+    };
+
+    let raw_items = match json.get("items").and_then(Value::as_array) {
+        Some(items) => items.as_slice(),
+        None => {
+            warnings.push(ParseWarning {
+                item_index: None,
+                field: Some("items".to_string()),
+                message: "missing or not an array".to_string(),
+            });
+            &[]
+        }
+    };
+
+    let mut seen_ids = HashSet::new();
+    let items = raw_items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, raw_item)| {
+            let warning = |field: Option<&str>, message: String| ParseWarning {
+                item_index: Some(idx),
+                field: field.map(str::to_string),
+                message,
+            };
+            match serde_json::from_value::<ExtItem>(raw_item.clone()) {
+                Err(e) => {
+                    let message = e.to_string();
+                    warnings.push(warning(offending_field(&message), message.clone()));
+                    None
+                }
+                Ok(item) if item.demand == 0 => {
+                    warnings.push(warning(Some("demand"), "demand is zero".to_string()));
+                    None
+                }
+                Ok(item) if !seen_ids.insert(item.base.id) => {
+                    warnings.push(warning(Some("id"), format!("duplicate id {}", item.base.id)));
+                    None
+                }
+                Ok(item) => Some(item),
+            }
+        })
+        .collect_vec();
+
+    for w in warnings.iter() {
+        warn!("[IO] {w}");
+    }
+
+    let instance = ExtSPInstance {
+        name,
+        items,
+        strip_height,
+    };
+    Ok((instance, warnings))
+}
+
+/// Extracts the name of the field from serde error messages such as "missing field `shape`"
+fn offending_field(message: &str) -> Option<&str> {
+    message.split('`').nth(1)
+}
+
+/// Streams every feasible solution (exploration and compression) as an exported solution on a separate line (JSONL).
+/// Timestamps are relative to [`EPOCH`], consistent with the final output. The sink is flushed when the final solution is reported.
+pub struct FeasibleSolutionStream<W: Write> {
+    sink: W,
+}
+
+impl<W: Write> FeasibleSolutionStream<W> {
+    pub fn new(sink: W) -> Self {
+        Self { sink }
+    }
+}
+
+impl FeasibleSolutionStream<BufWriter<File>> {
+    /// Creates a stream writing to a (new or truncated) file
+    pub fn to_file(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("could not create parent directory for solution stream")?;
+        }
+        let file = File::create(path).context("could not create solution stream file")?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> SolutionListener for FeasibleSolutionStream<W> {
+    fn report(&mut self, report: ReportType, solution: &SPSolution, instance: &SPInstance) {
+        match report {
+            ReportType::ExplFeas | ReportType::CmprFeas => {
+                let ext_solution = jagua_rs::probs::spp::io::export(instance, solution, *EPOCH);
+                let result = serde_json::to_writer(&mut self.sink, &ext_solution)
+                    .map_err(std::io::Error::from)
+                    .and_then(|_| writeln!(self.sink));
+                if let Err(e) = result {
+                    warn!("[IO] failed to stream feasible solution: {e}");
+                }
+            }
+            ReportType::Final => {
+                if let Err(e) = self.sink.flush() {
+                    warn!("[IO] failed to flush solution stream: {e}");
+                }
+            }
+            ReportType::ExplInfeas | ReportType::ExplImproving => {}
+        }
+    }
+}
+
+/// Memoizes imported instances, keyed by the path of the instance file and a hash of the parts of the config
+/// which affect the import (see [`CachedImporter::config_hash`]). Avoids repeating the expensive parts of an import
+/// (surrogate generation, simplification, ...) in long-lived processes which solve the same instances repeatedly.
+/// An entry is invalidated when the instance file is modified.
+#[derive(Default)]
+pub struct CachedImporter {
+    cache: HashMap<(PathBuf, u64), CachedInstance>,
+    n_hits: usize,
+    n_misses: usize,
+}
+
+struct CachedInstance {
+    modified: Option<SystemTime>,
+    ext_instance: ExtSPInstance,
+    instance: SPInstance,
+}
+
+impl CachedImporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads and imports the instance at `path` (like [`crate::api::run_from_json`]), or returns the cached import.
+    pub fn import(
+        &mut self,
+        path: &Path,
+        config: &SparrowConfig,
+    ) -> Result<(&ExtSPInstance, &SPInstance)> {
+        let key = (path.to_path_buf(), Self::config_hash(config));
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let is_fresh = self
+            .cache
+            .get(&key)
+            .is_some_and(|c| c.modified.is_some() && c.modified == modified);
+
+        if is_fresh {
+            self.n_hits += 1;
+            debug!("[IO] cache hit for instance {}", path.display());
+        } else {
+            self.n_misses += 1;
+            let ext_instance = read_spp_instance_json(path)?;
+            let instance = import_instance(&ext_instance, config)?;
+            let cached = CachedInstance {
+                modified,
+                ext_instance,
+                instance,
+            };
+            self.cache.insert(key.clone(), cached);
+        }
+        let cached = &self.cache[&key];
+        Ok((&cached.ext_instance, &cached.instance))
+    }
+
+    /// Hash of the parts of the config which affect the import of an instance
+    pub fn config_hash(config: &SparrowConfig) -> u64 {
+        let import_config = (
+            config.cde_config,
+            config.poly_simpl_tolerance,
+            config.min_item_separation,
+            config.narrow_concavity_cutoff_ratio,
+            config.lock_rotations,
+        );
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(&import_config)
+            .expect("import config should be serializable")
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Number of imports served from the cache
+    pub fn n_hits(&self) -> usize {
+        self.n_hits
+    }
+
+    /// Number of imports which had to be performed
+    pub fn n_misses(&self) -> usize {
+        self.n_misses
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimizer::optimize;
+    use crate::api::import_instance;
+    use crate::config::DEFAULT_SPARROW_CONFIG;
+    use crate::util::fixtures::{TempPath, mixed_rects, quick_config, rect_ext_instance};
+    use crate::util::terminator::TimedTerminator;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use std::time::Duration;
+
+    #[test]
+    fn streams_at_least_the_initial_feasible_solution() {
+        let path = TempPath::new("feasible_stream.jsonl");
+        let instance = mixed_rects();
+        let config = quick_config(Duration::from_millis(300));
+        let mut stream = FeasibleSolutionStream::to_file(&path).unwrap();
+        let term = TimedTerminator::new_duration(Duration::from_secs(10));
+        let final_sol = optimize(
+            instance.clone(),
+            Xoshiro256PlusPlus::seed_from_u64(0),
+            &mut stream,
+            &term,
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+        );
+        drop(stream);
+
+        let lines = fs::read_to_string(&path).unwrap();
+        let solutions: Vec<Value> = lines
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("every line should be an exported solution"))
+            .collect();
+        assert!(!solutions.is_empty());
+        //every streamed solution is feasible, so none is narrower than the final one
+        let final_width = final_sol.strip_width() as f64;
+        assert!(solutions.iter().all(|s| {
+            s["strip_width"].as_f64().expect("exported solution should have a strip width")
+                >= final_width * (1.0 - 1e-4)
+        }));
+    }
+
+    #[test]
+    fn degenerate_items_are_rejected_with_their_id() {
+        assert!(validate_instance(&mixed_rects()).is_ok());
+
+        //a sliver without any meaningful area
+        let ext_instance = rect_ext_instance(10.0, &[(4.0, 3.0, 1), (10.0, 1e-6, 1)]);
+        let err = import_instance(&ext_instance, &DEFAULT_SPARROW_CONFIG).unwrap_err();
+        let msg = format!("{err:#}");
+        assert!(msg.contains("item 1 is degenerate"), "unexpected error: {msg}");
+    }
+
+    #[test]
+    fn second_import_of_the_same_file_is_a_cache_hit() {
+        let path = TempPath::new("cached_importer.json");
+        let ext_instance = rect_ext_instance(10.0, &[(4.0, 3.0, 2), (2.0, 2.0, 4)]);
+        fs::write(&path, serde_json::to_string(&ext_instance).unwrap()).unwrap();
+        let config = DEFAULT_SPARROW_CONFIG;
+
+        let mut importer = CachedImporter::new();
+        let n_items = importer.import(&path, &config).unwrap().1.items.len();
+        assert_eq!((importer.n_hits(), importer.n_misses()), (0, 1));
+        let cached_n_items = importer.import(&path, &config).unwrap().1.items.len();
+        assert_eq!(cached_n_items, n_items);
+        assert_eq!((importer.n_hits(), importer.n_misses()), (1, 1));
+
+        //a config which imports differently is not served from the cache
+        let mut other_config = config;
+        other_config.poly_simpl_tolerance = None;
+        importer.import(&path, &other_config).unwrap();
+        assert_eq!((importer.n_hits(), importer.n_misses()), (1, 2));
+    }
+}
@@ -1,11 +1,67 @@
+use crate::EPOCH;
+use crate::util::stability::StablePlacement;
 use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Trait for listeners that can receive solutions during the optimization process
 pub trait SolutionListener {
     fn report(&mut self, report: ReportType, solution: &SPSolution, instance: &SPInstance);
+
+    /// Same as [`SolutionListener::report`], but with additional context on the solution.
+    /// The optimizer always reports through this method, by default it delegates to `report`.
+    fn report_with_meta(
+        &mut self,
+        report: ReportType,
+        solution: &SPSolution,
+        instance: &SPInstance,
+        _meta: ReportMeta,
+    ) {
+        self.report(report, solution, instance)
+    }
+
+    /// Allows the listener to end the run based on the solutions it received.
+    /// Polled by the exploration and compression phases, after which the final solution is still reported.
+    fn control(&self) -> ListenerControl {
+        ListenerControl::Continue
+    }
+
+    /// Receives placements once they are considered stable, see [`crate::util::stability::StabilityTracker`].
+    /// Only emitted if enabled in the [`crate::optimizer::separator::SeparatorConfig`].
+    fn report_stable(&mut self, _placement: StablePlacement) {}
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Signal from a [`SolutionListener`] to the optimization process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerControl {
+    /// Continue the optimization process
+    Continue,
+    /// Stop the optimization process as soon as possible
+    Stop,
+}
+
+/// Context accompanying a reported solution
+#[derive(Debug, Clone, Copy)]
+pub struct ReportMeta {
+    /// Time elapsed since [`EPOCH`]
+    pub elapsed: Duration,
+    pub density: f32,
+    /// Total (unweighted) collision loss of the solution, 0.0 for feasible solutions
+    pub total_loss: f32,
+}
+
+impl ReportMeta {
+    pub fn new(solution: &SPSolution, instance: &SPInstance, total_loss: f32) -> Self {
+        Self {
+            elapsed: EPOCH.elapsed(),
+            density: solution.density(instance),
+            total_loss,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum ReportType {
     /// Report contains a feasible solution reached by the exploration phase.
     ExplFeas,
@@ -19,11 +75,240 @@ pub enum ReportType {
     Final,
 }
 
+/// Fans every report out to multiple listeners, in the order they were added.
+/// Requests to stop from any of the listeners are respected.
+#[derive(Default)]
+pub struct CompositeSolListener {
+    listeners: Vec<Box<dyn SolutionListener + Send + Sync>>,
+}
+
+impl CompositeSolListener {
+    pub fn new(listeners: Vec<Box<dyn SolutionListener + Send + Sync>>) -> Self {
+        Self { listeners }
+    }
+
+    pub fn add(mut self, listener: impl SolutionListener + Send + Sync + 'static) -> Self {
+        self.listeners.push(Box::new(listener));
+        self
+    }
+}
+
+impl SolutionListener for CompositeSolListener {
+    fn report(&mut self, report: ReportType, solution: &SPSolution, instance: &SPInstance) {
+        for listener in self.listeners.iter_mut() {
+            listener.report(report.clone(), solution, instance);
+        }
+    }
+
+    fn report_with_meta(
+        &mut self,
+        report: ReportType,
+        solution: &SPSolution,
+        instance: &SPInstance,
+        meta: ReportMeta,
+    ) {
+        for listener in self.listeners.iter_mut() {
+            listener.report_with_meta(report.clone(), solution, instance, meta);
+        }
+    }
+
+    fn control(&self) -> ListenerControl {
+        match self
+            .listeners
+            .iter()
+            .any(|l| l.control() == ListenerControl::Stop)
+        {
+            true => ListenerControl::Stop,
+            false => ListenerControl::Continue,
+        }
+    }
+
+    fn report_stable(&mut self, placement: StablePlacement) {
+        for listener in self.listeners.iter_mut() {
+            listener.report_stable(placement);
+        }
+    }
+}
+
 /// A dummy implementation of the `SolutionListener` trait that does nothing.
-pub struct NullSolListener;
+pub struct DummySolListener;
 
-impl SolutionListener for NullSolListener {
+/// Former name of [`DummySolListener`]
+#[deprecated(note = "renamed to `DummySolListener`")]
+pub type NullSolListener = DummySolListener;
+
+/// Former name of [`DummySolListener`], allows `NullSolListener` to still be used as a value
+#[deprecated(note = "renamed to `DummySolListener`")]
+#[allow(non_upper_case_globals)]
+pub const NullSolListener: DummySolListener = DummySolListener;
+
+impl SolutionListener for DummySolListener {
     fn report(&mut self, _report: ReportType, _solution: &SPSolution, _instance: &SPInstance) {
         // Do nothing
     }
 }
+
+/// Keeps track of the highest density among the feasible solutions it receives.
+/// Used by [`crate::util::terminator::DensityTerminator`].
+#[derive(Debug, Clone)]
+pub struct DensityListener {
+    density: Arc<Mutex<f32>>,
+}
+
+impl DensityListener {
+    pub fn new(density: Arc<Mutex<f32>>) -> Self {
+        Self { density }
+    }
+}
+
+impl SolutionListener for DensityListener {
+    fn report(&mut self, report: ReportType, solution: &SPSolution, instance: &SPInstance) {
+        match report {
+            ReportType::ExplFeas | ReportType::CmprFeas | ReportType::Final => {
+                let mut density = self.density.lock().expect("density mutex was poisoned");
+                *density = density.max(solution.density(instance));
+            }
+            ReportType::ExplInfeas | ReportType::ExplImproving => {}
+        }
+    }
+}
+
+/// Shared handle to the best feasible solution reported so far, see [`BestSolutionListener`]
+pub type BestSolutionSnapshot = Arc<Mutex<Option<SPSolution>>>;
+
+/// Forwards all reports to the inner listener, while keeping track of the narrowest feasible solution.
+/// The snapshot is updated continuously, so it remains available when an optimization is interrupted
+/// before returning (see [`crate::optimizer::OptimizeWorker::terminate_and_wait`]).
+pub struct BestSolutionListener<L: SolutionListener> {
+    inner: L,
+    best: BestSolutionSnapshot,
+}
+
+impl<L: SolutionListener> BestSolutionListener<L> {
+    pub fn new(inner: L) -> Self {
+        Self {
+            inner,
+            best: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Handle to the best feasible solution, shared with the listener
+    pub fn snapshot(&self) -> BestSolutionSnapshot {
+        self.best.clone()
+    }
+
+    fn track(&self, report: &ReportType, solution: &SPSolution) {
+        if matches!(
+            report,
+            ReportType::ExplFeas | ReportType::CmprFeas | ReportType::Final
+        ) {
+            let mut best = self.best.lock().expect("best solution mutex was poisoned");
+            if best
+                .as_ref()
+                .is_none_or(|b| solution.strip_width() <= b.strip_width())
+            {
+                *best = Some(solution.clone());
+            }
+        }
+    }
+}
+
+impl<L: SolutionListener> SolutionListener for BestSolutionListener<L> {
+    fn report(&mut self, report: ReportType, solution: &SPSolution, instance: &SPInstance) {
+        self.track(&report, solution);
+        self.inner.report(report, solution, instance);
+    }
+
+    fn report_with_meta(
+        &mut self,
+        report: ReportType,
+        solution: &SPSolution,
+        instance: &SPInstance,
+        meta: ReportMeta,
+    ) {
+        self.track(&report, solution);
+        self.inner.report_with_meta(report, solution, instance, meta);
+    }
+
+    fn control(&self) -> ListenerControl {
+        self.inner.control()
+    }
+
+    fn report_stable(&mut self, placement: StablePlacement) {
+        self.inner.report_stable(placement);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixtures::mixed_rects;
+    use jagua_rs::probs::spp::entities::SPProblem;
+
+    /// Logs every received report type to a shared log
+    struct CountingListener {
+        log: Arc<Mutex<Vec<ReportType>>>,
+        control: ListenerControl,
+    }
+
+    impl CountingListener {
+        fn new(control: ListenerControl) -> (Self, Arc<Mutex<Vec<ReportType>>>) {
+            let log = Arc::new(Mutex::new(vec![]));
+            (Self { log: log.clone(), control }, log)
+        }
+    }
+
+    impl SolutionListener for CountingListener {
+        fn report(&mut self, report: ReportType, _solution: &SPSolution, _instance: &SPInstance) {
+            self.log.lock().unwrap().push(report);
+        }
+
+        fn control(&self) -> ListenerControl {
+            self.control
+        }
+    }
+
+    #[test]
+    fn composite_fans_out_every_report() {
+        let instance = mixed_rects();
+        let solution = SPProblem::new(instance.clone()).save();
+        let (first, first_log) = CountingListener::new(ListenerControl::Continue);
+        let (second, second_log) = CountingListener::new(ListenerControl::Continue);
+        let mut composite = CompositeSolListener::new(vec![Box::new(first)]).add(second);
+
+        let reports = [ReportType::ExplFeas, ReportType::ExplImproving, ReportType::CmprFeas];
+        for report in reports.iter() {
+            composite.report(report.clone(), &solution, &instance);
+        }
+        let meta = ReportMeta::new(&solution, &instance, 0.0);
+        composite.report_with_meta(ReportType::Final, &solution, &instance, meta);
+
+        let expected = [reports.to_vec(), vec![ReportType::Final]].concat();
+        assert_eq!(*first_log.lock().unwrap(), expected);
+        assert_eq!(*second_log.lock().unwrap(), expected);
+        assert_eq!(composite.control(), ListenerControl::Continue);
+    }
+
+    #[test]
+    fn composite_stops_when_any_listener_does() {
+        let (running, _) = CountingListener::new(ListenerControl::Continue);
+        let (stopping, _) = CountingListener::new(ListenerControl::Stop);
+        let composite = CompositeSolListener::default().add(running).add(stopping);
+        assert_eq!(composite.control(), ListenerControl::Stop);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn dummy_listener_and_its_former_name_do_nothing() {
+        let instance = mixed_rects();
+        let solution = SPProblem::new(instance.clone()).save();
+        let mut dummy = DummySolListener;
+        let mut null: NullSolListener = NullSolListener;
+        for listener in [&mut dummy, &mut null] {
+            listener.report(ReportType::Final, &solution, &instance);
+            let meta = ReportMeta::new(&solution, &instance, 0.0);
+            listener.report_with_meta(ReportType::CmprFeas, &solution, &instance, meta);
+            assert_eq!(listener.control(), ListenerControl::Continue);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::consts::ROTATION_EQUALITY_TOLERANCE;
+use itertools::Itertools;
+use jagua_rs::entities::Instance;
+use jagua_rs::geometry::convex_hull::convex_hull_from_points;
+use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::geometry::normalize_rotation;
+use jagua_rs::geometry::primitives::{Point, Rect};
+use jagua_rs::probs::spp::entities::{SPInstance, SPProblem, SPSolution};
+use ordered_float::OrderedFloat;
+use std::f32::consts::PI;
+use std::fmt::{Display, Formatter};
+
+/// Lists all distinct rotations used by the placed items of a solution, along with the number of items placed with it.
+/// Rotations within [`ROTATION_EQUALITY_TOLERANCE`] of each other are considered the same orientation.
+/// Rotations are normalized to the range [0, 2π[ and the result is sorted by rotation.
+pub fn distinct_rotations(solution: &SPSolution) -> Vec<(f32, usize)> {
+    let mut rotations: Vec<(f32, usize)> = vec![];
+    for pi in solution.layout_snapshot.placed_items.values() {
+        let r = pi.d_transf.rotation().rem_euclid(2.0 * PI);
+        match rotations
+            .iter_mut()
+            .find(|(other_r, _)| rotations_are_equal(*other_r, r))
+        {
+            Some((_, count)) => *count += 1,
+            None => rotations.push((r, 1)),
+        }
+    }
+    rotations.sort_by_key(|(r, _)| OrderedFloat(*r));
+    rotations
+}
+
+/// Ratio of the total area of all placed items to the area of the convex hull enclosing all of them.
+/// Measures how tightly the items nest, independent of the strip width.
+pub fn nesting_efficiency(solution: &SPSolution, instance: &SPInstance) -> f32 {
+    let placed_items = &solution.layout_snapshot.placed_items;
+    let total_item_area = placed_items
+        .values()
+        .map(|pi| instance.item(pi.item_id).shape_cd.area)
+        .sum::<f32>();
+
+    let all_vertices = placed_items
+        .values()
+        .flat_map(|pi| pi.shape.vertices.iter().copied())
+        .collect_vec();
+    if all_vertices.len() < 3 {
+        return 0.0;
+    }
+    let hull = convex_hull_from_points(all_vertices);
+
+    let hull_area = polygon_area(&hull);
+    if hull_area > 0.0 {
+        total_item_area / hull_area
+    } else {
+        0.0
+    }
+}
+
+/// Area of a simple polygon using the shoelace formula
+fn polygon_area(points: &[Point]) -> f32 {
+    let twice_area = points
+        .iter()
+        .circular_tuple_windows()
+        .map(|(p1, p2)| p1.0 * p2.1 - p2.0 * p1.1)
+        .sum::<f32>();
+    (twice_area / 2.0).abs()
+}
+
+pub(crate) fn rotations_are_equal(r1: f32, r2: f32) -> bool {
+    // normalize the delta to the range [-PI, PI] to handle the wraparound at 2π
+    normalize_rotation(r1 - r2).abs() < ROTATION_EQUALITY_TOLERANCE
+}
+
+/// Lower bound on the strip width of any feasible solution for the instance.
+/// The strip is at least as wide as the total item area divided by the strip height,
+/// and at least as wide as the widest item which cannot be rotated.
+pub fn lower_bound_width(instance: &SPInstance) -> f32 {
+    let strip_height = SPProblem::new(instance.clone())
+        .layout
+        .container
+        .outer_cd
+        .bbox
+        .height();
+    let total_item_area = instance
+        .items
+        .iter()
+        .map(|(item, q)| item.shape_cd.area * *q as f32)
+        .sum::<f32>();
+    let widest_fixed_item = instance
+        .items
+        .iter()
+        .filter(|(item, _)| matches!(item.allowed_rotation, RotationRange::None))
+        .map(|(item, _)| item.shape_cd.bbox.width())
+        .fold(0.0, f32::max);
+
+    f32::max(total_item_area / strip_height, widest_fixed_item)
+}
+
+/// Statistics of the item areas of an instance, weighted by demand.
+/// Computed once after importing an instance, see [`crate::config::TimeSplitStrategy::ByDifficulty`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemSizeStats {
+    /// Total number of items (sum of all demands)
+    pub n_items: usize,
+    pub mean_area: f32,
+    /// Coefficient of variation (stddev / mean) of the item areas, 1.0 for an instance without items
+    pub area_cv: f32,
+}
+
+impl ItemSizeStats {
+    pub fn of(instance: &SPInstance) -> Self {
+        let n_items = instance.items.iter().map(|(_, q)| *q).sum::<usize>();
+        if n_items == 0 {
+            return Self {
+                n_items,
+                mean_area: 0.0,
+                area_cv: 1.0,
+            };
+        }
+        let mean_area = instance
+            .items
+            .iter()
+            .map(|(item, q)| item.shape_cd.area * *q as f32)
+            .sum::<f32>()
+            / n_items as f32;
+        let variance = instance
+            .items
+            .iter()
+            .map(|(item, q)| (item.shape_cd.area - mean_area).powi(2) * *q as f32)
+            .sum::<f32>()
+            / n_items as f32;
+        Self {
+            n_items,
+            mean_area,
+            area_cv: variance.sqrt() / mean_area,
+        }
+    }
+}
+
+/// Bounding box of all placed items, independent of the container. `None` if no items are placed.
+pub fn utilized_bbox(solution: &SPSolution) -> Option<Rect> {
+    solution
+        .layout_snapshot
+        .placed_items
+        .values()
+        .map(|pi| pi.shape.bbox)
+        .reduce(|a, b| Rect {
+            x_min: f32::min(a.x_min, b.x_min),
+            y_min: f32::min(a.y_min, b.y_min),
+            x_max: f32::max(a.x_max, b.x_max),
+            y_max: f32::max(a.y_max, b.y_max),
+        })
+}
+
+/// Unused space of the container (bottom, top) below and above the [`utilized_bbox`].
+pub fn vertical_margins(solution: &SPSolution) -> Option<(f32, f32)> {
+    let container_bbox = solution.layout_snapshot.container.outer_cd.bbox;
+    utilized_bbox(solution).map(|bbox| {
+        (
+            bbox.y_min - container_bbox.y_min,
+            container_bbox.y_max - bbox.y_max,
+        )
+    })
+}
+
+/// One-line summary of a solution for logging, e.g. `width: 12.345, dens: 67.890%, #items: 42`.
+/// The total loss is only shown if it is non-zero (infeasible solutions), see [`SolutionSummary::with_loss`].
+#[derive(Debug, Clone, Copy)]
+pub struct SolutionSummary {
+    pub width: f32,
+    pub density: f32,
+    pub n_items: usize,
+    /// Total (unweighted) collision loss of the solution, 0.0 for feasible solutions
+    pub total_loss: f32,
+}
+
+impl SolutionSummary {
+    pub fn of(solution: &SPSolution, instance: &SPInstance) -> Self {
+        Self {
+            width: solution.strip_width(),
+            density: solution.density(instance),
+            n_items: solution.layout_snapshot.placed_items.len(),
+            total_loss: 0.0,
+        }
+    }
+
+    pub fn with_loss(mut self, total_loss: f32) -> Self {
+        self.total_loss = total_loss;
+        self
+    }
+}
+
+impl Display for SolutionSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "width: {:.3}, dens: {:.3}%, #items: {}",
+            self.width,
+            self.density * 100.0,
+            self.n_items
+        )?;
+        if self.total_loss > 0.0 {
+            write!(f, ", loss: {:.3}", self.total_loss)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::LBF_SAMPLE_CONFIG;
+    use crate::optimizer::lbf::LBFBuilder;
+    use crate::util::fixtures::mixed_rects;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    #[test]
+    fn summary_contains_width_and_density() {
+        let instance = mixed_rects();
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let solution = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG)
+            .construct()
+            .prob
+            .save();
+        let summary = SolutionSummary::of(&solution, &instance);
+        let formatted = summary.to_string();
+
+        assert!(formatted.contains(&format!("width: {:.3}", solution.strip_width())));
+        assert!(formatted.contains(&format!("dens: {:.3}%", solution.density(&instance) * 100.0)));
+        assert!(formatted.contains(&format!("#items: {}", summary.n_items)));
+        assert!(!formatted.contains("loss"));
+        assert!(summary.with_loss(1.5).to_string().ends_with(", loss: 1.500"));
+    }
+}
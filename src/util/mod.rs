@@ -1,6 +1,17 @@
 pub mod assertions;
 
 pub mod bit_reversal_iterator;
+pub mod collision_export;
+pub mod decisions;
+#[cfg(any(test, feature = "testing"))]
+pub mod fixtures;
+pub mod io;
 pub mod listener;
+pub mod metrics;
+pub mod placement_export;
+pub mod progress;
+pub mod serde_adapters;
+pub mod stability;
+pub mod svg_animation;
 pub mod svg_exporter;
 pub mod terminator;
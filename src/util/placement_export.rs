@@ -0,0 +1,107 @@
+use itertools::Itertools;
+use jagua_rs::probs::spp::entities::SPSolution;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// Placement of a single item in a solution, as a flat record
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlacementRecord {
+    pub item_id: usize,
+    pub x: f32,
+    pub y: f32,
+    pub rotation_rad: f32,
+}
+
+/// Flattens all placements of a solution into records, ordered by item id.
+/// The translation and rotation are those of the placed item's transformation (rotation first, then translation).
+pub fn placement_records(solution: &SPSolution) -> Vec<PlacementRecord> {
+    solution
+        .layout_snapshot
+        .placed_items
+        .values()
+        .map(|pi| {
+            let (x, y) = pi.d_transf.translation();
+            PlacementRecord {
+                item_id: pi.item_id,
+                x,
+                y,
+                rotation_rad: pi.d_transf.rotation(),
+            }
+        })
+        .sorted_by_key(|r| r.item_id)
+        .collect_vec()
+}
+
+/// Writes the records as CSV (with header) to a sink
+pub fn write_placements_csv(
+    records: &[PlacementRecord],
+    sink: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(sink, "item_id,x,y,rotation_rad")?;
+    for r in records {
+        writeln!(sink, "{},{},{},{}", r.item_id, r.x, r.y, r.rotation_rad)?;
+    }
+    sink.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::LBF_SAMPLE_CONFIG;
+    use crate::optimizer::lbf::LBFBuilder;
+    use crate::util::fixtures::mixed_rects;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::probs::spp::entities::{SPPlacement, SPProblem};
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    fn parse_csv(csv: &str) -> Vec<PlacementRecord> {
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("item_id,x,y,rotation_rad"));
+        lines
+            .map(|line| {
+                let fields = line.split(',').collect_vec();
+                PlacementRecord {
+                    item_id: fields[0].parse().unwrap(),
+                    x: fields[1].parse().unwrap(),
+                    y: fields[2].parse().unwrap(),
+                    rotation_rad: fields[3].parse().unwrap(),
+                }
+            })
+            .collect()
+    }
+
+    fn sorted(records: Vec<PlacementRecord>) -> Vec<PlacementRecord> {
+        records
+            .into_iter()
+            .sorted_by(|a, b| {
+                (a.item_id, a.x, a.y).partial_cmp(&(b.item_id, b.x, b.y)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn records_round_trip_through_csv_and_back_into_a_solution() {
+        let instance = mixed_rects();
+        let rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let prob = LBFBuilder::new(instance.clone(), rng, LBF_SAMPLE_CONFIG).construct().prob;
+        let solution = prob.save();
+        let records = placement_records(&solution);
+        assert_eq!(records.len(), solution.layout_snapshot.placed_items.len());
+
+        let mut csv = vec![];
+        write_placements_csv(&records, &mut csv).unwrap();
+        let parsed = parse_csv(&String::from_utf8(csv).unwrap());
+        assert_eq!(parsed, records);
+
+        let mut rebuilt = SPProblem::new(instance);
+        rebuilt.change_strip_width(solution.strip_width());
+        for r in parsed.iter() {
+            rebuilt.place_item(SPPlacement {
+                item_id: r.item_id,
+                d_transf: DTransformation::new(r.rotation_rad, (r.x, r.y)),
+            });
+        }
+        assert_eq!(sorted(placement_records(&rebuilt.save())), sorted(records));
+    }
+}
@@ -0,0 +1,173 @@
+#[cfg(feature = "indicatif")]
+use crate::EPOCH;
+#[cfg(feature = "indicatif")]
+use crate::config::SparrowConfig;
+use crate::util::listener::{ReportMeta, ReportType, SolutionListener};
+use anyhow::{Context, Result};
+#[cfg(feature = "indicatif")]
+use indicatif::{ProgressBar, ProgressStyle};
+use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
+use log::warn;
+use serde::Serialize;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+#[cfg(feature = "indicatif")]
+use std::time::Duration;
+
+/// Logs every reported solution as a JSON record on a separate line (JSONL) to a sink.
+/// The sink is flushed when the final solution is reported.
+pub struct ProgressLogger<W: Write> {
+    sink: W,
+}
+
+#[derive(Serialize)]
+struct ProgressRecord {
+    report: ReportType,
+    elapsed_secs: f64,
+    strip_width: f32,
+    density: f32,
+    /// `None` if the loss was not provided by the reporter
+    total_loss: Option<f32>,
+}
+
+impl<W: Write> ProgressLogger<W> {
+    pub fn new(sink: W) -> Self {
+        Self { sink }
+    }
+
+    fn write_record(&mut self, record: &ProgressRecord) {
+        let result = serde_json::to_writer(&mut self.sink, record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writeln!(self.sink));
+        if let Err(e) = result {
+            warn!("[IO] failed to write progress record: {e}");
+        }
+    }
+}
+
+impl ProgressLogger<BufWriter<File>> {
+    /// Creates a logger writing to a (new or truncated) file
+    pub fn to_file(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("could not create parent directory for progress file")?;
+        }
+        let file = File::create(path).context("could not create progress file")?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> SolutionListener for ProgressLogger<W> {
+    fn report(&mut self, report: ReportType, solution: &SPSolution, instance: &SPInstance) {
+        let meta = ReportMeta::new(solution, instance, f32::NAN);
+        self.report_with_meta(report, solution, instance, meta);
+    }
+
+    fn report_with_meta(
+        &mut self,
+        report: ReportType,
+        solution: &SPSolution,
+        _instance: &SPInstance,
+        meta: ReportMeta,
+    ) {
+        let is_final = report == ReportType::Final;
+        let record = ProgressRecord {
+            report,
+            elapsed_secs: meta.elapsed.as_secs_f64(),
+            strip_width: solution.strip_width(),
+            density: meta.density,
+            total_loss: (!meta.total_loss.is_nan()).then_some(meta.total_loss),
+        };
+        self.write_record(&record);
+
+        if is_final && let Err(e) = self.sink.flush() {
+            warn!("[IO] failed to flush progress records: {e}");
+        }
+    }
+}
+
+/// Shows a terminal progress bar of the elapsed time (since [`EPOCH`]) relative to the total time budget,
+/// along with the current phase and the density of the last reported solution.
+/// The bar is drawn to stderr, next to the regular log output.
+#[cfg(feature = "indicatif")]
+pub struct ProgressBarListener {
+    bar: ProgressBar,
+}
+
+#[cfg(feature = "indicatif")]
+impl ProgressBarListener {
+    pub fn new(time_budget: Duration) -> Self {
+        let bar = ProgressBar::new(time_budget.as_millis() as u64);
+        let style = ProgressStyle::with_template("[{elapsed_precise}] {wide_bar} {eta:>4} | {msg}")
+            .expect("progress bar template should be valid");
+        bar.set_style(style);
+        bar.set_message("exploring");
+        Self { bar }
+    }
+
+    /// Progress bar for the combined time limit of the exploration and compression phase
+    pub fn from_config(config: &SparrowConfig) -> Self {
+        Self::new(config.expl_cfg.time_limit + config.cmpr_cfg.time_limit)
+    }
+}
+
+#[cfg(feature = "indicatif")]
+impl SolutionListener for ProgressBarListener {
+    fn report(&mut self, report: ReportType, solution: &SPSolution, instance: &SPInstance) {
+        let phase = match report {
+            ReportType::ExplFeas | ReportType::ExplInfeas | ReportType::ExplImproving => {
+                "exploring"
+            }
+            ReportType::CmprFeas => "compressing",
+            ReportType::Final => "finished",
+        };
+        let elapsed = EPOCH.elapsed().as_millis() as u64;
+        self.bar.set_position(elapsed.min(self.bar.length().unwrap_or(elapsed)));
+        self.bar.set_message(format!(
+            "{phase} | density: {:.3}%",
+            solution.density(instance) * 100.0
+        ));
+        if report == ReportType::Final {
+            self.bar.finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimizer::optimize;
+    use crate::util::fixtures::{TempPath, mixed_rects, quick_config};
+    use crate::util::terminator::TimedTerminator;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    #[test]
+    fn logs_feasible_exploration_records_to_file() {
+        let path = TempPath::new("progress_logger.jsonl");
+        let config = quick_config(Duration::from_millis(500));
+        let mut logger = ProgressLogger::to_file(&path).unwrap();
+        let term = TimedTerminator::new_duration(Duration::from_secs(10));
+        optimize(
+            mixed_rects(),
+            Xoshiro256PlusPlus::seed_from_u64(0),
+            &mut logger,
+            &term,
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+        );
+        drop(logger);
+
+        let records = fs::read_to_string(&path).unwrap();
+        let records: Vec<Value> = records
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("every line should be a JSON record"))
+            .collect();
+        assert!(records.iter().any(|r| r["report"] == "ExplFeas"));
+        assert_eq!(records.last().unwrap()["report"], "Final");
+        assert!(records.iter().all(|r| r["strip_width"].as_f64().unwrap() > 0.0));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::quantify::tracker::CTSnapshot;
+use itertools::Itertools;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::probs::spp::entities::SPSolution;
+
+/// A placement which does not change anymore during the remainder of the optimization.
+/// Stability is a heuristic judgement, but once reported the placement is pinned by the separator
+/// (see [`crate::optimizer::separator::Separator::pin_stable`]).
+#[derive(Debug, Clone, Copy)]
+pub struct StablePlacement {
+    pub item_id: usize,
+    pub d_transf: DTransformation,
+}
+
+/// Tracks for every placement how many consecutive separations it has not moved.
+/// Placements which did not move for `k` separations and are collision-free are considered stable.
+#[derive(Debug, Clone)]
+pub struct StabilityTracker {
+    pub k: usize,
+    entries: Vec<StabilityEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct StabilityEntry {
+    item_id: usize,
+    d_transf: DTransformation,
+    n_unmoved: usize,
+    emitted: bool,
+}
+
+impl StabilityTracker {
+    pub fn new(k: usize) -> Self {
+        Self { k, entries: vec![] }
+    }
+
+    /// Takes over the state of another tracker, so placements which were already emitted are not emitted again.
+    pub fn carry_over(&mut self, other: StabilityTracker) {
+        self.entries = other.entries;
+    }
+
+    /// Updates the move recency of all placements with the outcome of a separation.
+    /// Returns the placements which became stable since the last update, every placement is only returned once.
+    pub fn update(&mut self, sol: &SPSolution, ct: &CTSnapshot) -> Vec<StablePlacement> {
+        let mut prev_entries = std::mem::take(&mut self.entries);
+
+        self.entries = sol
+            .layout_snapshot
+            .placed_items
+            .iter()
+            .map(|(pk, pi)| {
+                //look for an identical placement of the same item in the previous separation
+                let prev = prev_entries
+                    .iter()
+                    .position(|e| {
+                        e.item_id == pi.item_id
+                            && e.d_transf.rotation() == pi.d_transf.rotation()
+                            && e.d_transf.translation() == pi.d_transf.translation()
+                    })
+                    .map(|idx| prev_entries.swap_remove(idx));
+
+                let collision_free = ct.get_loss(pk) == 0.0;
+                match prev {
+                    Some(e) if collision_free => StabilityEntry {
+                        n_unmoved: e.n_unmoved + 1,
+                        ..e
+                    },
+                    Some(e) => StabilityEntry { n_unmoved: 0, ..e },
+                    None => StabilityEntry {
+                        item_id: pi.item_id,
+                        d_transf: pi.d_transf,
+                        n_unmoved: 0,
+                        emitted: false,
+                    },
+                }
+            })
+            .collect_vec();
+
+        self.entries
+            .iter_mut()
+            .filter(|e| !e.emitted && e.n_unmoved >= self.k)
+            .map(|e| {
+                e.emitted = true;
+                StablePlacement {
+                    item_id: e.item_id,
+                    d_transf: e.d_transf,
+                }
+            })
+            .collect_vec()
+    }
+}
@@ -0,0 +1,124 @@
+use crate::consts::ANIMATION_FRAME_DURATION;
+use anyhow::{Context, Result};
+use jagua_rs::geometry::primitives::Rect;
+use log::{Level, log};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use svg::Document;
+
+/// Accumulates solutions as frames of a single animated SVG.
+/// At most `max_frames` evenly spaced frames are kept: whenever the limit is exceeded,
+/// every other frame is dropped and only half as many of the subsequent reports are recorded.
+#[derive(Debug, Clone)]
+pub struct SvgAnimation {
+    max_frames: usize,
+    /// Only every `stride`-th offered frame is recorded
+    stride: usize,
+    n_offered: usize,
+    /// Frames and the bounding box of the container they depict
+    frames: Vec<(Document, Rect)>,
+}
+
+impl SvgAnimation {
+    pub fn new(max_frames: usize) -> Self {
+        assert!(max_frames >= 2, "an animation requires at least 2 frames");
+        Self {
+            max_frames,
+            stride: 1,
+            n_offered: 0,
+            frames: vec![],
+        }
+    }
+
+    pub fn add_frame(&mut self, frame: Document, container_bbox: Rect) {
+        if self.n_offered % self.stride == 0 {
+            self.frames.push((frame, container_bbox));
+            if self.frames.len() > self.max_frames {
+                //thin out the frames, keeping them evenly spaced
+                let mut i = 0;
+                self.frames.retain(|_| {
+                    i += 1;
+                    i % 2 == 1
+                });
+                self.stride *= 2;
+            }
+        }
+        self.n_offered += 1;
+    }
+
+    pub fn n_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Stitches all frames into a single SVG, showing them in turn (looping), followed by `last_frame`.
+    pub fn render(&self, last_frame: (Document, Rect)) -> String {
+        let frames = self.frames.iter().cloned().chain([last_frame]).collect::<Vec<_>>();
+
+        //view box encompassing all frames (the strip narrows over time)
+        let (x_min, y_min, x_max, y_max) = frames.iter().fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(x_min, y_min, x_max, y_max), (_, bbox)| {
+                (
+                    x_min.min(bbox.x_min),
+                    y_min.min(bbox.y_min),
+                    x_max.max(bbox.x_max),
+                    y_max.max(bbox.y_max),
+                )
+            },
+        );
+        let n = frames.len();
+        let cycle = n as f32 * ANIMATION_FRAME_DURATION;
+
+        let mut out = String::new();
+        write!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+            x_min,
+            y_min,
+            x_max - x_min,
+            y_max - y_min
+        )
+        .unwrap();
+        //every frame is visible for an equal share of the cycle, offset by its index
+        write!(
+            out,
+            "<style>.frame{{visibility:hidden;animation:frame {cycle}s step-end infinite}}\
+             @keyframes frame{{0%{{visibility:visible}}{}%{{visibility:hidden}}}}</style>",
+            100.0 / n as f32
+        )
+        .unwrap();
+        for (i, (frame, bbox)) in frames.into_iter().enumerate() {
+            let frame = frame
+                .set("x", bbox.x_min)
+                .set("y", bbox.y_min)
+                .set("width", bbox.width())
+                .set("height", bbox.height())
+                .set("class", "frame")
+                .set(
+                    "style",
+                    format!("animation-delay:{}s", i as f32 * ANIMATION_FRAME_DURATION),
+                );
+            write!(out, "{frame}").unwrap();
+        }
+        out.push_str("</svg>");
+        out
+    }
+}
+
+pub fn write_animation(animation: &str, path: &Path, log_lvl: Level) -> Result<()> {
+    //make sure the parent directory exists
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("could not create parent directory for svg file")?;
+    }
+    fs::write(path, animation)?;
+    log!(
+        log_lvl,
+        "[IO] animated svg exported to file://{}",
+        fs::canonicalize(&path)
+            .expect("could not canonicalize path")
+            .to_str()
+            .context("could not convert path to str")?
+    );
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use crate::consts::DRAW_OPTIONS;
 use crate::util::listener::{ReportType, SolutionListener};
+use crate::util::svg_animation::{SvgAnimation, write_animation};
 use anyhow::{Context, Result};
-use jagua_rs::io::svg::s_layout_to_svg;
+use jagua_rs::io::svg::{SvgDrawOptions, s_layout_to_svg};
 use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
 use log::{Level, log};
 use svg::Document;
@@ -9,12 +10,18 @@ use std::fs;
 use std::path::Path;
 pub struct SvgExporter {
     svg_counter: usize,
+    /// If disabled, intermediate and live SVGs are not written (the final SVG is always written)
+    enabled: bool,
     /// Path to write the final SVG file to, if provided
     pub final_path: Option<String>,
     /// Directory to write all intermedia solution SVG files to, if provided
     pub intermediate_dir: Option<String>,
     /// Path to write the live SVG file to, if provided
     pub live_path: Option<String>,
+    /// Theme and overlays used to draw the solutions, [`DRAW_OPTIONS`] by default
+    pub draw_options: SvgDrawOptions,
+    /// Accumulates the solutions into a single animated SVG, written next to the final SVG, if enabled
+    pub animation: Option<SvgAnimation>,
 }
 
 impl SvgExporter {
@@ -36,11 +43,38 @@ impl SvgExporter {
 
         SvgExporter {
             svg_counter: 0,
+            enabled: true,
             final_path,
             intermediate_dir,
             live_path,
+            draw_options: DRAW_OPTIONS,
+            animation: None,
         }
     }
+
+    /// Additionally collects (at most `max_frames` evenly spaced) solutions into an animated SVG,
+    /// which is written as `<final stem>_animation.svg` next to the final SVG.
+    pub fn with_animation(mut self, max_frames: usize) -> Self {
+        self.animation = Some(SvgAnimation::new(max_frames));
+        self
+    }
+
+    /// Draws the solutions with the given options (e.g. a dark theme or quadtree/surrogate overlays)
+    /// instead of [`DRAW_OPTIONS`].
+    pub fn with_draw_options(mut self, draw_options: SvgDrawOptions) -> Self {
+        self.draw_options = draw_options;
+        self
+    }
+
+    /// Pauses or resumes writing intermediate and live SVGs, without affecting the final SVG.
+    /// Reports received while paused are still counted.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
 }
 
 pub fn write_svg(document: &Document, path: &Path, log_lvl: Level) -> Result<()> {
@@ -60,6 +94,41 @@ pub fn write_svg(document: &Document, path: &Path, log_lvl: Level) -> Result<()>
     Ok(())
 }
 
+/// Rasterizes an SVG document to a PNG file, `scale` being the number of pixels per unit of the SVG
+#[cfg(feature = "png")]
+pub fn write_png(document: &Document, path: &Path, scale: f32, log_lvl: Level) -> Result<()> {
+    use resvg::{tiny_skia, usvg};
+
+    //make sure the parent directory exists
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("could not create parent directory for png file")?;
+    }
+    let tree = usvg::Tree::from_str(&document.to_string(), &usvg::Options::default())
+        .context("could not parse svg document")?;
+    let size = tree
+        .size()
+        .to_int_size()
+        .scale_by(scale)
+        .context("invalid png dimensions")?;
+    let mut pixmap =
+        tiny_skia::Pixmap::new(size.width(), size.height()).context("could not allocate png")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.save_png(path).context("could not write png")?;
+    log!(
+        log_lvl,
+        "[IO] png exported to file://{}",
+        fs::canonicalize(&path)
+            .expect("could not canonicalize path")
+            .to_str()
+            .context("could not convert path to str")?
+    );
+    Ok(())
+}
+
 impl SolutionListener for SvgExporter {
     fn report(&mut self, report_type: ReportType, solution: &SPSolution, instance: &SPInstance) {
         let suffix = match report_type {
@@ -75,11 +144,13 @@ impl SolutionListener for SvgExporter {
             solution.strip_width(),
             suffix
         );
-        if let Some(live_path) = &self.live_path {
+        if let Some(live_path) = &self.live_path
+            && self.enabled
+        {
             let svg = s_layout_to_svg(
                 &solution.layout_snapshot,
                 instance,
-                DRAW_OPTIONS,
+                self.draw_options,
                 &file_name.as_str(),
             );
             write_svg(&svg, Path::new(live_path), Level::Trace)
@@ -87,17 +158,31 @@ impl SolutionListener for SvgExporter {
         }
         if let Some(intermediate_dir) = &self.intermediate_dir
             && report_type != ReportType::ExplImproving
+        {
+            if self.enabled {
+                let svg = s_layout_to_svg(
+                    &solution.layout_snapshot,
+                    instance,
+                    self.draw_options,
+                    file_name.as_str(),
+                );
+                let file_path = &*format!("{intermediate_dir}/{file_name}.svg");
+                write_svg(&svg, Path::new(file_path), Level::Trace)
+                    .expect("failed to write intermediate svg");
+            }
+            self.svg_counter += 1;
+        }
+        if let Some(animation) = &mut self.animation
+            && self.enabled
+            && !matches!(report_type, ReportType::ExplImproving | ReportType::Final)
         {
             let svg = s_layout_to_svg(
                 &solution.layout_snapshot,
                 instance,
-                DRAW_OPTIONS,
+                self.draw_options,
                 file_name.as_str(),
             );
-            let file_path = &*format!("{intermediate_dir}/{file_name}.svg");
-            write_svg(&svg, Path::new(file_path), Level::Trace)
-                .expect("failed to write intermediate svg");
-            self.svg_counter += 1;
+            animation.add_frame(svg, solution.layout_snapshot.container.outer_cd.bbox);
         }
         if let Some(final_path) = &self.final_path
             && report_type == ReportType::Final
@@ -106,11 +191,26 @@ impl SolutionListener for SvgExporter {
             let svg = s_layout_to_svg(
                 &solution.layout_snapshot,
                 instance,
-                DRAW_OPTIONS,
+                self.draw_options,
                 stem.to_str().unwrap(),
             );
             write_svg(&svg, Path::new(final_path), Level::Info)
                 .expect("failed to write final svg");
+            #[cfg(feature = "png")]
+            write_png(
+                &svg,
+                &Path::new(final_path).with_extension("png"),
+                crate::consts::PNG_SCALE,
+                Level::Info,
+            )
+            .expect("failed to write final png");
+            if let Some(animation) = &self.animation {
+                let anim_path = Path::new(final_path)
+                    .with_file_name(format!("{}_animation.svg", stem.to_str().unwrap()));
+                let anim = animation.render((svg, solution.layout_snapshot.container.outer_cd.bbox));
+                write_animation(&anim, &anim_path, Level::Info)
+                    .expect("failed to write animated svg");
+            }
         }
     }
 }
@@ -1,6 +1,7 @@
+use crate::util::listener::DensityListener;
 use jagua_rs::Instant;
 use std::{
-    sync::{Arc, atomic::AtomicBool},
+    sync::{Arc, Mutex, atomic::AtomicBool},
     time::Duration,
 };
 
@@ -8,6 +9,11 @@ use std::{
 pub trait Terminator: Clone {
     /// Checks if the termination condition is met
     fn should_terminate(&self) -> bool;
+
+    /// The moment at which the terminator will certainly terminate, if it has a deadline
+    fn timeout_at(&self) -> Option<Instant> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +37,74 @@ impl Terminator for TimedTerminator {
     fn should_terminate(&self) -> bool {
         Instant::now() > self.timeout
     }
+
+    fn timeout_at(&self) -> Option<Instant> {
+        Some(self.timeout)
+    }
+}
+
+/// Terminator with an optional deadline, which can also be tripped manually.
+/// Clones share the same flag, so tripping one of them terminates all of them.
+///
+/// ```
+/// use sparrow::util::terminator::{BasicTerminator, Terminator};
+/// use std::time::Duration;
+///
+/// let term = BasicTerminator::new_timeout(Duration::from_secs(60));
+/// assert!(term.timeout_at().is_some());
+/// assert!(!term.should_terminate());
+///
+/// // e.g. handed to a run on another thread, which stops once the original is tripped
+/// let clone = term.clone();
+/// term.trip();
+/// assert!(clone.should_terminate());
+///
+/// assert!(BasicTerminator::new().timeout_at().is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct BasicTerminator {
+    deadline: Option<Instant>,
+    flag: Arc<AtomicBool>,
+}
+
+impl BasicTerminator {
+    /// Creates a terminator without a deadline, it only terminates when tripped
+    pub fn new() -> Self {
+        Self {
+            deadline: None,
+            flag: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Creates a terminator which terminates once `timeout` has elapsed (or when tripped)
+    pub fn new_timeout(timeout: Duration) -> Self {
+        Self {
+            deadline: Some(Instant::now() + timeout),
+            ..Self::new()
+        }
+    }
+
+    /// Terminates all clones of this terminator
+    pub fn trip(&self) {
+        self.flag.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+impl Terminator for BasicTerminator {
+    fn should_terminate(&self) -> bool {
+        self.flag.load(std::sync::atomic::Ordering::Relaxed)
+            || self.deadline.is_some_and(|d| Instant::now() > d)
+    }
+
+    fn timeout_at(&self) -> Option<Instant> {
+        self.deadline
+    }
+}
+
+impl Default for BasicTerminator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -76,4 +150,108 @@ impl<T1: Terminator, T2: Terminator> Terminator for CombinedTerminator<T1, T2> {
     fn should_terminate(&self) -> bool {
         self.term1.should_terminate() || self.term2.should_terminate()
     }
-}
\ No newline at end of file
+
+    /// The earliest deadline of both terminators
+    fn timeout_at(&self) -> Option<Instant> {
+        match (self.term1.timeout_at(), self.term2.timeout_at()) {
+            (Some(t1), Some(t2)) => Some(t1.min(t2)),
+            (t1, t2) => t1.or(t2),
+        }
+    }
+}
+
+/// Terminates once the density of the best feasible solution reaches a target.
+/// The current density is kept up to date by the accompanying [`DensityListener`] (see [`DensityTerminator::listener`]),
+/// which should be (part of) the listener passed to [`crate::optimizer::optimize`].
+/// To also bound the runtime, combine it with a [`TimedTerminator`] using [`DensityTerminator::or`].
+#[derive(Debug, Clone)]
+pub struct DensityTerminator {
+    target: f32,
+    current: Arc<Mutex<f32>>,
+}
+
+impl DensityTerminator {
+    pub fn new(target: f32) -> Self {
+        Self {
+            target,
+            current: Arc::new(Mutex::new(0.0)),
+        }
+    }
+
+    /// Creates the listener which updates the current density of this terminator
+    pub fn listener(&self) -> DensityListener {
+        DensityListener::new(self.current.clone())
+    }
+
+    /// Combines the terminator with another one, terminating as soon as either does
+    pub fn or<T: Terminator>(self, other: T) -> CombinedTerminator<Self, T> {
+        CombinedTerminator::new(self, other)
+    }
+
+    pub fn current_density(&self) -> f32 {
+        *self.current.lock().expect("density mutex was poisoned")
+    }
+}
+
+impl Terminator for DensityTerminator {
+    fn should_terminate(&self) -> bool {
+        self.current_density() >= self.target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimizer::optimize;
+    use crate::util::fixtures::{mixed_rects, quick_config};
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    #[test]
+    fn density_target_ends_the_run_early() {
+        let time_limit = Duration::from_secs(30);
+        let config = quick_config(time_limit);
+        let density_term = DensityTerminator::new(0.2);
+        let mut listener = density_term.listener();
+        let term = density_term.clone().or(TimedTerminator::new_duration(2 * time_limit));
+
+        let start = Instant::now();
+        optimize(
+            mixed_rects(),
+            Xoshiro256PlusPlus::seed_from_u64(0),
+            &mut listener,
+            &term,
+            &config.expl_cfg,
+            &config.cmpr_cfg,
+        );
+        assert!(density_term.current_density() >= 0.2);
+        assert!(start.elapsed() < time_limit, "run should stop well before its time limit");
+    }
+
+    #[test]
+    fn combined_terminator_fires_on_either_timeout() {
+        let expired = TimedTerminator::new_duration(Duration::ZERO);
+        let pending = TimedTerminator::new_duration(Duration::from_secs(3600));
+        std::thread::sleep(Duration::from_millis(5));
+
+        for combined in [
+            CombinedTerminator::new(expired.clone(), pending.clone()),
+            CombinedTerminator::new(pending.clone(), expired.clone()),
+        ] {
+            assert!(combined.should_terminate());
+            assert_eq!(combined.timeout_at(), expired.timeout_at());
+        }
+        let neither = CombinedTerminator::new(pending.clone(), pending.clone());
+        assert!(!neither.should_terminate());
+    }
+
+    #[test]
+    fn combined_terminator_takes_the_only_deadline() {
+        let timed = TimedTerminator::new_duration(Duration::from_secs(60));
+        let flag = FlagTerminator::new();
+        let combined = CombinedTerminator::new(flag.clone(), timed.clone());
+        assert_eq!(combined.timeout_at(), timed.timeout_at());
+        assert!(!combined.should_terminate());
+        assert_eq!(CombinedTerminator::new(flag.clone(), flag).timeout_at(), None);
+    }
+}